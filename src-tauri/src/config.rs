@@ -0,0 +1,344 @@
+use crate::filter::{FilterConfig, WindowFilter};
+use crate::grouping::GroupBy;
+use crate::hotkeys::HotkeyAction;
+use crate::window::Scope;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri_plugin_global_shortcut::Shortcut;
+
+/// Written to the config dir on first launch so there is something to edit.
+const DEFAULT_CONFIG: &str = r#"# Binocular configuration, changes are picked up without restarting.
+
+# Size of the window icons in pixels at 100% scale, they are rendered sharper
+# on scaled displays.
+icon_size = 16
+
+# Start Binocular when you log in.
+autostart = false
+
+# Rank search results by the window titles you switched to as well as by
+# application, so a particular document or tab can rise to the top. The titles
+# are kept in frecency.json in the app data dir.
+remember_titles = false
+
+# How the apps picker puts windows together: "app" for every window of an
+# application, "process_tree" for every window of one running instance.
+group_by = "app"
+
+# Which workspaces the switcher starts out listing windows of: "all", or
+# "current" for the ones shown right now. Ctrl+D flips between them.
+workspace_scope = "all"
+
+# Which monitors the switcher starts out listing windows of: "all", or
+# "current" for the one it opens on. Ctrl+O flips between them.
+monitor_scope = "all"
+
+# Global shortcuts, e.g. "Ctrl+M" or "Super+Space", and what they do:
+#   "toggle_switcher"                 show or hide the switcher
+#   { open_picker = "windows" }       show the switcher with every window
+#   { open_picker = "same_app" }      show the switcher with the focused app's windows
+#   { open_picker = "apps" }          show the applications and how many windows they have
+#   { open_picker = "workspaces" }    show the workspaces and how many windows they have
+#   "focus_previous"                  switch to the previously focused window
+#   { run_command = ["alacritty"] }   run a program
+[hotkeys]
+"Ctrl+M" = "toggle_switcher"
+
+[filter]
+# Hide windows of these processes, e.g. ["explorer.exe"].
+exclude_processes = []
+# Hide windows whose title contains any of these, ignoring case.
+exclude_titles = []
+
+# Rules match on any of process, exe_path, class (window class or app id),
+# title (a regular expression) and window_type (normal, dialog, utility or
+# popup). Include rules win over exclude rules, which by default hide utility
+# windows, popups and untitled windows.
+#
+# [[filter.rules]]
+# action = "include"
+# process = "obs64.exe"
+# window_type = "popup"
+#
+# [[filter.rules]]
+# action = "exclude"
+# title = "^Picture-in-Picture$"
+"#;
+
+/// Icon sizes the config and the icon protocol accept, in pixels.
+pub const ICON_SIZES: std::ops::RangeInclusive<u32> = 8..=256;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Icon size in logical pixels, the webview asks for more on scaled displays.
+    pub icon_size: u32,
+    pub autostart: bool,
+    /// Whether the frecency store keeps window titles.
+    pub remember_titles: bool,
+    pub group_by: GroupBy,
+    pub workspace_scope: Scope,
+    pub monitor_scope: Scope,
+    /// Shortcut strings mapped to what they do.
+    pub hotkeys: BTreeMap<String, HotkeyAction>,
+    pub filter: FilterConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            icon_size: 16,
+            autostart: false,
+            remember_titles: false,
+            group_by: GroupBy::default(),
+            workspace_scope: Scope::default(),
+            monitor_scope: Scope::default(),
+            hotkeys: BTreeMap::from([("Ctrl+M".to_string(), HotkeyAction::ToggleSwitcher)]),
+            filter: FilterConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Parses and validates a config file, listing every problem found.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+        let mut errors = Vec::new();
+
+        // "Ctrl+M" and "control+m" are the same shortcut
+        let mut shortcuts: HashMap<Shortcut, &str> = HashMap::new();
+        for (name, action) in &config.hotkeys {
+            match name.parse::<Shortcut>() {
+                Ok(shortcut) => {
+                    if let Some(other) = shortcuts.insert(shortcut, name) {
+                        errors.push(format!(
+                            "hotkeys: {name:?} is the same shortcut as {other:?}"
+                        ));
+                    }
+                }
+                Err(e) => errors.push(format!("hotkeys: invalid shortcut {name:?}: {e}")),
+            }
+
+            if matches!(action, HotkeyAction::RunCommand(command) if command.is_empty()) {
+                errors.push(format!("hotkeys: {name:?} has an empty run_command"));
+            }
+        }
+
+        if !ICON_SIZES.contains(&config.icon_size) {
+            errors.push(format!(
+                "icon_size: must be between {} and {}, got {}",
+                ICON_SIZES.start(),
+                ICON_SIZES.end(),
+                config.icon_size
+            ));
+        }
+
+        if let Err(e) = WindowFilter::new(&config.filter) {
+            errors.push(e);
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+/// The current configuration along with the file it came from.
+///
+/// A config that fails to load leaves the previous one in place, the error is
+/// kept around until the file is fixed so the UI can show it.
+pub struct ConfigManager {
+    path: PathBuf,
+    config: Mutex<Config>,
+    error: Mutex<Option<String>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl ConfigManager {
+    /// Loads the config at `path`, writing the default one first if it doesn't exist.
+    pub fn load(path: PathBuf) -> Self {
+        if !path.exists() {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, DEFAULT_CONFIG));
+
+            if let Err(e) = written {
+                println!("Failed to write default config to {}: {e}", path.display());
+            }
+        }
+
+        let manager = Self {
+            path,
+            config: Mutex::new(Config::default()),
+            error: Mutex::new(None),
+            watcher: Mutex::new(None),
+        };
+
+        if let Err(e) = manager.reload() {
+            println!("{e}");
+        }
+
+        manager
+    }
+
+    pub fn get(&self) -> Config {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// The error from the last reload, if it failed.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Re-reads the config file, a missing file means the defaults.
+    pub fn reload(&self) -> Result<Config, String> {
+        let result = match fs::read_to_string(&self.path) {
+            Ok(contents) => Config::parse(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.to_string()),
+        }
+        .map_err(|e| format!("Error in {}:\n{e}", self.path.display()));
+
+        match &result {
+            Ok(config) => {
+                *self.config.lock().unwrap() = config.clone();
+                *self.error.lock().unwrap() = None;
+            }
+            Err(e) => *self.error.lock().unwrap() = Some(e.clone()),
+        }
+
+        result
+    }
+
+    /// Calls `on_change` whenever the config file is written, created or removed.
+    pub fn watch(&self, on_change: impl Fn() + Send + 'static) -> Result<(), String> {
+        let path = self.path.clone();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !event.kind.is_access() && event.paths.contains(&path) => on_change(),
+                Ok(_) => {}
+                Err(e) => println!("Error watching config: {e}"),
+            })
+            .map_err(|e| e.to_string())?;
+
+        // Editors often save by replacing the file, so watch the directory rather than the file
+        let dir = self
+            .path
+            .parent()
+            .ok_or("Config path has no parent directory")?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| e.to_string())?;
+
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static NEXT_DIR: AtomicU32 = AtomicU32::new(0);
+
+    /// A config path in a directory of its own that doesn't exist yet.
+    fn config_path() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "binocular-config-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("config.toml")
+    }
+
+    #[test]
+    fn default_config_parses_to_the_defaults() {
+        assert_eq!(Config::parse(DEFAULT_CONFIG), Ok(Config::default()));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let error = Config::parse("icon_szie = 24").unwrap_err();
+        assert!(error.contains("icon_szie"), "{error}");
+
+        let error = Config::parse("[filter]\nexclude = []").unwrap_err();
+        assert!(error.contains("exclude"), "{error}");
+    }
+
+    #[test]
+    fn every_problem_is_listed() {
+        let error = Config::parse(
+            r#"
+            icon_size = 4
+
+            [hotkeys]
+            "Ctrl+M" = "toggle_switcher"
+            "control+m" = "focus_previous"
+            "Super+T" = { run_command = [] }
+
+            [[filter.rules]]
+            action = "exclude"
+            title = "(unclosed"
+            "#,
+        )
+        .unwrap_err();
+        let errors: Vec<&str> = error.lines().collect();
+
+        // Hotkeys are checked in key order, uppercase first
+        assert!(errors.contains(&r#"hotkeys: "control+m" is the same shortcut as "Ctrl+M""#));
+        assert!(errors.contains(&r#"hotkeys: "Super+T" has an empty run_command"#));
+        assert!(errors.contains(&"icon_size: must be between 8 and 256, got 4"));
+        assert!(error.contains("(unclosed"), "{error}");
+    }
+
+    #[test]
+    fn invalid_shortcuts_are_rejected() {
+        let error = Config::parse("[hotkeys]\n\"Ctrl+Nope\" = \"toggle_switcher\"").unwrap_err();
+        assert!(
+            error.starts_with(r#"hotkeys: invalid shortcut "Ctrl+Nope""#),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn load_writes_the_default_config() {
+        let path = config_path();
+        let manager = ConfigManager::load(path.clone());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG);
+        assert_eq!(manager.get(), Config::default());
+        assert_eq!(manager.error(), None);
+    }
+
+    #[test]
+    fn a_broken_edit_keeps_the_previous_config() {
+        let path = config_path();
+        let manager = ConfigManager::load(path.clone());
+
+        fs::write(&path, "icon_size = 24").unwrap();
+        assert_eq!(manager.reload().unwrap().icon_size, 24);
+
+        fs::write(&path, "icon_size = 24\nautostart = maybe").unwrap();
+        let error = manager.reload().unwrap_err();
+        assert!(error.starts_with("Error in "), "{error}");
+        assert_eq!(manager.get().icon_size, 24);
+        assert_eq!(manager.error(), Some(error));
+
+        // Fixing it clears the error
+        fs::write(&path, "icon_size = 32").unwrap();
+        manager.reload().unwrap();
+        assert_eq!(manager.get().icon_size, 32);
+        assert_eq!(manager.error(), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! User-defined rules deciding which windows show up in the switcher.
+//!
+//! Backends list every visible top-level window, the rules here decide which
+//! of those are worth switching to. An include rule always wins over an
+//! exclude rule, so a rule can bring back a window the built-in rules hide.
+
+use crate::window::WindowType;
+use crate::window_manager::Window;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    Include,
+    Exclude,
+}
+
+/// Matches windows on every field that is set, fields left out match anything.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FilterRule {
+    pub action: RuleAction,
+    /// Process name, e.g. "firefox" or "explorer.exe", ignoring case.
+    pub process: Option<String>,
+    /// Full path of the executable, ignoring case.
+    pub exe_path: Option<String>,
+    /// Window class or app id, ignoring case.
+    pub class: Option<String>,
+    /// Regular expression searched for in the title, case-insensitive.
+    pub title: Option<String>,
+    pub window_type: Option<WindowType>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Process names to hide, shorthand for exclude rules.
+    pub exclude_processes: Vec<String>,
+    /// Title substrings to hide, shorthand for exclude rules.
+    pub exclude_titles: Vec<String>,
+    pub rules: Vec<FilterRule>,
+}
+
+#[derive(Clone)]
+struct CompiledRule {
+    action: RuleAction,
+    process: Option<String>,
+    exe_path: Option<String>,
+    class: Option<String>,
+    title: Option<Regex>,
+    window_type: Option<WindowType>,
+}
+
+impl CompiledRule {
+    fn new(rule: &FilterRule) -> Result<Self, String> {
+        let title = rule
+            .title
+            .as_deref()
+            .map(|title| {
+                RegexBuilder::new(title)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| format!("filter: invalid title regex {title:?}: {e}"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            action: rule.action,
+            process: rule.process.clone(),
+            exe_path: rule.exe_path.clone(),
+            class: rule.class.clone(),
+            title,
+            window_type: rule.window_type,
+        })
+    }
+
+    fn matches(&self, window: &Window) -> bool {
+        let matches_text = |expected: &Option<String>, actual: Option<&str>| match expected {
+            Some(expected) => actual.is_some_and(|actual| actual.eq_ignore_ascii_case(expected)),
+            None => true,
+        };
+
+        let class_matches = self.class.is_none()
+            || matches_text(&self.class, window.class_name.as_deref())
+            || matches_text(&self.class, window.app_id.as_deref());
+
+        matches_text(&self.process, window.process_name.as_deref())
+            && matches_text(&self.exe_path, window.exe_path.as_deref())
+            && class_matches
+            && match &self.title {
+                Some(title) => title.is_match(&window.title),
+                None => true,
+            }
+            && match self.window_type {
+                Some(window_type) => window_type == window.window_type,
+                None => true,
+            }
+    }
+}
+
+/// The configured rules, compiled and ready to match against windows.
+#[derive(Clone)]
+pub struct WindowFilter {
+    rules: Vec<CompiledRule>,
+}
+
+impl Default for WindowFilter {
+    fn default() -> Self {
+        Self::new(&FilterConfig::default()).unwrap()
+    }
+}
+
+impl WindowFilter {
+    /// Compiles the rules in `config`, failing on the first invalid one.
+    pub fn new(config: &FilterConfig) -> Result<Self, String> {
+        let shorthand = config
+            .exclude_processes
+            .iter()
+            .map(|process| FilterRule {
+                process: Some(process.clone()),
+                ..exclude()
+            })
+            .chain(config.exclude_titles.iter().map(|title| FilterRule {
+                title: Some(regex::escape(title)),
+                ..exclude()
+            }));
+
+        let rules = config
+            .rules
+            .iter()
+            .cloned()
+            .chain(shorthand)
+            .chain(builtin_rules())
+            .map(|rule| CompiledRule::new(&rule))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Returns true if `window` should be listed.
+    ///
+    /// Windows matching any include rule are listed, otherwise windows
+    /// matching any exclude rule are hidden and everything else is listed.
+    pub fn allows(&self, window: &Window) -> bool {
+        let matching = |action| {
+            self.rules
+                .iter()
+                .any(|rule| rule.action == action && rule.matches(window))
+        };
+
+        matching(RuleAction::Include) || !matching(RuleAction::Exclude)
+    }
+}
+
+fn exclude() -> FilterRule {
+    FilterRule {
+        action: RuleAction::Exclude,
+        process: None,
+        exe_path: None,
+        class: None,
+        title: None,
+        window_type: None,
+    }
+}
+
+/// Hides what nobody switches to: tool windows, popups and untitled windows.
+fn builtin_rules() -> [FilterRule; 3] {
+    [
+        FilterRule {
+            window_type: Some(WindowType::Utility),
+            ..exclude()
+        },
+        FilterRule {
+            window_type: Some(WindowType::Popup),
+            ..exclude()
+        },
+        FilterRule {
+            title: Some("^$".to_string()),
+            ..exclude()
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(process_name: &str, title: &str) -> Window {
+        Window {
+            title: title.to_string(),
+            process_name: Some(process_name.to_string()),
+            exe_path: Some(format!("/usr/bin/{process_name}")),
+            class_name: Some(process_name.to_uppercase()),
+            ..Default::default()
+        }
+    }
+
+    fn rule(action: RuleAction) -> FilterRule {
+        FilterRule {
+            action,
+            ..exclude()
+        }
+    }
+
+    fn filter(config: FilterConfig) -> WindowFilter {
+        WindowFilter::new(&config).unwrap()
+    }
+
+    #[test]
+    fn builtin_rules_hide_utility_popup_and_untitled_windows() {
+        let filter = WindowFilter::default();
+
+        assert!(filter.allows(&window("gimp", "GNU Image Manipulation Program")));
+        assert!(filter.allows(&Window {
+            window_type: WindowType::Dialog,
+            ..window("gimp", "Export Image")
+        }));
+        assert!(!filter.allows(&Window {
+            window_type: WindowType::Utility,
+            ..window("gimp", "Toolbox")
+        }));
+        assert!(!filter.allows(&Window {
+            window_type: WindowType::Popup,
+            ..window("gimp", "Splash")
+        }));
+        assert!(!filter.allows(&window("gimp", "")));
+    }
+
+    #[test]
+    fn rules_match_every_field_that_is_set() {
+        let filter = filter(FilterConfig {
+            rules: vec![FilterRule {
+                process: Some("Firefox".to_string()),
+                title: Some("private browsing$".to_string()),
+                ..rule(RuleAction::Exclude)
+            }],
+            ..Default::default()
+        });
+
+        assert!(!filter.allows(&window("firefox", "Mozilla Firefox Private Browsing")));
+        assert!(filter.allows(&window("firefox", "Mozilla Firefox")));
+        assert!(filter.allows(&window("chromium", "Private Browsing")));
+    }
+
+    #[test]
+    fn class_matches_class_name_or_app_id() {
+        let filter = filter(FilterConfig {
+            rules: vec![FilterRule {
+                class: Some("org.gnome.Nautilus".to_string()),
+                ..rule(RuleAction::Exclude)
+            }],
+            ..Default::default()
+        });
+
+        assert!(!filter.allows(&Window {
+            app_id: Some("org.gnome.Nautilus".to_string()),
+            ..window("nautilus", "Home")
+        }));
+        assert!(!filter.allows(&Window {
+            class_name: Some("org.gnome.nautilus".to_string()),
+            ..window("nautilus", "Home")
+        }));
+        assert!(filter.allows(&window("nautilus", "Home")));
+    }
+
+    #[test]
+    fn include_wins_over_exclude() {
+        let filter = filter(FilterConfig {
+            exclude_processes: vec!["steam".to_string()],
+            rules: vec![
+                FilterRule {
+                    process: Some("steam".to_string()),
+                    title: Some("^Steam$".to_string()),
+                    ..rule(RuleAction::Include)
+                },
+                FilterRule {
+                    window_type: Some(WindowType::Utility),
+                    class: Some("GIMP".to_string()),
+                    ..rule(RuleAction::Include)
+                },
+            ],
+            ..Default::default()
+        });
+
+        assert!(filter.allows(&window("steam", "Steam")));
+        assert!(!filter.allows(&window("steam", "Friends List")));
+        // Brings back what a built-in rule hides
+        assert!(filter.allows(&Window {
+            window_type: WindowType::Utility,
+            ..window("gimp", "Toolbox")
+        }));
+    }
+
+    #[test]
+    fn shorthands_exclude_processes_and_title_substrings() {
+        let filter = filter(FilterConfig {
+            exclude_processes: vec!["Discord".to_string()],
+            exclude_titles: vec!["(Picture-in-Picture)".to_string()],
+            ..Default::default()
+        });
+
+        assert!(!filter.allows(&window("discord", "#general")));
+        assert!(!filter.allows(&window("firefox", "Video (Picture-in-Picture) - Firefox")));
+        // The titles are plain text, not regular expressions
+        assert!(filter.allows(&window("firefox", "Picture-in-Picture")));
+    }
+
+    #[test]
+    fn invalid_title_regex_is_an_error() {
+        let config = FilterConfig {
+            rules: vec![FilterRule {
+                title: Some("(unclosed".to_string()),
+                ..rule(RuleAction::Exclude)
+            }],
+            ..Default::default()
+        };
+
+        let error = WindowFilter::new(&config).err().unwrap();
+        assert!(
+            error.starts_with("filter: invalid title regex \"(unclosed\""),
+            "{error}"
+        );
+    }
+}
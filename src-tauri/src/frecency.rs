@@ -0,0 +1,413 @@
+use crate::window_manager::{now_millis, Window};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Time it takes for a visit to count half as much, in milliseconds.
+const HALF_LIFE: f64 = 7.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// How much a visit to the exact window counts relative to its application.
+const TITLE_WEIGHT: f64 = 0.5;
+
+/// Between the application and the title in title keys. App ids and process
+/// names don't contain it.
+const TITLE_SEPARATOR: &str = ": ";
+
+/// Entries that decayed below this are forgotten, a single visit gets there
+/// in about seven weeks.
+const MIN_SCORE: f64 = 0.01;
+
+/// At most this many entries are kept, the lowest scoring ones go first.
+const MAX_ENTRIES: usize = 1000;
+
+/// How long visits are collected before they're written out together.
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FrecencyEntry {
+    pub key: String,
+    /// Score as of `last_used`, decays from there.
+    pub score: f64,
+    pub visits: u64,
+    /// Unix timestamp in milliseconds.
+    pub last_used: u64,
+}
+
+impl FrecencyEntry {
+    fn score_at(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.last_used) as f64;
+        self.score * 0.5f64.powf(elapsed / HALF_LIFE)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct FrecencyFile {
+    entries: Vec<FrecencyEntry>,
+}
+
+/// On-disk record of how often and how recently windows were switched to.
+///
+/// Visits are tracked per application and, when `remember_titles` is on, per
+/// application + normalized title, so both "Slack" and "that one terminal
+/// tab" can rise to the top.
+pub struct FrecencyStore {
+    path: PathBuf,
+    entries: Arc<Mutex<HashMap<String, FrecencyEntry>>>,
+    remember_titles: AtomicBool,
+    /// Set while a save is on its way, visits until then go along with it.
+    save_scheduled: Arc<AtomicBool>,
+}
+
+impl FrecencyStore {
+    /// Loads the store from `path`, starting empty if it doesn't exist or can't be read.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = match fs::read(&path) {
+            Ok(contents) => match serde_json::from_slice::<FrecencyFile>(&contents) {
+                Ok(file) => file.entries,
+                Err(e) => {
+                    println!("Ignoring corrupt frecency store {}: {e}", path.display());
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            path,
+            entries: Arc::new(Mutex::new(
+                entries
+                    .into_iter()
+                    .map(|entry| (entry.key.clone(), entry))
+                    .collect(),
+            )),
+            remember_titles: AtomicBool::new(false),
+            save_scheduled: Default::default(),
+        }
+    }
+
+    /// Whether visits count for the window title too. Turning it off forgets
+    /// the titles on the next save.
+    pub fn set_remember_titles(&self, remember_titles: bool) {
+        self.remember_titles
+            .store(remember_titles, Ordering::Relaxed);
+    }
+
+    /// Records a switch to `window`. It's written to disk shortly after on a
+    /// thread of its own, so switching never waits on the disk.
+    pub fn record_visit(&self, window: &Window) {
+        let now = now_millis();
+
+        {
+            let mut entries = self.entries.lock().unwrap();
+
+            for key in keys(window, self.remember_titles()) {
+                let entry = entries.entry(key.clone()).or_insert(FrecencyEntry {
+                    key,
+                    score: 0.0,
+                    visits: 0,
+                    last_used: now,
+                });
+
+                entry.score = entry.score_at(now) + 1.0;
+                entry.visits += 1;
+                entry.last_used = now;
+            }
+        }
+
+        if self.save_scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let path = self.path.clone();
+        let entries = self.entries.clone();
+        let save_scheduled = self.save_scheduled.clone();
+        let remember_titles = self.remember_titles();
+
+        std::thread::spawn(move || {
+            std::thread::sleep(SAVE_DELAY);
+            // Visits from here on schedule a save of their own
+            save_scheduled.store(false, Ordering::Release);
+
+            if let Err(e) = save(&path, &entries, remember_titles) {
+                println!("Error saving frecency store: {e}");
+            }
+        });
+    }
+
+    /// Current score of `window`, zero for windows that were never visited.
+    pub fn score(&self, window: &Window) -> f64 {
+        let now = now_millis();
+        let entries = self.entries.lock().unwrap();
+
+        let mut keys = keys(window, self.remember_titles()).into_iter();
+        let app_score = keys
+            .next()
+            .and_then(|key| entries.get(&key))
+            .map_or(0.0, |entry| entry.score_at(now));
+        let title_score = keys
+            .next()
+            .and_then(|key| entries.get(&key))
+            .map_or(0.0, |entry| entry.score_at(now));
+
+        app_score + title_score * TITLE_WEIGHT
+    }
+
+    /// Returns every entry with its score decayed to now, highest first.
+    pub fn entries(&self) -> Vec<FrecencyEntry> {
+        let now = now_millis();
+
+        let mut entries: Vec<FrecencyEntry> = self
+            .entries
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| FrecencyEntry {
+                score: entry.score_at(now),
+                ..entry.clone()
+            })
+            .collect();
+
+        entries.sort_by(|a, b| b.score.total_cmp(&a.score));
+        entries
+    }
+
+    pub fn reset(&self) -> Result<(), String> {
+        self.entries.lock().unwrap().clear();
+        self.save()
+    }
+
+    /// Writes the store out now, for visits that haven't been saved yet when quitting.
+    pub fn save(&self) -> Result<(), String> {
+        save(&self.path, &self.entries, self.remember_titles())
+    }
+
+    fn remember_titles(&self) -> bool {
+        self.remember_titles.load(Ordering::Relaxed)
+    }
+}
+
+/// Forgets what decayed away or doesn't fit, then writes the rest to `path`.
+fn save(
+    path: &Path,
+    entries: &Mutex<HashMap<String, FrecencyEntry>>,
+    remember_titles: bool,
+) -> Result<(), String> {
+    let file = {
+        let mut entries = entries.lock().unwrap();
+        let now = now_millis();
+
+        entries.retain(|key, entry| {
+            entry.score_at(now) >= MIN_SCORE && (remember_titles || !is_title_key(key))
+        });
+
+        if entries.len() > MAX_ENTRIES {
+            // Best first, the more recently used winning ties
+            let mut ranked: Vec<(f64, u64, String)> = entries
+                .iter()
+                .map(|(key, entry)| (entry.score_at(now), entry.last_used, key.clone()))
+                .collect();
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+
+            for (_, _, key) in ranked.split_off(MAX_ENTRIES) {
+                entries.remove(&key);
+            }
+        }
+
+        FrecencyFile {
+            entries: entries.values().cloned().collect(),
+        }
+    };
+    let contents = serde_json::to_vec_pretty(&file).map_err(|e| e.to_string())?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    // Write to a temporary file first so a crash never leaves a truncated store behind
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, contents).map_err(|e| e.to_string())?;
+    fs::rename(&temp_path, path).map_err(|e| e.to_string())
+}
+
+/// The application key followed by the application + title key if titles
+/// are remembered, empty if the window has no identity.
+fn keys(window: &Window, remember_titles: bool) -> Vec<String> {
+    let Some(app) = window
+        .app_id
+        .as_deref()
+        .or(window.process_name.as_deref())
+        .map(str::to_lowercase)
+    else {
+        return Vec::new();
+    };
+
+    if !remember_titles {
+        return vec![app];
+    }
+
+    let title = normalize_title(&window.title);
+    let title_key = format!("{app}{TITLE_SEPARATOR}{title}");
+
+    vec![app, title_key]
+}
+
+fn is_title_key(key: &str) -> bool {
+    key.contains(TITLE_SEPARATOR)
+}
+
+/// Lowercases the title and drops digits, so "Inbox (3)" and "Inbox (12)" count as one window.
+fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    static NEXT_STORE: AtomicU32 = AtomicU32::new(0);
+
+    /// A store in a directory of its own that doesn't exist yet.
+    fn store() -> FrecencyStore {
+        let dir = std::env::temp_dir().join(format!(
+            "binocular-frecency-{}-{}",
+            std::process::id(),
+            NEXT_STORE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        FrecencyStore::load(dir.join("frecency.json"))
+    }
+
+    fn window(app_id: &str, title: &str) -> Window {
+        Window {
+            title: title.to_string(),
+            app_id: Some(app_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn keys(store: &FrecencyStore) -> Vec<String> {
+        let mut keys: Vec<String> = store.entries().into_iter().map(|entry| entry.key).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn visits_rank_applications() {
+        let store = store();
+        let terminal = window("Alacritty", "~/binocular");
+        let editor = window("code", "README.md");
+
+        store.record_visit(&terminal);
+        store.record_visit(&terminal);
+        store.record_visit(&editor);
+
+        assert!(store.score(&terminal) > store.score(&editor));
+        assert!(store.score(&editor) > 0.0);
+        assert_eq!(store.score(&window("firefox", "New Tab")), 0.0);
+        assert_eq!(store.entries()[0].visits, 2);
+    }
+
+    #[test]
+    fn titles_only_when_remembered() {
+        let store = store();
+        store.record_visit(&window("Alacritty", "~/binocular"));
+        assert_eq!(keys(&store), ["alacritty"]);
+
+        store.set_remember_titles(true);
+        store.record_visit(&window("Alacritty", "Inbox (3)"));
+        assert_eq!(keys(&store), ["alacritty", "alacritty: inbox ()"]);
+        assert!(
+            store.score(&window("Alacritty", "Inbox (12)"))
+                > store.score(&window("Alacritty", "~/binocular"))
+        );
+
+        // Turned off, they're gone from disk with the next save
+        store.set_remember_titles(false);
+        store.save().unwrap();
+        assert_eq!(
+            keys(&FrecencyStore::load(store.path.clone())),
+            ["alacritty"]
+        );
+    }
+
+    #[test]
+    fn visits_are_saved_later() {
+        let store = store();
+        store.record_visit(&window("Alacritty", "~/binocular"));
+        assert!(!store.path.exists());
+
+        store.save().unwrap();
+        assert_eq!(
+            keys(&FrecencyStore::load(store.path.clone())),
+            ["alacritty"]
+        );
+    }
+
+    #[test]
+    fn forgets_what_decayed_or_doesnt_fit() {
+        let store = store();
+        let now = now_millis();
+        let entry = |key: String, score: f64, last_used: u64| {
+            (
+                key.clone(),
+                FrecencyEntry {
+                    key,
+                    score,
+                    visits: 1,
+                    last_used,
+                },
+            )
+        };
+
+        {
+            let mut entries = store.entries.lock().unwrap();
+            let seven_weeks_ago = now - 7 * 7 * 24 * 60 * 60 * 1000;
+            entries.extend([entry("forgotten".to_string(), 1.0, seven_weeks_ago)]);
+            entries.extend(
+                (0..MAX_ENTRIES + 10).map(|n| entry(format!("app {n}"), 1.0 + n as f64, now)),
+            );
+        }
+        store.save().unwrap();
+
+        let kept = keys(&store);
+        assert_eq!(kept.len(), MAX_ENTRIES);
+        assert!(!kept.contains(&"forgotten".to_string()));
+        assert!(!kept.contains(&"app 9".to_string()));
+        assert!(kept.contains(&"app 10".to_string()));
+    }
+
+    #[test]
+    fn ties_dont_go_over_the_limit() {
+        let store = store();
+        let now = now_millis();
+
+        {
+            let mut entries = store.entries.lock().unwrap();
+            entries.extend((0..MAX_ENTRIES + 10).map(|n| {
+                let key = format!("app {n}");
+                let entry = FrecencyEntry {
+                    key: key.clone(),
+                    score: 1.0,
+                    visits: 1,
+                    last_used: now,
+                };
+                (key, entry)
+            }));
+        }
+        store.save().unwrap();
+
+        assert_eq!(keys(&store).len(), MAX_ENTRIES);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::window::WindowBackend;
+use crate::window_manager::Window;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// How windows are put together into groups.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    /// Windows of the same application, by app id or process name.
+    #[default]
+    App,
+    /// Windows of one running instance of a program, following its process
+    /// tree so windows of helper processes join the main one.
+    ProcessTree,
+}
+
+/// What to do with a whole group of windows.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupAction {
+    /// Focuses the window of the group that was used last.
+    FocusRecent,
+    /// Focuses the window of the group that was used longest ago, so doing it
+    /// again and again goes through all of them.
+    Cycle,
+    CloseAll,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WindowGroup {
+    /// Identifies the group across refreshes, for `group_action`.
+    pub key: String,
+    /// Application name of the group's first window.
+    pub name: String,
+    pub icon_id: Option<String>,
+    pub count: usize,
+    /// In the order they were grouped in.
+    pub windows: Vec<Window>,
+}
+
+impl WindowGroup {
+    /// The window that had focus last, the first one if none ever had.
+    pub fn most_recent(&self) -> Option<&Window> {
+        self.windows
+            .iter()
+            .rev()
+            .max_by_key(|window| window.last_focused)
+    }
+
+    /// The window that went the longest without focus, never focused ones first.
+    pub fn least_recent(&self) -> Option<&Window> {
+        self.windows.iter().min_by_key(|window| window.last_focused)
+    }
+}
+
+/// Groups `windows`, ordering the groups by their first window and keeping
+/// the order of the windows within each group.
+///
+/// The backend is only asked about processes when grouping by process tree.
+pub fn group_windows(
+    windows: Vec<Window>,
+    group_by: GroupBy,
+    backend: &dyn WindowBackend,
+) -> Vec<WindowGroup> {
+    let keys = match group_by {
+        GroupBy::App => windows.iter().map(app_key).collect(),
+        GroupBy::ProcessTree => process_tree_keys(&windows, backend),
+    };
+
+    let mut groups: Vec<WindowGroup> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+
+    for (window, key) in windows.into_iter().zip(keys) {
+        let index = *indices.entry(key.clone()).or_insert_with(|| {
+            groups.push(WindowGroup {
+                key,
+                name: display_name(&window),
+                icon_id: window.icon_id.clone(),
+                count: 0,
+                windows: Vec::new(),
+            });
+            groups.len() - 1
+        });
+
+        let group = &mut groups[index];
+        group.count += 1;
+        group.windows.push(window);
+    }
+
+    groups
+}
+
+/// The app id, or the process name when there is none, like the frecency keys.
+fn app_key(window: &Window) -> String {
+    window
+        .app_id
+        .as_deref()
+        .or(window.process_name.as_deref())
+        .map(|app| format!("app:{}", app.to_lowercase()))
+        .unwrap_or_else(|| process_key(window, window.process_id))
+}
+
+/// Groups by process `process_id`, or keeps the window to itself when its
+/// process isn't known.
+fn process_key(window: &Window, process_id: u32) -> String {
+    match process_id {
+        0 => format!("window:{}", window.id),
+        process_id => format!("pid:{process_id}"),
+    }
+}
+
+fn display_name(window: &Window) -> String {
+    window
+        .app_name
+        .as_deref()
+        .or(window.app_id.as_deref())
+        .or(window.process_name.as_deref())
+        .unwrap_or(&window.title)
+        .to_string()
+}
+
+/// Keys every window by the oldest ancestor of its process that runs the same
+/// executable, which is where browsers and Electron apps keep their windows.
+///
+/// Ancestors running something else are left out, otherwise everything
+/// started from a shell or the desktop would end up in one group.
+fn process_tree_keys(windows: &[Window], backend: &dyn WindowBackend) -> Vec<String> {
+    let mut roots: HashMap<u32, u32> = HashMap::new();
+
+    windows
+        .iter()
+        .map(|window| {
+            if window.process_id == 0 {
+                return process_key(window, 0);
+            }
+
+            let root = *roots
+                .entry(window.process_id)
+                .or_insert_with(|| tree_root(window, backend));
+            process_key(window, root)
+        })
+        .collect()
+}
+
+fn tree_root(window: &Window, backend: &dyn WindowBackend) -> u32 {
+    let Some(exe_path) = window.exe_path.as_deref() else {
+        return window.process_id;
+    };
+
+    let mut root = window.process_id;
+    // Reused process ids could make the tree loop
+    let mut seen = HashSet::from([root]);
+
+    while let Some(parent) = backend
+        .get_parent_process_id(root)
+        .filter(|parent| seen.insert(*parent))
+    {
+        if backend.get_process_path(parent).as_deref() != Some(exe_path) {
+            break;
+        }
+        root = parent;
+    }
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::FakeBackend;
+
+    fn keys(groups: &[WindowGroup]) -> Vec<(&str, Vec<&str>)> {
+        groups
+            .iter()
+            .map(|group| {
+                let titles = group.windows.iter().map(|window| window.title.as_str());
+                (group.key.as_str(), titles.collect())
+            })
+            .collect()
+    }
+
+    /// The backend's windows as the manager lists them, with their process names.
+    fn windows(backend: &FakeBackend) -> Vec<Window> {
+        backend
+            .get_windows(0)
+            .into_iter()
+            .map(|window| Window {
+                process_name: backend.get_process_name(window.process_id),
+                ..window
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_app_in_order() {
+        let backend = FakeBackend::default();
+        backend.add_window("Inbox", 1001, "mail");
+        backend.add_window("GitHub", 1002, "chrome");
+        backend.add_window("Drafts", 1003, "mail");
+        let mut windows = windows(&backend);
+        windows[0].app_id = Some("Mail".to_string());
+        windows[0].app_name = Some("Evolution".to_string());
+
+        let groups = group_windows(windows, GroupBy::App, &backend);
+
+        assert_eq!(
+            keys(&groups),
+            [
+                ("app:mail", vec!["Drafts", "Inbox"]),
+                ("app:chrome", vec!["GitHub"]),
+            ]
+        );
+        assert_eq!(groups[0].name, "Evolution");
+        assert_eq!(groups[0].count, 2);
+        assert_eq!(groups[1].name, "chrome");
+    }
+
+    #[test]
+    fn groups_by_process_tree_of_the_same_executable() {
+        let backend = FakeBackend::default();
+        backend.add_window("GitHub", 1004, "chrome");
+        backend.add_window("DevTools", 1005, "chrome");
+        backend.set_parent_process(1005, 1004);
+        // Started from a terminal, which isn't part of it
+        backend.add_window("~/binocular", 1003, "alacritty");
+        backend.add_window("Docs", 1006, "chrome");
+        backend.set_parent_process(1006, 1003);
+
+        let windows: Vec<Window> = windows(&backend)
+            .into_iter()
+            .map(|window| Window {
+                exe_path: backend.get_process_path(window.process_id),
+                ..window
+            })
+            .collect();
+        let groups = group_windows(windows, GroupBy::ProcessTree, &backend);
+
+        assert_eq!(
+            keys(&groups),
+            [
+                ("pid:1006", vec!["Docs"]),
+                ("pid:1003", vec!["~/binocular"]),
+                ("pid:1004", vec!["DevTools", "GitHub"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_without_a_process_stay_apart() {
+        let backend = FakeBackend::default();
+        let first = backend.add_window("Untitled", 0, "unknown");
+        let second = backend.add_window("Untitled", 0, "unknown");
+        let windows = backend.get_windows(u32::MAX);
+
+        for group_by in [GroupBy::App, GroupBy::ProcessTree] {
+            let groups = group_windows(windows.clone(), group_by, &backend);
+            let keys: Vec<&str> = groups.iter().map(|group| group.key.as_str()).collect();
+            assert_eq!(
+                keys,
+                [format!("window:{second}"), format!("window:{first}")],
+                "{group_by:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn group_recency() {
+        let backend = FakeBackend::default();
+        backend.add_window("First", 1001, "mail");
+        backend.add_window("Second", 1001, "mail");
+        backend.add_window("Third", 1001, "mail");
+        let mut windows = windows(&backend);
+        windows[0].last_focused = Some(20);
+        windows[2].last_focused = Some(30);
+
+        let groups = group_windows(windows, GroupBy::App, &backend);
+
+        assert_eq!(groups[0].most_recent().unwrap().title, "First");
+        assert_eq!(groups[0].least_recent().unwrap().title, "Second");
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// What a global hotkey does when pressed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Shows the switcher, or hides it if it's already showing.
+    ToggleSwitcher,
+    /// Shows the switcher with a specific picker.
+    OpenPicker(Picker),
+    /// Switches straight to the window that was focused before the current one.
+    FocusPrevious,
+    /// Runs a program, the first element is the executable and the rest its arguments.
+    RunCommand(Vec<String>),
+}
+
+/// The lists the switcher can show.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Picker {
+    /// Every window.
+    Windows,
+    /// Only the windows of the application that has focus.
+    SameApp,
+    /// The applications, each with the number of windows it has.
+    Apps,
+    /// The workspaces, each with the number of windows on it.
+    Workspaces,
+}
+
+/// A configured hotkey and whether it could be registered.
+#[derive(Debug, Serialize, Clone)]
+pub struct HotkeyStatus {
+    pub shortcut: String,
+    pub action: HotkeyAction,
+    /// Why the shortcut isn't active, e.g. another application already owns it.
+    pub error: Option<String>,
+}
+
+/// Keeps the registered global shortcuts in sync with the configured hotkeys.
+#[derive(Default)]
+pub struct HotkeyManager {
+    actions: Mutex<HashMap<Shortcut, HotkeyAction>>,
+    statuses: Mutex<Vec<HotkeyStatus>>,
+}
+
+impl HotkeyManager {
+    /// Registers the shortcuts in `hotkeys` and unregisters the ones that are gone.
+    ///
+    /// Shortcuts that stay bound are left registered, only their action is
+    /// updated. A shortcut that fails to register doesn't stop the others.
+    pub fn update(
+        &self,
+        app: &AppHandle,
+        hotkeys: &BTreeMap<String, HotkeyAction>,
+    ) -> Vec<HotkeyStatus> {
+        let global_shortcut = app.global_shortcut();
+        let mut actions = self.actions.lock().unwrap();
+        let mut previous = std::mem::take(&mut *actions);
+        let mut statuses = Vec::with_capacity(hotkeys.len());
+
+        for (name, action) in hotkeys {
+            let registered = name
+                .parse::<Shortcut>()
+                .map_err(|e| e.to_string())
+                .and_then(|shortcut| {
+                    if previous.remove(&shortcut).is_none() {
+                        global_shortcut
+                            .register(shortcut)
+                            .map_err(|e| e.to_string())?;
+                    }
+
+                    actions.insert(shortcut, action.clone());
+                    Ok(())
+                });
+
+            if let Err(e) = &registered {
+                println!("failed to register global shortcut {name}: {e}");
+            }
+
+            statuses.push(HotkeyStatus {
+                shortcut: name.clone(),
+                action: action.clone(),
+                error: registered.err(),
+            });
+        }
+
+        for shortcut in previous.into_keys() {
+            if let Err(e) = global_shortcut.unregister(shortcut) {
+                println!("failed to unregister global shortcut: {e}");
+            }
+        }
+
+        *self.statuses.lock().unwrap() = statuses.clone();
+        statuses
+    }
+
+    pub fn action(&self, shortcut: &Shortcut) -> Option<HotkeyAction> {
+        self.actions.lock().unwrap().get(shortcut).cloned()
+    }
+
+    pub fn statuses(&self) -> Vec<HotkeyStatus> {
+        self.statuses.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,223 @@
+use crate::window::{IconHandle, WindowBackend, WindowId};
+use crate::window_manager::Window;
+use lru::LruCache;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+/// How many distinct icons to keep around, each with its renderings at every requested size.
+const CAPACITY: usize = 256;
+
+/// Windows of the same application share an icon unless they set their own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum IconKey {
+    /// App id, or executable path when there's no app id, and the fingerprint.
+    /// Different apps can share an executable, e.g. Electron or Python ones,
+    /// and windows of one app can show different icons, e.g. browser profiles.
+    App(String, Option<u64>),
+    Window(WindowId),
+}
+
+struct CachedIcon {
+    /// Handed to the webview, changes whenever the fingerprint does.
+    id: String,
+    /// The icon handle the renderings were made from, see `IconHandle::fingerprint`.
+    fingerprint: Option<u64>,
+    /// A window showing this icon, to render other sizes from.
+    window: Window,
+    /// PNG bytes by size.
+    renderings: HashMap<u32, Vec<u8>>,
+}
+
+struct Icons {
+    by_key: LruCache<IconKey, CachedIcon>,
+    /// Icon ids to the key of their entry in `by_key`.
+    keys: HashMap<String, IconKey>,
+}
+
+/// Rendered window icons, so each distinct icon is only encoded once.
+pub struct IconCache {
+    icons: Mutex<Icons>,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self {
+            icons: Mutex::new(Icons {
+                by_key: LruCache::new(NonZeroUsize::new(CAPACITY).unwrap()),
+                keys: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl Icons {
+    fn insert(&mut self, key: IconKey, icon: CachedIcon) {
+        let id = icon.id.clone();
+
+        // `push` hands back the entry it replaced as well as the one it evicted
+        if let Some((_, replaced)) = self.by_key.push(key.clone(), icon) {
+            self.keys.remove(&replaced.id);
+        }
+
+        self.keys.insert(id, key);
+    }
+}
+
+impl IconCache {
+    /// Returns the id of the icon `window` shows, rendering it at `size` only if
+    /// it isn't cached or has changed. `None` if the window has no icon.
+    pub fn get(&self, backend: &dyn WindowBackend, window: &Window, size: u32) -> Option<String> {
+        let handle = backend.get_icon_handle(window.id);
+        let fingerprint = handle.map(|handle| handle.fingerprint);
+
+        let app = window.app_id.clone().or_else(|| window.exe_path.clone());
+        let key = match (handle, app) {
+            (Some(IconHandle { own: false, .. }) | None, Some(app)) => {
+                IconKey::App(app, fingerprint)
+            }
+            _ => IconKey::Window(window.id),
+        };
+
+        if let Some(cached) = self.icons.lock().unwrap().by_key.get_mut(&key) {
+            if cached.fingerprint == fingerprint {
+                // Any window showing the icon will do, prefer one we know is still open
+                if cached.window.id != window.id {
+                    cached.window = window.clone();
+                }
+                return Some(cached.id.clone());
+            }
+        }
+
+        // Rendering can be slow, don't hold the lock while it happens
+        let png = backend.get_window_icon(window, size)?;
+
+        let id = icon_id(&key, fingerprint);
+        self.icons.lock().unwrap().insert(
+            key,
+            CachedIcon {
+                id: id.clone(),
+                fingerprint,
+                window: window.clone(),
+                renderings: HashMap::from([(size, png)]),
+            },
+        );
+
+        Some(id)
+    }
+
+    /// Returns the PNG bytes of the icon with `id` at `size`, rendering it if
+    /// needed and the window it came from is still around.
+    pub fn png(&self, backend: &dyn WindowBackend, id: &str, size: u32) -> Option<Vec<u8>> {
+        let window = {
+            let mut icons = self.icons.lock().unwrap();
+            let key = icons.keys.get(id)?.clone();
+            let cached = icons.by_key.get(&key)?;
+
+            if let Some(png) = cached.renderings.get(&size) {
+                return Some(png.clone());
+            }
+
+            cached.window.clone()
+        };
+
+        let png = backend.get_window_icon(&window, size)?;
+
+        let mut icons = self.icons.lock().unwrap();
+        if let Some(key) = icons.keys.get(id).cloned() {
+            if let Some(cached) = icons.by_key.get_mut(&key) {
+                cached.renderings.insert(size, png.clone());
+            }
+        }
+
+        Some(png)
+    }
+
+    /// Drops the icon a closed window set for itself, if it had one.
+    pub fn forget_window(&self, id: WindowId) {
+        let mut icons = self.icons.lock().unwrap();
+
+        if let Some(cached) = icons.by_key.pop(&IconKey::Window(id)) {
+            icons.keys.remove(&cached.id);
+        }
+    }
+
+    /// Drops every icon, e.g. after the icon theme changed.
+    pub fn clear(&self) {
+        let mut icons = self.icons.lock().unwrap();
+        icons.by_key.clear();
+        icons.keys.clear();
+    }
+}
+
+/// Derives an id that changes whenever the icon behind `key` does.
+fn icon_id(key: &IconKey, fingerprint: Option<u64>) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    fingerprint.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::FakeBackend;
+
+    #[test]
+    fn windows_of_one_app_with_different_icons_keep_both() {
+        let backend = FakeBackend::default();
+        let window = |title, fingerprint| {
+            let id = backend.add_window(title, 1004, "chrome");
+            backend.set_icon(id, fingerprint);
+            Window {
+                id,
+                app_id: Some("google-chrome".to_string()),
+                ..Default::default()
+            }
+        };
+        let work = window("Work - Google Chrome", 1);
+        let personal = window("Personal - Google Chrome", 2);
+        let other_work = window("Docs - Google Chrome", 1);
+        let cache = IconCache::default();
+
+        let work_icon = cache.get(&backend, &work, 16).unwrap();
+        let personal_icon = cache.get(&backend, &personal, 16).unwrap();
+        assert_ne!(work_icon, personal_icon);
+        assert_eq!(cache.get(&backend, &other_work, 16).unwrap(), work_icon);
+
+        // Refreshing again renders nothing and every id still serves its icon
+        assert_eq!(cache.get(&backend, &work, 16).unwrap(), work_icon);
+        assert_eq!(cache.get(&backend, &personal, 16).unwrap(), personal_icon);
+        assert_eq!(backend.icon_renders(), 2);
+        assert_eq!(
+            cache.png(&backend, &work_icon, 16).unwrap(),
+            1u64.to_le_bytes()
+        );
+        assert_eq!(
+            cache.png(&backend, &personal_icon, 16).unwrap(),
+            2u64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn changed_icons_get_a_new_id() {
+        let backend = FakeBackend::default();
+        let id = backend.add_window("Inbox", 1001, "mail");
+        backend.set_icon(id, 1);
+        let window = Window {
+            id,
+            app_id: Some("org.gnome.Evolution".to_string()),
+            ..Default::default()
+        };
+        let cache = IconCache::default();
+
+        let before = cache.get(&backend, &window, 16).unwrap();
+        backend.set_icon(id, 2);
+        let after = cache.get(&backend, &window, 16).unwrap();
+
+        assert_ne!(before, after);
+        assert_eq!(cache.png(&backend, &after, 32).unwrap(), 2u64.to_le_bytes());
+    }
+}
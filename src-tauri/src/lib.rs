@@ -1,17 +1,34 @@
+mod config;
+mod filter;
+mod frecency;
+mod grouping;
+mod hotkeys;
+mod icon_cache;
+mod preview_cache;
+mod search;
 mod utils;
 mod window;
 mod window_manager;
 
-use std::process;
+use config::{Config, ConfigManager, ICON_SIZES};
+use frecency::{FrecencyEntry, FrecencyStore};
+use grouping::{GroupAction, GroupBy, WindowGroup};
+use hotkeys::{HotkeyAction, HotkeyManager, HotkeyStatus, Picker};
+use search::{SearchField, SearchResult};
 use tauri::{
+    http,
     menu::{Menu, MenuItem},
     tray::TrayIconBuilder,
-    AppHandle, Emitter, Manager,
+    AppHandle, Emitter, Manager, Runtime, WebviewWindow,
 };
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use tauri_plugin_global_shortcut::ShortcutState;
 use utils::{
     display::center_window_in_display,
-    setup::{setup_autostart, setup_window_event_listener},
+    process::ProcessInfo,
+    setup::{set_autostart, setup_autostart},
+};
+use window::{
+    Monitor, MoveTarget, WindowAction, WindowCapabilities, WindowEvent, WindowId, Workspace,
 };
 use window_manager::{Window, WindowManager};
 
@@ -20,22 +37,478 @@ fn get_windows(app: &AppHandle) -> Result<(), String> {
 
     state.inner().refresh_window_list();
 
-    app.emit("windows-updated", state.windows.lock().unwrap().clone())
-        .unwrap();
+    app.emit("windows-updated", state.snapshot())
+        .map_err(|e| e.to_string())
+}
+
+/// Refreshes the window list in the background and only sends the webview what changed.
+fn emit_window_changes(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<WindowManager>();
+
+    let changes = state.inner().refresh_window_list();
+
+    if !changes.added.is_empty() {
+        app.emit("window-added", &changes.added)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if !changes.removed.is_empty() {
+        app.emit("window-removed", &changes.removed)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if !changes.updated.is_empty() {
+        app.emit("window-updated", &changes.updated)
+            .map_err(|e| e.to_string())?;
+    }
 
     Ok(())
 }
 
+/// Applies `config` on top of `previous`, only touching the hotkeys and autostart if they changed.
+fn apply_config(app: &AppHandle, config: &Config, previous: Option<&Config>) {
+    if previous.map(|previous| &previous.hotkeys) != Some(&config.hotkeys) {
+        let statuses = app.state::<HotkeyManager>().update(app, &config.hotkeys);
+
+        if let Err(e) = app.emit("hotkeys-changed", statuses) {
+            println!("Error sending hotkeys: {e}");
+        }
+    }
+
+    if previous.map(|previous| previous.autostart) != Some(config.autostart) {
+        set_autostart(app, config.autostart);
+    }
+
+    // Not worth watching the desktop settings for, a config reload picks it up
+    #[cfg(target_os = "linux")]
+    if utils::freedesktop::icon_theme::reload_theme() {
+        app.state::<WindowManager>().forget_icons();
+    }
+
+    app.state::<WindowManager>().apply_config(config);
+    app.state::<FrecencyStore>()
+        .set_remember_titles(config.remember_titles);
+}
+
+/// Re-reads the config file after it changed on disk and applies it, or reports why it can't.
+fn reload_config(app: &AppHandle) {
+    let config_manager = app.state::<ConfigManager>();
+    let previous = config_manager.get();
+    let previous_error = config_manager.error();
+
+    match config_manager.reload() {
+        // Editors tend to fire several events per save
+        Ok(config) if config == previous && previous_error.is_none() => {}
+        Ok(config) => {
+            apply_config(app, &config, Some(&previous));
+
+            if let Err(e) = get_windows(app) {
+                println!("Error refreshing window list: {e}");
+            }
+            if let Err(e) = app.emit("config-changed", &config) {
+                println!("Error sending config: {e}");
+            }
+        }
+        Err(e) => {
+            println!("{e}");
+
+            if let Err(e) = app.emit("config-error", &e) {
+                println!("Error sending config error: {e}");
+            }
+        }
+    }
+}
+
 #[tauri::command]
-fn focus_window(app_handle: tauri::AppHandle, window: Window) {
-    let main_window = app_handle.get_webview_window("main").unwrap();
-    main_window.hide().unwrap();
-    window.focus_window();
+fn get_config(config: tauri::State<ConfigManager>) -> Config {
+    config.get()
 }
 
+/// The reason the config file couldn't be loaded, if it couldn't.
 #[tauri::command]
-fn close_window(window: Window) {
-    window.close_window();
+fn get_config_error(config: tauri::State<ConfigManager>) -> Option<String> {
+    config.error()
+}
+
+/// Refreshes the window list and shows the switcher on the active monitor.
+fn show_switcher(app: &AppHandle, main_window: &WebviewWindow) {
+    if let Err(e) = get_windows(app) {
+        println!("Error refreshing window list: {e}");
+    }
+
+    let state = app.state::<WindowManager>();
+    if let Err(e) = center_window_in_display(main_window, state.inner()) {
+        println!("Error centering window: {e}");
+    }
+
+    if let Err(e) = main_window.show() {
+        println!("Error showing window: {e}");
+    }
+    if let Err(e) = main_window.set_focus() {
+        println!("Error focusing window: {e}");
+    }
+}
+
+/// Shows the switcher with `picker`, or switches picker if it's already showing.
+fn open_picker(app: &AppHandle, main_window: &WebviewWindow, picker: Picker) {
+    match main_window.is_visible() {
+        Ok(true) => {}
+        Ok(false) => show_switcher(app, main_window),
+        Err(e) => println!("Error checking window visibility: {e}"),
+    }
+
+    if let Err(e) = app.emit("open-picker", picker) {
+        println!("Error opening picker: {e}");
+    }
+}
+
+/// Focuses `window` and counts it as a visit for ranking.
+fn switch_to_window<R: Runtime>(app: &AppHandle<R>, window: &Window) -> Result<(), String> {
+    app.state::<WindowManager>().focus_window(window.id)?;
+    record_visit(app, window);
+    Ok(())
+}
+
+fn record_visit<R: Runtime>(app: &AppHandle<R>, window: &Window) {
+    app.state::<FrecencyStore>().record_visit(window);
+}
+
+/// Hides the switcher before a command takes the user to another window.
+fn hide_main_window<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    app.get_webview_window("main")
+        .ok_or("The main window is gone")?
+        .hide()
+        .map_err(|e| e.to_string())
+}
+
+fn run_hotkey_action(app: &AppHandle, action: HotkeyAction) {
+    let Some(main_window) = app.get_webview_window("main") else {
+        println!("Error running hotkey: the main window is gone");
+        return;
+    };
+
+    match action {
+        HotkeyAction::ToggleSwitcher => match main_window.is_visible() {
+            Ok(true) => {
+                if let Err(e) = main_window.hide() {
+                    println!("Error hiding window: {e}");
+                }
+            }
+            Ok(false) => open_picker(app, &main_window, Picker::Windows),
+            Err(e) => {
+                println!("Error checking window visibility: {e}");
+            }
+        },
+        HotkeyAction::OpenPicker(picker) => open_picker(app, &main_window, picker),
+        HotkeyAction::FocusPrevious => {
+            if let Err(e) = main_window.hide() {
+                println!("Error hiding window: {e}");
+            }
+
+            // The focused window sits second, the first one is where we came from
+            let state = app.state::<WindowManager>();
+            state.refresh_window_list();
+
+            if let Some(window) = state.snapshot().first() {
+                if let Err(e) = switch_to_window(app, window) {
+                    println!("Error focusing previous window: {e}");
+                }
+            }
+        }
+        HotkeyAction::RunCommand(command) => {
+            let Some((program, args)) = command.split_first() else {
+                return;
+            };
+
+            match std::process::Command::new(program).args(args).spawn() {
+                // Waited on so it doesn't linger as a zombie once it exits
+                Ok(mut child) => {
+                    std::thread::spawn(move || {
+                        let _ = child.wait();
+                    });
+                }
+                Err(e) => println!("Error running {program}: {e}"),
+            }
+        }
+    }
+}
+
+/// The configured hotkeys, with the reason for each one that couldn't be registered.
+#[tauri::command]
+fn get_hotkeys(hotkeys: tauri::State<HotkeyManager>) -> Vec<HotkeyStatus> {
+    hotkeys.statuses()
+}
+
+#[tauri::command]
+fn get_window_snapshot(state: tauri::State<WindowManager>) -> Vec<Window> {
+    state.snapshot()
+}
+
+/// The windows matching `query`, best first and frecency breaking ties. An empty
+/// query lists them all, most recently used first like the snapshot.
+#[tauri::command]
+fn search_windows(
+    state: tauri::State<WindowManager>,
+    frecency: tauri::State<FrecencyStore>,
+    query: String,
+    fields: Option<Vec<SearchField>>,
+) -> Vec<SearchResult> {
+    let fields = fields.as_deref().unwrap_or(SearchField::DEFAULT);
+
+    search::search_windows(state.snapshot(), &query, fields, |window| {
+        frecency.score(window)
+    })
+}
+
+/// The windows grouped as configured, unless `group_by` asks for something else.
+#[tauri::command]
+fn get_window_groups(
+    state: tauri::State<WindowManager>,
+    config: tauri::State<ConfigManager>,
+    group_by: Option<GroupBy>,
+) -> Vec<WindowGroup> {
+    state.window_groups(group_by.unwrap_or(config.get().group_by))
+}
+
+/// Runs `action` on the group `key` names, as grouped by `get_window_groups`.
+#[tauri::command]
+async fn group_action<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    key: String,
+    group_by: Option<GroupBy>,
+    action: GroupAction,
+) -> Result<(), String> {
+    let group_by = group_by.unwrap_or_else(|| app_handle.state::<ConfigManager>().get().group_by);
+
+    let group = blocking(app_handle.clone(), move |state| {
+        state
+            .window_groups(group_by)
+            .into_iter()
+            .find(|group| group.key == key)
+            .ok_or_else(|| format!("No window group {key}"))
+    })
+    .await?;
+
+    let window = match action {
+        GroupAction::FocusRecent => group.most_recent(),
+        GroupAction::Cycle => group.least_recent(),
+        GroupAction::CloseAll => {
+            return blocking(app_handle, move |state| {
+                let errors: Vec<String> = group
+                    .windows
+                    .iter()
+                    .filter_map(|window| state.close_window(window.id).err())
+                    .collect();
+
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors.join("\n"))
+                }
+            })
+            .await;
+        }
+    };
+    let window = window.ok_or("The group has no windows")?.clone();
+
+    hide_main_window(&app_handle)?;
+    switch_to_window_blocking(app_handle, window).await
+}
+
+/// Runs `work` on a thread that is allowed to block, for commands that wait
+/// on the window system or read `/proc` and would stall the main thread or the
+/// async runtime otherwise.
+async fn blocking<R: Runtime, T: Send + 'static>(
+    app_handle: tauri::AppHandle<R>,
+    work: impl FnOnce(&WindowManager) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || work(&app_handle.state::<WindowManager>()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Focuses `window` on a blocking thread and counts it as a visit, like
+/// `switch_to_window` does for hotkeys.
+async fn switch_to_window_blocking<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    window: Window,
+) -> Result<(), String> {
+    let id = window.id;
+    blocking(app_handle.clone(), move |state| state.focus_window(id)).await?;
+    record_visit(&app_handle, &window);
+    Ok(())
+}
+
+/// Returns a PNG thumbnail of the window as an `ArrayBuffer`.
+#[tauri::command]
+async fn get_window_preview<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    id: WindowId,
+    max_width: u32,
+    max_height: u32,
+) -> Result<tauri::ipc::Response, String> {
+    blocking(app_handle, move |state| {
+        state.get_window_preview(id, max_width, max_height)
+    })
+    .await
+    .map(tauri::ipc::Response::new)
+}
+
+#[tauri::command]
+async fn get_process_info<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    id: WindowId,
+) -> Result<ProcessInfo, String> {
+    blocking(app_handle, move |state| state.get_process_info(id)).await
+}
+
+#[tauri::command]
+fn get_frecency(frecency: tauri::State<FrecencyStore>) -> Vec<FrecencyEntry> {
+    frecency.entries()
+}
+
+#[tauri::command]
+fn reset_frecency(frecency: tauri::State<FrecencyStore>) -> Result<(), String> {
+    frecency.reset()
+}
+
+#[tauri::command]
+async fn focus_window<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    window: Window,
+) -> Result<(), String> {
+    hide_main_window(&app_handle)?;
+    switch_to_window_blocking(app_handle, window).await
+}
+
+#[tauri::command]
+async fn close_window<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    window: Window,
+) -> Result<(), String> {
+    blocking(app_handle, move |state| state.close_window(window.id)).await
+}
+
+#[tauri::command]
+async fn window_action<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    id: WindowId,
+    action: WindowAction,
+) -> Result<(), String> {
+    blocking(app_handle, move |state| state.window_action(id, action)).await
+}
+
+/// The monitors and workspaces `move_window` can send windows to.
+#[tauri::command]
+async fn get_move_targets<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+) -> Result<Vec<MoveTarget>, String> {
+    blocking(app_handle, |state| Ok(state.get_move_targets())).await
+}
+
+#[tauri::command]
+async fn move_window<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    id: WindowId,
+    target: MoveTarget,
+) -> Result<(), String> {
+    blocking(app_handle, move |state| state.move_window(id, &target)).await
+}
+
+/// Switches to the window by bringing it over instead of going to it.
+#[tauri::command]
+async fn summon_window<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    window: Window,
+) -> Result<(), String> {
+    hide_main_window(&app_handle)?;
+
+    let id = window.id;
+    blocking(app_handle.clone(), move |state| state.summon_window(id)).await?;
+    record_visit(&app_handle, &window);
+    Ok(())
+}
+
+/// The workspaces with how many windows each has, empty on window systems without them.
+#[tauri::command]
+async fn get_workspaces<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+) -> Result<Vec<Workspace>, String> {
+    blocking(app_handle, |state| Ok(state.workspaces())).await
+}
+
+/// Monitors with their work areas, the active one being where the switcher opens.
+#[tauri::command]
+async fn get_monitors<R: Runtime>(app_handle: tauri::AppHandle<R>) -> Result<Vec<Monitor>, String> {
+    blocking(app_handle, |state| Ok(state.monitors())).await
+}
+
+#[tauri::command]
+async fn switch_workspace<R: Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    id: String,
+) -> Result<(), String> {
+    hide_main_window(&app_handle)?;
+    blocking(app_handle, move |state| state.switch_workspace(&id)).await
+}
+
+/// Which `window_action`s the window system supports, so the UI can leave out the rest.
+#[tauri::command]
+fn get_window_capabilities(state: tauri::State<WindowManager>) -> WindowCapabilities {
+    state.capabilities()
+}
+
+/// Serves window icons as `binocular-icon://localhost/<icon id>?size=<pixels>`.
+///
+/// Icon ids only change with the icon where the backend can tell, e.g. not
+/// when the icon theme does, so responses are only cached for a minute.
+fn serve_icon(app: &AppHandle, request: &http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let id = request.uri().path().trim_start_matches('/');
+    let size = request
+        .uri()
+        .query()
+        .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("size=")))
+        .and_then(|size| size.parse().ok())
+        .filter(|size| ICON_SIZES.contains(size));
+
+    let response = match app.state::<WindowManager>().get_icon_png(id, size) {
+        Some(png) => http::Response::builder()
+            .header(http::header::CONTENT_TYPE, "image/png")
+            .header(http::header::CACHE_CONTROL, "max-age=60")
+            .body(png),
+        None => http::Response::builder()
+            .status(http::StatusCode::NOT_FOUND)
+            .body(Vec::new()),
+    };
+
+    response.unwrap()
+}
+
+/// Every command the webview can invoke.
+fn command_handler<R: Runtime>() -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        focus_window,
+        close_window,
+        window_action,
+        get_window_capabilities,
+        get_move_targets,
+        move_window,
+        summon_window,
+        get_workspaces,
+        switch_workspace,
+        get_monitors,
+        get_window_snapshot,
+        search_windows,
+        get_window_groups,
+        group_action,
+        get_window_preview,
+        get_process_info,
+        get_frecency,
+        reset_frecency,
+        get_config,
+        get_config_error,
+        get_hotkeys
+    ]
 }
 
 fn disable_dev_tools_in_dev() -> tauri::plugin::TauriPlugin<tauri::Wry> {
@@ -57,18 +530,40 @@ fn disable_dev_tools_in_dev() -> tauri::plugin::TauriPlugin<tauri::Wry> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let current_pid = process::id();
-
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(disable_dev_tools_in_dev())
-        .manage(WindowManager {
-            windows: Default::default(),
-            current_pid,
+        .register_uri_scheme_protocol("binocular-icon", |ctx, request| {
+            serve_icon(ctx.app_handle(), &request)
         })
+        .manage(WindowManager::new(window::default_backend()))
+        .manage(HotkeyManager::default())
         .setup(|app| {
             setup_autostart(app);
-            setup_window_event_listener(app);
+
+            let data_dir = app.path().app_data_dir()?;
+            app.manage(FrecencyStore::load(data_dir.join("frecency.json")));
+
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(ConfigManager::load(config_dir.join("config.toml")));
+
+            let app_handle = app.handle().clone();
+            if let Err(e) =
+                app.state::<WindowManager>()
+                    .subscribe(Box::new(move |event| match event {
+                        // Focus changes only reorder the list, which is sent in full when it's shown
+                        WindowEvent::Activated(id) => {
+                            app_handle.state::<WindowManager>().record_focus(id);
+                        }
+                        _ => {
+                            if let Err(e) = emit_window_changes(&app_handle) {
+                                println!("Error refreshing window list: {e}");
+                            }
+                        }
+                    }))
+            {
+                println!("Error listening for window events: {e}");
+            }
 
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let menu = Menu::with_items(app, &[&quit_i])?;
@@ -82,6 +577,9 @@ pub fn run() {
                 .on_menu_event(|app, event| match event.id.as_ref() {
                     "quit" => {
                         println!("quit menu item was clicked");
+                        if let Err(e) = app.state::<FrecencyStore>().save() {
+                            println!("Error saving frecency store: {e}");
+                        }
                         app.exit(0);
                     }
                     _ => {
@@ -90,54 +588,228 @@ pub fn run() {
                 })
                 .build(app)?;
 
-            let ctrl_n_shortcut = Shortcut::new(Some(Modifiers::CONTROL), Code::KeyM);
-
             app.handle().plugin(
                 tauri_plugin_global_shortcut::Builder::new()
                     .with_handler(move |app, shortcut, event| {
-                        let main_window = app.get_webview_window("main").unwrap();
-
-                        if shortcut == &ctrl_n_shortcut {
-                            match event.state() {
-                                ShortcutState::Pressed => match main_window.is_visible() {
-                                    Ok(true) => {
-                                        main_window.hide().expect("failed to hide window");
-                                    }
-                                    Ok(false) => {
-                                        if let Err(e) = get_windows(app) {
-                                            println!("Error refreshing window list: {e}");
-                                        }
-
-                                        if let Err(e) = center_window_in_display(&main_window) {
-                                            println!("Error centering window: {e}");
-                                        }
-
-                                        if let Err(e) = main_window.show() {
-                                            println!("Error showing window: {e}");
-                                        }
-                                        if let Err(e) = main_window.set_focus() {
-                                            println!("Error focusing window: {e}");
-                                        }
-                                    }
-                                    Err(e) => {
-                                        println!("Error checking window visibility: {e}");
-                                    }
-                                },
-                                _ => {}
-                            }
+                        if !matches!(event.state(), ShortcutState::Pressed) {
+                            return;
+                        }
+
+                        if let Some(action) = app.state::<HotkeyManager>().action(shortcut) {
+                            run_hotkey_action(app, action);
                         }
                     })
                     .build(),
             )?;
 
-            match app.global_shortcut().register(ctrl_n_shortcut) {
-                Ok(_) => println!("registered global shortcut"),
-                Err(e) => println!("failed to register global shortcut: {e}"),
+            let config_manager = app.state::<ConfigManager>();
+            apply_config(app.handle(), &config_manager.get(), None);
+
+            let app_handle = app.handle().clone();
+            if let Err(e) = config_manager.watch(move || reload_config(&app_handle)) {
+                println!("Error watching config: {e}");
             }
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![focus_window, close_window])
+        .invoke_handler(command_handler())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tauri::ipc::{CallbackFn, InvokeBody};
+    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime, INVOKE_KEY};
+    use tauri::webview::InvokeRequest;
+    use window::FakeBackend;
+
+    static NEXT_APP: AtomicU32 = AtomicU32::new(0);
+
+    /// An app on the sample windows with the main window open, keeping its
+    /// config and frecency store in a directory of its own.
+    fn app() -> (tauri::App<MockRuntime>, tauri::WebviewWindow<MockRuntime>) {
+        let dir = std::env::temp_dir().join(format!(
+            "binocular-commands-{}-{}",
+            std::process::id(),
+            NEXT_APP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let app = mock_builder()
+            .manage(WindowManager::new(Box::new(
+                FakeBackend::with_sample_windows(),
+            )))
+            .manage(HotkeyManager::default())
+            .manage(FrecencyStore::load(dir.join("frecency.json")))
+            .manage(ConfigManager::load(dir.join("config.toml")))
+            .invoke_handler(command_handler())
+            .build(mock_context(noop_assets()))
+            .unwrap();
+        app.state::<WindowManager>().refresh_window_list();
+
+        let main_window = tauri::WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .unwrap();
+        (app, main_window)
+    }
+
+    fn invoke<T: DeserializeOwned>(
+        main_window: &tauri::WebviewWindow<MockRuntime>,
+        command: &str,
+        args: serde_json::Value,
+    ) -> Result<T, serde_json::Value> {
+        tauri::test::get_ipc_response(
+            main_window,
+            InvokeRequest {
+                cmd: command.into(),
+                callback: CallbackFn(0),
+                error: CallbackFn(1),
+                url: "http://tauri.localhost".parse().unwrap(),
+                body: InvokeBody::Json(args),
+                headers: Default::default(),
+                invoke_key: INVOKE_KEY.to_string(),
+            },
+        )
+        .map(|body| body.deserialize().unwrap())
+    }
+
+    fn snapshot(main_window: &tauri::WebviewWindow<MockRuntime>) -> Vec<Window> {
+        invoke(main_window, "get_window_snapshot", serde_json::json!({})).unwrap()
+    }
+
+    #[test]
+    fn snapshot_lists_the_windows() {
+        let (_app, main_window) = app();
+
+        let mut titles: Vec<_> = snapshot(&main_window)
+            .into_iter()
+            .map(|window| window.title)
+            .collect();
+        titles.sort();
+
+        assert_eq!(
+            titles,
+            [
+                "DevTools - Google Chrome",
+                "GitHub - Google Chrome",
+                "Inbox - Mail",
+                "README.md - binocular - Visual Studio Code",
+                "~/binocular",
+            ]
+        );
+    }
+
+    #[test]
+    fn focusing_counts_a_visit() {
+        let (_app, main_window) = app();
+
+        let terminal = snapshot(&main_window)
+            .into_iter()
+            .find(|window| window.title == "~/binocular")
+            .unwrap();
+        invoke::<()>(
+            &main_window,
+            "focus_window",
+            serde_json::json!({ "window": terminal }),
+        )
+        .unwrap();
+
+        let frecency: Vec<FrecencyEntry> =
+            invoke(&main_window, "get_frecency", serde_json::json!({})).unwrap();
+        assert_eq!(frecency.len(), 1);
+        assert!(snapshot(&main_window)
+            .iter()
+            .any(|window| window.id == terminal.id && window.last_focused.is_some()));
+    }
+
+    #[test]
+    fn closing_removes_the_window_on_refresh() {
+        let (app, main_window) = app();
+        let mail = snapshot(&main_window)
+            .into_iter()
+            .find(|window| window.title == "Inbox - Mail")
+            .unwrap();
+
+        invoke::<()>(
+            &main_window,
+            "close_window",
+            serde_json::json!({ "window": mail }),
+        )
+        .unwrap();
+        app.state::<WindowManager>().refresh_window_list();
+
+        assert!(snapshot(&main_window)
+            .iter()
+            .all(|window| window.id != mail.id));
+    }
+
+    #[test]
+    fn errors_come_back_as_strings() {
+        let (_app, main_window) = app();
+
+        let error = invoke::<()>(
+            &main_window,
+            "window_action",
+            serde_json::json!({ "id": 3, "action": { "set_opacity": 2.0 } }),
+        )
+        .unwrap_err();
+        assert_eq!(error, "Opacity must be between 0 and 1, got 2");
+
+        let error = invoke::<()>(
+            &main_window,
+            "get_process_info",
+            serde_json::json!({ "id": 42 }),
+        )
+        .unwrap_err();
+        assert_eq!(error, "No window with id 42");
+    }
+
+    #[test]
+    fn search_ranks_matching_windows() {
+        let (_app, main_window) = app();
+
+        // Results and groups only go out, so they're read back as plain JSON
+        let results: Vec<serde_json::Value> = invoke(
+            &main_window,
+            "search_windows",
+            serde_json::json!({ "query": "chrome" }),
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result["window"]["title"]
+            .as_str()
+            .is_some_and(|title| title.ends_with("Google Chrome"))));
+    }
+
+    #[test]
+    fn workspaces_and_groups() {
+        let (_app, main_window) = app();
+
+        let workspaces: Vec<Workspace> =
+            invoke(&main_window, "get_workspaces", serde_json::json!({})).unwrap();
+        assert_eq!(
+            workspaces
+                .iter()
+                .map(|workspace| workspace.window_count)
+                .collect::<Vec<_>>(),
+            [4, 1, 0]
+        );
+
+        let groups: Vec<serde_json::Value> = invoke(
+            &main_window,
+            "get_window_groups",
+            serde_json::json!({ "groupBy": "app" }),
+        )
+        .unwrap();
+        let chrome = groups
+            .iter()
+            .find(|group| group["name"] == "Google Chrome")
+            .unwrap();
+        assert_eq!(chrome["count"], 2);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::utils::icon::scale;
+use crate::window::{WindowBackend, WindowId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a capture is shown before the window is captured again.
+const TTL: Duration = Duration::from_secs(2);
+
+struct CachedPreview {
+    captured: Instant,
+    png: Vec<u8>,
+}
+
+/// Recent window thumbnails, so moving through the list doesn't capture the
+/// same window over and over.
+#[derive(Default)]
+pub struct PreviewCache {
+    /// Keyed by window and the size the thumbnail was scaled to fit.
+    previews: Mutex<HashMap<(WindowId, u32, u32), CachedPreview>>,
+}
+
+impl PreviewCache {
+    /// Returns a PNG of the window scaled to fit `max_width` by `max_height`,
+    /// capturing it again once the cached one is older than the TTL.
+    pub fn get(
+        &self,
+        backend: &dyn WindowBackend,
+        id: WindowId,
+        max_width: u32,
+        max_height: u32,
+    ) -> Result<Vec<u8>, String> {
+        let key = (id, max_width, max_height);
+
+        if let Some(cached) = self.previews.lock().unwrap().get(&key) {
+            if cached.captured.elapsed() < TTL {
+                return Ok(cached.png.clone());
+            }
+        }
+
+        // Capturing can be slow, don't hold the lock while it happens
+        let image = backend.capture_window(id)?;
+        let png = scale::encode_png(&scale::fit_within(&image, max_width, max_height))
+            .ok_or_else(|| "Failed to encode the preview".to_string())?;
+
+        let mut previews = self.previews.lock().unwrap();
+        previews.retain(|_, cached| cached.captured.elapsed() < TTL);
+        previews.insert(
+            key,
+            CachedPreview {
+                captured: Instant::now(),
+                png: png.clone(),
+            },
+        );
+
+        Ok(png)
+    }
+
+    /// Drops the previews of a closed window.
+    pub fn forget_window(&self, id: WindowId) {
+        self.previews
+            .lock()
+            .unwrap()
+            .retain(|(window, _, _), _| *window != id);
+    }
+}
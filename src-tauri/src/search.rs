@@ -0,0 +1,215 @@
+use crate::utils::fuzzy::fuzzy_match;
+use crate::window_manager::Window;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Window fields the search query is matched against.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Title,
+    ProcessName,
+    AppName,
+    AppId,
+    WorkspaceName,
+    Output,
+    /// The kind of sandbox, so "flatpak" finds every Flatpak.
+    Sandbox,
+    /// The Flatpak app id or snap name, or the container name.
+    SandboxId,
+}
+
+impl SearchField {
+    pub const DEFAULT: &'static [SearchField] = &[
+        SearchField::Title,
+        SearchField::ProcessName,
+        SearchField::AppName,
+        SearchField::AppId,
+        SearchField::Sandbox,
+    ];
+
+    fn value<'a>(&self, window: &'a Window) -> Option<&'a str> {
+        match self {
+            SearchField::Title => Some(&window.title),
+            SearchField::ProcessName => window.process_name.as_deref(),
+            SearchField::AppName => window.app_name.as_deref(),
+            SearchField::AppId => window.app_id.as_deref(),
+            SearchField::WorkspaceName => window.workspace_name.as_deref(),
+            SearchField::Output => window.output.as_deref(),
+            SearchField::Sandbox => window.sandbox.as_ref().map(|sandbox| sandbox.kind.label()),
+            SearchField::SandboxId => window
+                .sandbox
+                .as_ref()
+                .and_then(|sandbox| sandbox.app_id.as_deref().or(sandbox.container.as_deref())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResult {
+    pub window: Window,
+    pub score: i32,
+    /// Matched character indices per field, for highlighting.
+    pub matches: HashMap<SearchField, Vec<usize>>,
+}
+
+/// Ranks `windows` against `query`, best match first.
+///
+/// Every whitespace separated term has to match at least one of `fields`,
+/// each term is scored against the field it matches best. Windows with equal
+/// scores are ordered by `tie_breaker`, highest first, and keep the order they
+/// came in after that.
+///
+/// An empty or all-whitespace query matches every window with a score of 0 and
+/// no highlights, in the order they came in. The tie breaker isn't consulted,
+/// so the switcher shows the most recently used order until something is typed.
+pub fn search_windows(
+    windows: Vec<Window>,
+    query: &str,
+    fields: &[SearchField],
+    tie_breaker: impl Fn(&Window) -> f64,
+) -> Vec<SearchResult> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+
+    let results: Vec<SearchResult> = windows
+        .into_iter()
+        .filter_map(|window| {
+            let mut score = 0;
+            let mut matches: HashMap<SearchField, Vec<usize>> = HashMap::new();
+
+            for term in &terms {
+                let (field, term_match) = fields
+                    .iter()
+                    .filter_map(|field| {
+                        let value = field.value(&window)?;
+                        Some((*field, fuzzy_match(term, value)?))
+                    })
+                    .max_by_key(|(_, term_match)| term_match.score)?;
+
+                score += term_match.score;
+                matches
+                    .entry(field)
+                    .or_default()
+                    .extend(term_match.positions);
+            }
+
+            for positions in matches.values_mut() {
+                positions.sort_unstable();
+                positions.dedup();
+            }
+
+            Some(SearchResult {
+                window,
+                score,
+                matches,
+            })
+        })
+        .collect();
+
+    if terms.is_empty() {
+        return results;
+    }
+
+    let mut ranked: Vec<(f64, SearchResult)> = results
+        .into_iter()
+        .map(|result| (tie_breaker(&result.window), result))
+        .collect();
+
+    ranked.sort_by(|(a_tie, a), (b_tie, b)| b.score.cmp(&a.score).then(b_tie.total_cmp(a_tie)));
+
+    ranked.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::WindowId;
+
+    fn window(id: WindowId, title: &str, app_name: &str) -> Window {
+        Window {
+            id,
+            title: title.to_string(),
+            app_name: Some(app_name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn windows() -> Vec<Window> {
+        vec![
+            window(1, "Inbox - Mail", "Evolution"),
+            window(2, "README.md - binocular", "Visual Studio Code"),
+            window(3, "binocular/README.md at main - GitHub", "Google Chrome"),
+            window(4, "New Tab", "Google Chrome"),
+        ]
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<WindowId> {
+        results.iter().map(|result| result.window.id).collect()
+    }
+
+    fn no_tie_breaker(_window: &Window) -> f64 {
+        0.0
+    }
+
+    #[test]
+    fn empty_query_keeps_the_order() {
+        let results = search_windows(windows(), "  ", SearchField::DEFAULT, |window| {
+            window.id as f64
+        });
+
+        assert_eq!(ids(&results), [1, 2, 3, 4]);
+        assert!(results
+            .iter()
+            .all(|result| result.score == 0 && result.matches.is_empty()));
+    }
+
+    #[test]
+    fn best_match_first() {
+        let results = search_windows(windows(), "readme", SearchField::DEFAULT, no_tie_breaker);
+
+        // Right at the start of the title beats after a word boundary
+        assert_eq!(ids(&results), [2, 3]);
+        assert_eq!(results[0].matches[&SearchField::Title], [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        let results = search_windows(
+            windows(),
+            "chrome readme",
+            SearchField::DEFAULT,
+            no_tie_breaker,
+        );
+
+        assert_eq!(ids(&results), [3]);
+        let matches = &results[0].matches;
+        assert_eq!(matches[&SearchField::AppName], [7, 8, 9, 10, 11, 12]);
+        assert_eq!(matches[&SearchField::Title].len(), 6);
+    }
+
+    #[test]
+    fn only_the_given_fields() {
+        let results = search_windows(windows(), "chrome", &[SearchField::Title], no_tie_breaker);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn ties_go_to_the_tie_breaker() {
+        let windows = vec![
+            window(1, "Terminal", "Alacritty"),
+            window(2, "Terminal", "Alacritty"),
+            window(3, "Terminal", "Alacritty"),
+        ];
+
+        let results = search_windows(windows, "term", SearchField::DEFAULT, |window| {
+            if window.id == 2 {
+                5.0
+            } else {
+                1.0
+            }
+        });
+
+        // The rest keep the order they came in
+        assert_eq!(ids(&results), [2, 1, 3]);
+    }
+}
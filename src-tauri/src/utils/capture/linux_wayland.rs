@@ -0,0 +1,268 @@
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::AsFd;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use image::RgbaImage;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool};
+use wayland_client::{delegate_noop, Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+};
+
+/// A region of an output, in the output's logical coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// The shm buffer the compositor wants the frame copied into.
+#[derive(Clone, Copy)]
+struct BufferInfo {
+    format: wl_shm::Format,
+    width: u32,
+    height: u32,
+    stride: u32,
+}
+
+#[derive(Default)]
+struct CaptureState {
+    /// Every output along with its name, which needs wl_output v4.
+    outputs: Vec<(wl_output::WlOutput, Option<String>)>,
+    buffer: Option<BufferInfo>,
+    /// All buffer types have been announced, always true before screencopy v3.
+    buffer_done: bool,
+    y_invert: bool,
+    ready: bool,
+    failed: bool,
+}
+
+impl CaptureState {
+    /// The buffer to copy into has been picked and no others are coming.
+    fn buffer_known(&self) -> bool {
+        self.buffer_done && self.buffer.is_some()
+    }
+}
+
+/// Copies `region` of the output called `output_name` using
+/// `wlr-screencopy`, which wlroots compositors and Hyprland implement.
+///
+/// Only captures what's on screen, the caller has to make sure the window is
+/// visible and on top.
+pub fn capture_output_region(output_name: &str, region: Region) -> Result<RgbaImage, String> {
+    let conn = Connection::connect_to_env().map_err(|e| e.to_string())?;
+    let (globals, mut queue) =
+        registry_queue_init::<CaptureState>(&conn).map_err(|e| e.to_string())?;
+    let qh = queue.handle();
+
+    let manager: ZwlrScreencopyManagerV1 = globals
+        .bind(&qh, 1..=3, ())
+        .map_err(|e| format!("Compositor does not support wlr-screencopy: {e}"))?;
+    let shm: wl_shm::WlShm = globals.bind(&qh, 1..=1, ()).map_err(|e| e.to_string())?;
+
+    let mut state = CaptureState::default();
+
+    for global in globals.contents().clone_list() {
+        if global.interface == "wl_output" {
+            let output = globals.registry().bind::<wl_output::WlOutput, _, _>(
+                global.name,
+                global.version.min(4),
+                &qh,
+                state.outputs.len(),
+            );
+            state.outputs.push((output, None));
+        }
+    }
+
+    // Output names arrive in response to the binds
+    queue.roundtrip(&mut state).map_err(|e| e.to_string())?;
+
+    // Without names the only output we can be sure of is the only one there is
+    let output = match state.outputs.as_slice() {
+        [(output, None)] => output.clone(),
+        outputs => outputs
+            .iter()
+            .find(|(_, name)| name.as_deref() == Some(output_name))
+            .map(|(output, _)| output.clone())
+            .ok_or_else(|| format!("No output named {output_name}"))?,
+    };
+
+    let frame = manager.capture_output_region(
+        0,
+        &output,
+        region.x,
+        region.y,
+        region.width,
+        region.height,
+        &qh,
+        (),
+    );
+    state.buffer_done = manager.version() < 3;
+
+    while !state.failed && !state.buffer_known() {
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let Some(info) = state.buffer.filter(|_| !state.failed) else {
+        frame.destroy();
+        return Err("The compositor refused to capture the window".to_string());
+    };
+
+    let size = info.stride * info.height;
+    let file = shm_file(size as u64)?;
+    let pool = shm.create_pool(file.as_fd(), size as i32, &qh, ());
+    let buffer = pool.create_buffer(
+        0,
+        info.width as i32,
+        info.height as i32,
+        info.stride as i32,
+        info.format,
+        &qh,
+        (),
+    );
+
+    frame.copy(&buffer);
+
+    while !state.ready && !state.failed {
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| e.to_string())?;
+    }
+
+    frame.destroy();
+    buffer.destroy();
+    pool.destroy();
+
+    if state.failed {
+        return Err("The compositor failed to capture the window".to_string());
+    }
+
+    let mut data = vec![0u8; size as usize];
+    file.read_exact_at(&mut data, 0)
+        .map_err(|e| e.to_string())?;
+
+    let mut image = to_rgba(&data, info)?;
+    if state.y_invert {
+        image::imageops::flip_vertical_in_place(&mut image);
+    }
+
+    Ok(image)
+}
+
+/// Creates a file for the compositor to copy into, unlinked so it goes away with us.
+fn shm_file(size: u64) -> Result<File, String> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    let path = dir.join(format!("binocular-capture-{}-{nanos}", std::process::id()));
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    let _ = fs::remove_file(&path);
+
+    file.set_len(size).map_err(|e| e.to_string())?;
+    Ok(file)
+}
+
+fn to_rgba(data: &[u8], info: BufferInfo) -> Result<RgbaImage, String> {
+    // wl_shm formats are little-endian, ARGB8888 is stored as B, G, R, A
+    let (red, blue) = match info.format {
+        wl_shm::Format::Argb8888 | wl_shm::Format::Xrgb8888 => (2, 0),
+        wl_shm::Format::Abgr8888 | wl_shm::Format::Xbgr8888 => (0, 2),
+        format => return Err(format!("Unsupported capture format {format:?}")),
+    };
+
+    let rgba = data
+        .chunks_exact(info.stride as usize)
+        .flat_map(|row| row[..info.width as usize * 4].chunks_exact(4))
+        .flat_map(|pixel| [pixel[red], pixel[1], pixel[blue], 255])
+        .collect();
+
+    RgbaImage::from_raw(info.width, info.height, rgba)
+        .ok_or_else(|| "Failed to capture the window".to_string())
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for CaptureState {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for CaptureState {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            state.outputs[*index].1 = Some(name);
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for CaptureState {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_screencopy_frame_v1::Event::Buffer {
+                format: WEnum::Value(format),
+                width,
+                height,
+                stride,
+            } => {
+                // Compositors offer a single shm format, keep the first just in case
+                state.buffer.get_or_insert(BufferInfo {
+                    format,
+                    width,
+                    height,
+                    stride,
+                });
+            }
+            zwlr_screencopy_frame_v1::Event::Flags {
+                flags: WEnum::Value(flags),
+            } => {
+                state.y_invert = flags.contains(zwlr_screencopy_frame_v1::Flags::YInvert);
+            }
+            zwlr_screencopy_frame_v1::Event::BufferDone => state.buffer_done = true,
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => state.ready = true,
+            zwlr_screencopy_frame_v1::Event::Failed => state.failed = true,
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(CaptureState: ignore wl_shm::WlShm);
+delegate_noop!(CaptureState: ignore wl_buffer::WlBuffer);
+delegate_noop!(CaptureState: wl_shm_pool::WlShmPool);
+delegate_noop!(CaptureState: ZwlrScreencopyManagerV1);
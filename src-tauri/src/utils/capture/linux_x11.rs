@@ -0,0 +1,121 @@
+use image::RgbaImage;
+use x11rb::connection::Connection;
+use x11rb::protocol::composite::ConnectionExt as _;
+use x11rb::protocol::xproto::{self, ConnectionExt as _, ImageFormat, ImageOrder, MapState};
+use x11rb::rust_connection::RustConnection;
+
+/// Captures the contents of `window`, a client window managed under `root`.
+///
+/// With a compositing window manager the contents come from the offscreen
+/// copy of the window's frame, so covered windows are captured correctly.
+/// Otherwise whatever is on screen where the window is gets captured.
+pub fn capture_window(
+    conn: &RustConnection,
+    root: xproto::Window,
+    window: xproto::Window,
+) -> Result<RgbaImage, String> {
+    let attributes = conn
+        .get_window_attributes(window)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    if attributes.map_state != MapState::VIEWABLE {
+        return Err("The window isn't visible".to_string());
+    }
+
+    let geometry = conn
+        .get_geometry(window)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    let composited = frame_pixmap(conn, root, window);
+
+    let (drawable, x, y) = match composited {
+        Some((pixmap, x, y)) => (pixmap, x, y),
+        None => (window, 0, 0),
+    };
+
+    let image = conn
+        .get_image(
+            ImageFormat::Z_PIXMAP,
+            drawable,
+            x,
+            y,
+            geometry.width,
+            geometry.height,
+            !0,
+        )
+        .map_err(|e| e.to_string())
+        .and_then(|cookie| cookie.reply().map_err(|e| e.to_string()));
+
+    if let Some((pixmap, _, _)) = composited {
+        let _ = conn.free_pixmap(pixmap);
+    }
+
+    let image = image?;
+
+    // Only the usual 24 and 32 bit TrueColor layouts, which is what every desktop runs
+    let setup = conn.setup();
+    let bits_per_pixel = setup
+        .pixmap_formats
+        .iter()
+        .find(|format| format.depth == image.depth)
+        .map(|format| format.bits_per_pixel);
+
+    if !matches!(image.depth, 24 | 32) || bits_per_pixel != Some(32) {
+        return Err(format!("Unsupported window depth {}", image.depth));
+    }
+
+    let lsb_first = setup.image_byte_order == ImageOrder::LSB_FIRST;
+    let rgba = image
+        .data
+        .chunks_exact(4)
+        .flat_map(|pixel| {
+            let [blue, green, red] = if lsb_first {
+                [pixel[0], pixel[1], pixel[2]]
+            } else {
+                [pixel[3], pixel[2], pixel[1]]
+            };
+            [red, green, blue, 255]
+        })
+        .collect();
+
+    RgbaImage::from_raw(geometry.width as u32, geometry.height as u32, rgba)
+        .ok_or_else(|| "Failed to capture the window".to_string())
+}
+
+/// Names the offscreen pixmap of the frame `window` sits in, along with where
+/// in it the window is. `None` without a compositing window manager.
+fn frame_pixmap(
+    conn: &RustConnection,
+    root: xproto::Window,
+    window: xproto::Window,
+) -> Option<(xproto::Pixmap, i16, i16)> {
+    conn.composite_query_version(0, 2).ok()?.reply().ok()?;
+
+    // Window managers reparent clients into a frame, it's the frame that gets redirected
+    let mut frame = window;
+    loop {
+        let tree = conn.query_tree(frame).ok()?.reply().ok()?;
+        if tree.parent == root || tree.parent == x11rb::NONE {
+            break;
+        }
+        frame = tree.parent;
+    }
+
+    let offset = conn
+        .translate_coordinates(window, frame, 0, 0)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    let pixmap = conn.generate_id().ok()?;
+    conn.composite_name_window_pixmap(frame, pixmap)
+        .ok()?
+        .check()
+        .ok()?;
+
+    Some((pixmap, offset.dst_x, offset.dst_y))
+}
@@ -0,0 +1,79 @@
+use image::RgbaImage;
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::Graphics::Gdi::{
+    CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+    ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, RGBQUAD,
+};
+use windows::Win32::Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS};
+use windows::Win32::UI::WindowsAndMessaging::{GetWindowRect, IsIconic};
+
+/// Also captures DirectComposition content such as browsers, which plain
+/// `PrintWindow` leaves black. Missing from the metadata `windows` is generated from.
+const PW_RENDERFULLCONTENT: PRINT_WINDOW_FLAGS = PRINT_WINDOW_FLAGS(2);
+
+/// Captures the window as it would look on screen, even when it's covered.
+pub unsafe fn capture_window(hwnd: HWND) -> Result<RgbaImage, String> {
+    if IsIconic(hwnd).as_bool() {
+        return Err("Minimized windows can't be captured".to_string());
+    }
+
+    let mut rect = RECT::default();
+    GetWindowRect(hwnd, &mut rect).map_err(|e| e.to_string())?;
+
+    let width = rect.right - rect.left;
+    let height = rect.bottom - rect.top;
+    if width <= 0 || height <= 0 {
+        return Err("The window has no size".to_string());
+    }
+
+    let hdc = GetDC(hwnd);
+    let hdc_mem = CreateCompatibleDC(hdc);
+    let h_bitmap = CreateCompatibleBitmap(hdc, width, height);
+    let old_bitmap = SelectObject(hdc_mem, h_bitmap);
+    ReleaseDC(hwnd, hdc);
+
+    let printed = PrintWindow(hwnd, hdc_mem, PW_RENDERFULLCONTENT).as_bool();
+
+    let mut buffer = vec![0u8; (width * height * 4) as usize];
+    let mut bitmap_info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height, // Negative for top-down
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        bmiColors: [RGBQUAD::default()],
+    };
+
+    let read = printed
+        && GetDIBits(
+            hdc_mem,
+            h_bitmap,
+            0,
+            height as u32,
+            Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut bitmap_info,
+            DIB_RGB_COLORS,
+        ) != 0;
+
+    // Clean up
+    SelectObject(hdc_mem, old_bitmap);
+    let _ = DeleteObject(h_bitmap);
+    let _ = DeleteDC(hdc_mem);
+
+    if !read {
+        return Err("Failed to capture the window".to_string());
+    }
+
+    // BGRX to RGBA, the fourth byte isn't alpha for window contents
+    for pixel in buffer.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        pixel[3] = 255;
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, buffer)
+        .ok_or_else(|| "Failed to capture the window".to_string())
+}
@@ -1,5 +0,0 @@
-use tauri::WebviewWindow;
-
-pub fn center_window_in_display(main_window: &WebviewWindow) -> Result<(), String> {
-    Ok(())
-}
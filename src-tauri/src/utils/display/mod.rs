@@ -3,7 +3,25 @@ mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::*;
 
-#[cfg(target_os = "macos")]
-mod macos;
-#[cfg(target_os = "macos")]
-pub use macos::*;
+use crate::window_manager::WindowManager;
+use tauri::{PhysicalPosition, Position, WebviewWindow};
+
+pub fn center_window_in_display(
+    main_window: &WebviewWindow,
+    window_manager: &WindowManager,
+) -> Result<(), String> {
+    // Move onto the monitor holding the foreground window first so that
+    // center() picks that monitor
+    if let Some(monitor) = window_manager.get_active_monitor() {
+        let center_x = monitor.x + monitor.width as i32 / 2;
+        let center_y = monitor.y + monitor.height as i32 / 2;
+
+        main_window
+            .set_position(Position::Physical(PhysicalPosition::new(
+                center_x, center_y,
+            )))
+            .map_err(|e| e.to_string())?;
+    }
+
+    main_window.center().map_err(|e| e.to_string())
+}
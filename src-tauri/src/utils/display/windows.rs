@@ -1,43 +1,108 @@
-use tauri::{PhysicalPosition, Position, WebviewWindow};
+use crate::window::{Monitor, MonitorGeometry};
 use windows::Win32::{
-    Foundation::RECT,
-    Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST},
-    UI::WindowsAndMessaging::GetForegroundWindow,
+    Foundation::{BOOL, HWND, LPARAM, RECT, TRUE},
+    Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, HDC, HMONITOR, MONITORINFO,
+        MONITORINFOEXW, MONITORINFOF_PRIMARY, MONITOR_DEFAULTTONEAREST,
+    },
+    UI::{
+        HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+        WindowsAndMessaging::{GetForegroundWindow, USER_DEFAULT_SCREEN_DPI},
+    },
 };
 
-pub fn center_window_in_display(main_window: &WebviewWindow) -> Result<(), String> {
+/// Every display on the desktop, the one holding the foreground window is active.
+pub fn get_monitors() -> Vec<Monitor> {
+    let mut handles: Vec<HMONITOR> = Vec::new();
+
     unsafe {
-        // Get the currently active window
-        let foreground_window = GetForegroundWindow();
-        if foreground_window.0 == 0 {
-            return Err("Failed to get foreground window".to_string());
+        if !EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(enum_monitor_proc),
+            LPARAM(&mut handles as *mut _ as isize),
+        )
+        .as_bool()
+        {
+            println!("Failed to enumerate monitors");
+            return Vec::new();
         }
 
-        // Get the monitor that contains this window
-        let monitor = MonitorFromWindow(foreground_window, MONITOR_DEFAULTTONEAREST);
+        let foreground_window = GetForegroundWindow();
+        let active = (foreground_window.0 != 0)
+            .then(|| MonitorFromWindow(foreground_window, MONITOR_DEFAULTTONEAREST));
+
+        handles
+            .into_iter()
+            .filter_map(|handle| {
+                let info = get_monitor_info(handle)?;
+                let mut dpi_x = USER_DEFAULT_SCREEN_DPI;
+                let mut dpi_y = USER_DEFAULT_SCREEN_DPI;
+                let _ = GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y);
+
+                Some(Monitor {
+                    name: device_name(&info),
+                    geometry: to_geometry(info.monitorInfo.rcMonitor),
+                    work_area: to_geometry(info.monitorInfo.rcWork),
+                    scale: dpi_x as f64 / USER_DEFAULT_SCREEN_DPI as f64,
+                    primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+                    active: active == Some(handle),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Device name of the monitor most of the window is on.
+pub fn get_window_monitor(hwnd: HWND) -> Option<String> {
+    unsafe {
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
         if monitor.is_invalid() {
-            return Err("Failed to get monitor".to_string());
-        }
-        // Get monitor information
-        let mut monitor_info = MONITORINFO {
-            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
-            rcMonitor: RECT::default(),
-            rcWork: RECT::default(),
-            dwFlags: 0,
-        };
-
-        if GetMonitorInfoW(monitor, &mut monitor_info).as_bool() {
-            let center_x = (monitor_info.rcMonitor.left + monitor_info.rcMonitor.right) / 2;
-            let center_y = (monitor_info.rcMonitor.top + monitor_info.rcMonitor.bottom) / 2;
-
-            main_window
-                .set_position(Position::Physical(PhysicalPosition::new(
-                    center_x, center_y,
-                )))
-                .unwrap();
-            main_window.center().unwrap();
+            return None;
         }
 
-        Ok(())
+        get_monitor_info(monitor).map(|info| device_name(&info))
+    }
+}
+
+unsafe extern "system" fn enum_monitor_proc(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let handles = &mut *(lparam.0 as *mut Vec<HMONITOR>);
+    handles.push(monitor);
+    TRUE
+}
+
+unsafe fn get_monitor_info(monitor: HMONITOR) -> Option<MONITORINFOEXW> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+
+    GetMonitorInfoW(
+        monitor,
+        &mut info as *mut MONITORINFOEXW as *mut MONITORINFO,
+    )
+    .as_bool()
+    .then_some(info)
+}
+
+/// Like `\\.\DISPLAY1`, the same name the display settings go by.
+fn device_name(info: &MONITORINFOEXW) -> String {
+    let len = info
+        .szDevice
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(info.szDevice.len());
+    String::from_utf16_lossy(&info.szDevice[..len])
+}
+
+fn to_geometry(rect: RECT) -> MonitorGeometry {
+    MonitorGeometry {
+        x: rect.left,
+        y: rect.top,
+        width: (rect.right - rect.left) as u32,
+        height: (rect.bottom - rect.top) as u32,
     }
 }
@@ -0,0 +1,436 @@
+use super::{data_dirs, parse_key_file};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the scanned entries are reused, so newly installed applications
+/// still show up without a rescan for every window.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Programs that run many different applications, so running one tells
+/// nothing about which application a process is. Version suffixes are ignored.
+const INTERPRETERS: [&str; 15] = [
+    "bash", "dash", "electron", "gjs", "java", "lua", "mono", "node", "nodejs", "perl", "php",
+    "python", "ruby", "sh", "zsh",
+];
+
+static ENTRIES: Mutex<Option<(Instant, Arc<Vec<DesktopEntry>>)>> = Mutex::new(None);
+
+/// The parts of an application's `.desktop` file we use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesktopEntry {
+    /// Desktop file id, e.g. "org.mozilla.firefox" for `org.mozilla.firefox.desktop`.
+    pub id: String,
+    pub path: PathBuf,
+    /// Translated to the user's language when the entry has a translation.
+    pub name: Option<String>,
+    /// Icon theme name or absolute path of the icon.
+    pub icon: Option<String>,
+    /// The WM_CLASS or app id the application's windows have, when it differs from the id.
+    pub startup_wm_class: Option<String>,
+    /// File name of the program it runs, from `TryExec` or `Exec`.
+    pub executable: Option<String>,
+}
+
+/// Finds the desktop entry of a window's application, by the window class or
+/// app id first and by the executable the process runs otherwise.
+pub fn resolve(app_id: Option<&str>, exe_path: Option<&str>) -> Option<DesktopEntry> {
+    app_id
+        .and_then(find)
+        .or_else(|| find_by_executable(exe_path?))
+}
+
+/// Finds the desktop entry of the application with window class or app id `app_id`.
+///
+/// Prefers the entry named after it, then one whose reverse-DNS id ends in
+/// it, then one claiming it through `StartupWMClass`, ignoring case throughout.
+pub fn find(app_id: &str) -> Option<DesktopEntry> {
+    find_in(&cached_entries(), app_id).cloned()
+}
+
+fn find_in<'a>(entries: &'a [DesktopEntry], app_id: &str) -> Option<&'a DesktopEntry> {
+    let by_id = || {
+        entries
+            .iter()
+            .find(|entry| entry.id.eq_ignore_ascii_case(app_id))
+    };
+    let by_last_component = || {
+        entries.iter().find(|entry| {
+            entry
+                .id
+                .rsplit_once('.')
+                .is_some_and(|(_, last)| last.eq_ignore_ascii_case(app_id))
+        })
+    };
+    let by_wm_class = || {
+        entries.iter().find(|entry| {
+            entry
+                .startup_wm_class
+                .as_deref()
+                .is_some_and(|class| class.eq_ignore_ascii_case(app_id))
+        })
+    };
+
+    by_id().or_else(by_last_component).or_else(by_wm_class)
+}
+
+/// Finds the desktop entry of the snap called `name`.
+///
+/// Snaps export theirs as `<snap>_<app>.desktop`, the app named after the
+/// snap is preferred when there are several.
+pub fn find_snap(name: &str) -> Option<DesktopEntry> {
+    let entries = cached_entries();
+    let main_app = format!("{name}_{name}");
+    let prefix = format!("{name}_");
+
+    entries
+        .iter()
+        .find(|entry| entry.id == main_app)
+        .or_else(|| entries.iter().find(|entry| entry.id.starts_with(&prefix)))
+        .cloned()
+}
+
+/// Finds the desktop entry that launches `exe_path`.
+///
+/// Launchers are often symlinks or wrapper scripts named after the program,
+/// so either the file names or the resolved paths have to match. Interpreters
+/// such as python3 or electron never match, whatever they run.
+pub fn find_by_executable(exe_path: &str) -> Option<DesktopEntry> {
+    find_by_executable_in(&cached_entries(), exe_path).cloned()
+}
+
+fn find_by_executable_in<'a>(
+    entries: &'a [DesktopEntry],
+    exe_path: &str,
+) -> Option<&'a DesktopEntry> {
+    let exe_path = Path::new(exe_path);
+    let file_name = exe_path.file_name()?;
+
+    if is_interpreter(&file_name.to_string_lossy()) {
+        return None;
+    }
+
+    entries.iter().find(|entry| {
+        entry.executable.as_deref().is_some_and(|executable| {
+            let executable = Path::new(executable);
+            executable.file_name() == Some(file_name)
+                || (executable.is_absolute()
+                    && fs::canonicalize(executable).is_ok_and(|path| path == exe_path))
+        })
+    })
+}
+
+/// Whether `program` is one of `INTERPRETERS`, e.g. "python3.12" or "electron28".
+fn is_interpreter(program: &str) -> bool {
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS.contains(&name)
+}
+
+/// `all_entries`, scanned again once `RESCAN_INTERVAL` has passed.
+fn cached_entries() -> Arc<Vec<DesktopEntry>> {
+    let mut cached = ENTRIES.lock().unwrap();
+
+    match cached.as_ref() {
+        Some((scanned, entries)) if scanned.elapsed() < RESCAN_INTERVAL => entries.clone(),
+        _ => {
+            let entries = Arc::new(all_entries());
+            *cached = Some((Instant::now(), entries.clone()));
+            entries
+        }
+    }
+}
+
+/// Every application installed, an id found in an earlier data dir hides later ones.
+pub fn all_entries() -> Vec<DesktopEntry> {
+    entries_in(&data_dirs())
+}
+
+fn entries_in(data_dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+
+    for dir in data_dirs {
+        let applications = dir.join("applications");
+
+        for path in desktop_files(&applications) {
+            let Some(id) = desktop_file_id(&applications, &path) else {
+                continue;
+            };
+
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+
+            if let Some(entry) = read_entry(id, &path) {
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
+
+fn read_entry(id: String, path: &Path) -> Option<DesktopEntry> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut groups = parse_key_file(&contents);
+    let mut keys = groups.remove("Desktop Entry")?;
+
+    // Hidden means the user deleted it, its id still hides entries in later data dirs
+    if keys.get("Hidden").is_some_and(|hidden| hidden == "true") {
+        return None;
+    }
+
+    let executable = keys
+        .get("TryExec")
+        .or_else(|| keys.get("Exec"))
+        .and_then(|exec| program(exec));
+
+    Some(DesktopEntry {
+        id,
+        path: path.to_path_buf(),
+        name: localized(&keys, "Name"),
+        icon: keys.remove("Icon").filter(|icon| !icon.is_empty()),
+        startup_wm_class: keys.remove("StartupWMClass"),
+        executable,
+    })
+}
+
+/// Picks the translation of `key` for the user's locale, e.g. `Name[de_AT]`,
+/// then `Name[de]`, falling back to the untranslated value.
+fn localized(keys: &HashMap<String, String>, key: &str) -> Option<String> {
+    let setting = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|setting| !setting.is_empty())
+        .unwrap_or_default();
+
+    // Strip the encoding and modifier, "de_AT.UTF-8@euro" is looked up as "de_AT" and "de"
+    let locale = setting.split(['.', '@']).next().unwrap_or_default();
+    let language = locale.split('_').next().unwrap_or_default();
+
+    let translated = [locale, language]
+        .into_iter()
+        .filter(|locale| !locale.is_empty() && *locale != "C" && *locale != "POSIX")
+        .find_map(|locale| keys.get(&format!("{key}[{locale}]")));
+
+    translated.or_else(|| keys.get(key)).cloned()
+}
+
+/// The program an `Exec` line runs, looking through `env VAR=value` prefixes.
+///
+/// `None` for `flatpak run`, the sandboxed application never runs as `flatpak`
+/// so every Flatpak would match it otherwise.
+fn program(exec: &str) -> Option<String> {
+    let mut arguments = split_exec(exec).into_iter();
+    let mut program = arguments.next()?;
+
+    if program == "env" || program.ends_with("/env") {
+        program = arguments.find(|argument| !argument.contains('='))?;
+    }
+
+    if program == "flatpak" || program.ends_with("/flatpak") {
+        return None;
+    }
+
+    Some(program)
+}
+
+/// Splits an `Exec` line into arguments, only double quotes group them.
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => current.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    arguments.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        arguments.push(current);
+    }
+
+    arguments
+}
+
+fn desktop_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for path in read_dir.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            files.extend(desktop_files(&path));
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "desktop")
+        {
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+/// Files in subdirectories get the directory names as a prefix, `kde4/konsole.desktop` is `kde4-konsole`.
+fn desktop_file_id(applications: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(applications).ok()?.with_extension("");
+    let parts: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+
+    Some(parts.join("-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_lines_split_on_unquoted_whitespace() {
+        assert_eq!(
+            split_exec(r#""/opt/My App/app"  --name "say \"hi\"" %U"#),
+            ["/opt/My App/app", "--name", "say \"hi\"", "%U"]
+        );
+        assert!(split_exec("   ").is_empty());
+    }
+
+    #[test]
+    fn program_looks_through_env_but_not_flatpak() {
+        assert_eq!(
+            program("env GDK_BACKEND=x11 MOZ_X=1 firefox %u").as_deref(),
+            Some("firefox")
+        );
+        assert_eq!(program("/usr/bin/env LANG=C").as_deref(), None);
+        assert_eq!(program("/usr/bin/flatpak run org.gimp.GIMP %U"), None);
+        assert_eq!(program("").as_deref(), None);
+    }
+
+    #[test]
+    fn ids_prefix_subdirectories() {
+        let applications = Path::new("/usr/share/applications");
+
+        assert_eq!(
+            desktop_file_id(
+                applications,
+                &applications.join("org.gnome.Nautilus.desktop")
+            )
+            .as_deref(),
+            Some("org.gnome.Nautilus")
+        );
+        assert_eq!(
+            desktop_file_id(applications, &applications.join("kde4/konsole.desktop")).as_deref(),
+            Some("kde4-konsole")
+        );
+        assert_eq!(
+            desktop_file_id(applications, Path::new("/opt/app/app.desktop")),
+            None
+        );
+    }
+
+    #[test]
+    fn interpreters_are_told_apart_from_applications() {
+        for program in ["python3", "python3.12", "electron28", "sh", "java"] {
+            assert!(is_interpreter(program), "{program}");
+        }
+        for program in ["firefox", "shotwell", "nodepad", "gimp-2.10"] {
+            assert!(!is_interpreter(program), "{program}");
+        }
+    }
+
+    #[test]
+    fn scans_and_matches_entries_in_data_dirs() {
+        let base =
+            std::env::temp_dir().join(format!("binocular-applications-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let files = [
+            (
+                "local/applications/org.mozilla.firefox.desktop",
+                "[Desktop Entry]\nName=Firefox\nIcon=firefox\nStartupWMClass=Navigator\n\
+                 Exec=env MOZ_ENABLE_WAYLAND=1 /usr/lib/firefox/firefox %u\n",
+            ),
+            (
+                "local/applications/removed.desktop",
+                "[Desktop Entry]\nHidden=true\n",
+            ),
+            (
+                "system/applications/removed.desktop",
+                "[Desktop Entry]\nName=Removed\nExec=removed\n",
+            ),
+            (
+                "system/applications/kde4/konsole.desktop",
+                "[Desktop Entry]\nName=Konsole\nTryExec=konsole\nExec=konsole --new-tab\n",
+            ),
+            (
+                "system/applications/org.gimp.GIMP.desktop",
+                "[Desktop Entry]\nName=GIMP\nExec=flatpak run org.gimp.GIMP %U\n",
+            ),
+            (
+                "system/applications/converter.desktop",
+                "[Desktop Entry]\nName=Converter\nExec=python3 /usr/share/converter/main.py\n",
+            ),
+            (
+                "system/applications/notes.txt",
+                "[Desktop Entry]\nName=Notes\n",
+            ),
+        ];
+        for (path, contents) in files {
+            let path = base.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        let entries = entries_in(&[base.join("local"), base.join("system")]);
+        let mut ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            [
+                "converter",
+                "kde4-konsole",
+                "org.gimp.GIMP",
+                "org.mozilla.firefox"
+            ]
+        );
+
+        let find = |app_id| Some(find_in(&entries, app_id)?.id.as_str());
+        assert_eq!(find("org.mozilla.firefox"), Some("org.mozilla.firefox"));
+        assert_eq!(find("Firefox"), Some("org.mozilla.firefox"));
+        assert_eq!(find("navigator"), Some("org.mozilla.firefox"));
+        assert_eq!(find("gimp"), Some("org.gimp.GIMP"));
+        assert_eq!(find("konsole"), None);
+
+        let firefox = find_in(&entries, "firefox").unwrap();
+        assert_eq!(firefox.icon.as_deref(), Some("firefox"));
+        assert_eq!(
+            firefox.executable.as_deref(),
+            Some("/usr/lib/firefox/firefox")
+        );
+
+        let find_by_executable =
+            |exe_path| Some(find_by_executable_in(&entries, exe_path)?.id.as_str());
+        assert_eq!(find_by_executable("/usr/bin/konsole"), Some("kde4-konsole"));
+        assert_eq!(
+            find_by_executable("/usr/lib/firefox/firefox"),
+            Some("org.mozilla.firefox")
+        );
+        assert_eq!(find_by_executable("/usr/bin/python3"), None);
+        assert_eq!(find_by_executable("/usr/bin/flatpak"), None);
+
+        fs::remove_dir_all(base).unwrap();
+    }
+}
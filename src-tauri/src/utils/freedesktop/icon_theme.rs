@@ -0,0 +1,354 @@
+//! Icon lookup following the freedesktop.org icon theme spec.
+
+use super::{config_home, data_dirs, home_dir, parse_key_file};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Formats we can load, in order of preference.
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+/// The user's theme by name, loaded together with the themes it falls back to.
+static THEMES: Mutex<Option<(String, Arc<Vec<Theme>>)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// A subdirectory of a theme holding icons of one size, e.g. `48x48/apps`.
+#[derive(Debug)]
+struct Directory {
+    path: String,
+    /// Sizes in pixels, already multiplied by the directory's scale.
+    size: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    kind: DirectoryType,
+}
+
+impl Directory {
+    fn parse(path: &str, keys: &HashMap<String, String>) -> Option<Self> {
+        let number = |key: &str| keys.get(key).and_then(|value| value.parse::<u32>().ok());
+
+        let scale = number("Scale").unwrap_or(1);
+        let size = number("Size")?;
+        let kind = match keys.get("Type").map(String::as_str) {
+            Some("Fixed") => DirectoryType::Fixed,
+            Some("Scalable") => DirectoryType::Scalable,
+            _ => DirectoryType::Threshold,
+        };
+
+        Some(Self {
+            path: path.to_string(),
+            size: size * scale,
+            min_size: number("MinSize").unwrap_or(size) * scale,
+            max_size: number("MaxSize").unwrap_or(size) * scale,
+            threshold: number("Threshold").unwrap_or(2) * scale,
+            kind,
+        })
+    }
+
+    /// How far off `size` this directory's icons are, 0 if they fit.
+    fn distance(&self, size: u32) -> u32 {
+        let (min, max) = match self.kind {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+
+        min.saturating_sub(size) + size.saturating_sub(max)
+    }
+}
+
+#[derive(Debug)]
+struct Theme {
+    /// The theme's directory in every base dir it's installed in.
+    roots: Vec<PathBuf>,
+    directories: Vec<Directory>,
+}
+
+impl Theme {
+    fn load(name: &str, bases: &[PathBuf]) -> Option<(Self, Vec<String>)> {
+        let roots: Vec<PathBuf> = bases
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+
+        let index = roots
+            .iter()
+            .find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+        let groups = parse_key_file(&index);
+        let theme = groups.get("Icon Theme")?;
+
+        let list = |key: &str| -> Vec<String> {
+            theme
+                .get(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let directories = list("Directories")
+            .into_iter()
+            .chain(list("ScaledDirectories"))
+            .filter_map(|path| Directory::parse(&path, groups.get(&path)?))
+            .collect();
+
+        Some((Self { roots, directories }, list("Inherits")))
+    }
+
+    /// Finds `name` in this theme alone, the closest size if there's no exact match.
+    fn lookup(&self, name: &str, size: u32) -> Option<PathBuf> {
+        let mut best: Option<(PathBuf, (u32, bool))> = None;
+
+        for directory in &self.directories {
+            // Prefer scaling down over scaling up when two sizes are equally far off
+            let rank = (directory.distance(size), directory.max_size < size);
+            if best
+                .as_ref()
+                .is_some_and(|(_, best_rank)| *best_rank <= rank)
+            {
+                continue;
+            }
+
+            let found = self.roots.iter().find_map(|root| {
+                EXTENSIONS
+                    .iter()
+                    .map(|extension| {
+                        root.join(&directory.path)
+                            .join(format!("{name}.{extension}"))
+                    })
+                    .find(|path| path.is_file())
+            });
+
+            if let Some(path) = found {
+                if rank.0 == 0 {
+                    return Some(path);
+                }
+                best = Some((path, rank));
+            }
+        }
+
+        best.map(|(path, _)| path)
+    }
+}
+
+/// Finds the file of the icon called `name`, as close to `size` pixels as the
+/// themes have it.
+///
+/// Looks in the user's icon theme, the themes it inherits from and hicolor,
+/// then the unthemed icons in `pixmaps`. Until `reload_theme` runs, the user's
+/// theme is the one in the GTK or KDE settings files.
+pub fn lookup(name: &str, size: u32) -> Option<PathBuf> {
+    let themes = THEMES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| {
+            let theme = settings_theme().unwrap_or_else(|| "hicolor".to_string());
+            let themes = load_themes(&theme, &base_dirs());
+            (theme, Arc::new(themes))
+        })
+        .1
+        .clone();
+
+    themes
+        .iter()
+        .find_map(|theme| theme.lookup(name, size))
+        .or_else(|| {
+            data_dirs().into_iter().find_map(|dir| {
+                EXTENSIONS
+                    .iter()
+                    .map(|extension| dir.join("pixmaps").join(format!("{name}.{extension}")))
+                    .find(|path| path.is_file())
+            })
+        })
+}
+
+/// Checks which icon theme the desktop uses and loads it if it changed,
+/// returning whether it did.
+///
+/// Asks gsettings, which is too slow to do on every lookup, so this runs when
+/// the config is (re)loaded.
+pub fn reload_theme() -> bool {
+    let theme = current_theme().unwrap_or_else(|| "hicolor".to_string());
+    if THEMES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|(loaded, _)| *loaded == theme)
+    {
+        return false;
+    }
+
+    let themes = Arc::new(load_themes(&theme, &base_dirs()));
+    *THEMES.lock().unwrap() = Some((theme, themes));
+    true
+}
+
+/// `theme` followed by everything it inherits from, breadth first, and hicolor.
+fn load_themes(theme: &str, bases: &[PathBuf]) -> Vec<Theme> {
+    let mut names = vec![theme.to_string()];
+    let mut themes = Vec::new();
+    let mut next = 0;
+
+    while let Some(name) = names.get(next).cloned() {
+        next += 1;
+
+        if let Some((theme, inherits)) = Theme::load(&name, bases) {
+            themes.push(theme);
+
+            for parent in inherits {
+                if !names.contains(&parent) {
+                    names.push(parent);
+                }
+            }
+        }
+
+        // Every theme implicitly falls back to hicolor, so it goes last
+        if next == names.len() && !names.iter().any(|name| name == "hicolor") {
+            names.push("hicolor".to_string());
+        }
+    }
+
+    themes
+}
+
+/// Directories themes are installed in, most important first.
+fn base_dirs() -> Vec<PathBuf> {
+    home_dir()
+        .map(|home| home.join(".icons"))
+        .into_iter()
+        .chain(data_dirs().into_iter().map(|dir| dir.join("icons")))
+        .collect()
+}
+
+/// The icon theme the desktop is using, there's no standard place for it so
+/// ask GNOME first and then read the GTK and KDE settings files.
+fn current_theme() -> Option<String> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+
+    output
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .trim_matches('\'')
+                .to_string()
+        })
+        .filter(|theme| !theme.is_empty())
+        .or_else(settings_theme)
+}
+
+/// The icon theme set in the GTK or KDE settings files.
+fn settings_theme() -> Option<String> {
+    let setting = |file: &str, group: &str, key: &str| {
+        let contents = fs::read_to_string(config_home()?.join(file)).ok()?;
+        let groups = parse_key_file(&contents);
+        groups
+            .get(group)?
+            .get(key)
+            .filter(|theme| !theme.is_empty())
+            .cloned()
+    };
+
+    setting("gtk-4.0/settings.ini", "Settings", "gtk-icon-theme-name")
+        .or_else(|| setting("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name"))
+        .or_else(|| setting("kdeglobals", "Icons", "Theme"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `files` below a new temporary directory, empty unless given contents.
+    fn fixture(files: &[(&str, &str)]) -> PathBuf {
+        let base = std::env::temp_dir().join(format!("binocular-icons-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+
+        for (path, contents) in files {
+            let path = base.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        base
+    }
+
+    #[test]
+    fn finds_icons_in_the_theme_its_parents_and_hicolor() {
+        let base = fixture(&[
+            (
+                "Fixture/index.theme",
+                "[Icon Theme]\nName=Fixture\nInherits=Parent\n\
+                 Directories=16x16/apps,48x48/apps,scalable/apps\n\n\
+                 [16x16/apps]\nSize=16\n\n\
+                 [48x48/apps]\nSize=48\n\n\
+                 [scalable/apps]\nSize=64\nType=Scalable\nMinSize=8\nMaxSize=512\n",
+            ),
+            ("Fixture/16x16/apps/terminal.png", ""),
+            ("Fixture/48x48/apps/terminal.png", ""),
+            ("Fixture/scalable/apps/editor.svg", ""),
+            (
+                "Parent/index.theme",
+                "[Icon Theme]\nDirectories=32x32/apps\n\n[32x32/apps]\nSize=32\n",
+            ),
+            ("Parent/32x32/apps/terminal.png", ""),
+            ("Parent/32x32/apps/browser.png", ""),
+            (
+                "hicolor/index.theme",
+                "[Icon Theme]\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\n",
+            ),
+            ("hicolor/48x48/apps/files.png", ""),
+        ]);
+        let themes = load_themes("Fixture", std::slice::from_ref(&base));
+        let lookup = |name: &str, size| {
+            let path = themes.iter().find_map(|theme| theme.lookup(name, size))?;
+            Some(path.strip_prefix(&base).unwrap().to_str()?.to_string())
+        };
+
+        assert_eq!(themes.len(), 3);
+        assert_eq!(
+            lookup("terminal", 16).unwrap(),
+            "Fixture/16x16/apps/terminal.png"
+        );
+        assert_eq!(
+            lookup("terminal", 17).unwrap(),
+            "Fixture/16x16/apps/terminal.png"
+        );
+        // Equally far off, scaling down wins
+        assert_eq!(
+            lookup("terminal", 32).unwrap(),
+            "Fixture/48x48/apps/terminal.png"
+        );
+        assert_eq!(
+            lookup("editor", 256).unwrap(),
+            "Fixture/scalable/apps/editor.svg"
+        );
+        assert_eq!(
+            lookup("browser", 16).unwrap(),
+            "Parent/32x32/apps/browser.png"
+        );
+        assert_eq!(lookup("files", 24).unwrap(), "hicolor/48x48/apps/files.png");
+        assert_eq!(lookup("missing", 16), None);
+
+        fs::remove_dir_all(base).unwrap();
+    }
+}
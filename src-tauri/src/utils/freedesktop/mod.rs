@@ -0,0 +1,125 @@
+//! Lookups following the freedesktop.org specs: base directories, desktop
+//! entries and icon themes.
+
+pub mod desktop_entry;
+pub mod icon_theme;
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+/// Group names mapped to their keys, as found in `.desktop` and `index.theme` files.
+pub type KeyFile = HashMap<String, HashMap<String, String>>;
+
+/// Parses the ini-like key file format desktop entries and icon themes share.
+pub fn parse_key_file(contents: &str) -> KeyFile {
+    let mut groups = KeyFile::new();
+    let mut group = None;
+
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            groups.entry(name.to_string()).or_default();
+            group = Some(name.to_string());
+            continue;
+        }
+
+        let (Some(group), Some((key, value))) = (&group, line.split_once('=')) else {
+            continue;
+        };
+
+        if let Some(keys) = groups.get_mut(group) {
+            keys.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    groups
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$XDG_CONFIG_HOME`, `~/.config` when it isn't set.
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(home_dir()?.join(".config")))
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, most important first.
+///
+/// Flatpak and Snap export their applications and icons to directories of
+/// their own. Sessions usually add them to `$XDG_DATA_DIRS`, they come last
+/// when they didn't.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(home_dir()?.join(".local/share")));
+
+    let data_dirs = env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    let mut dirs: Vec<PathBuf> = data_home
+        .into_iter()
+        .chain(env::split_paths(&data_dirs))
+        .collect();
+
+    let exports = home_dir()
+        .map(|home| home.join(".local/share/flatpak/exports/share"))
+        .into_iter()
+        .chain([
+            PathBuf::from("/var/lib/flatpak/exports/share"),
+            PathBuf::from("/var/lib/snapd/desktop"),
+        ]);
+
+    for dir in exports {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_files_parse_into_groups() {
+        let groups = parse_key_file(
+            "Orphan=ignored\n\
+             # A comment\n\
+             [Desktop Entry]\n\
+             Name = Files\n\
+             Exec=nautilus --new-window=true %U\n\
+             \n\
+             [Desktop Action new-window]\n\
+             Name=New Window\n\
+             not a key\n\
+             [Empty]\n",
+        );
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups["Desktop Entry"]["Name"], "Files");
+        assert_eq!(
+            groups["Desktop Entry"]["Exec"],
+            "nautilus --new-window=true %U"
+        );
+        assert_eq!(groups["Desktop Entry"].len(), 2);
+        assert_eq!(groups["Desktop Action new-window"]["Name"], "New Window");
+        assert!(groups["Empty"].is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+//! Fuzzy matching modelled on fzf's v2 algorithm.
+//!
+//! Matches are scored with a Smith-Waterman style pass that rewards matches
+//! on word boundaries and consecutive runs, and penalises gaps. The pattern
+//! is case-insensitive unless it contains an uppercase character.
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+const BONUS_NON_WORD: i32 = SCORE_MATCH / 2;
+const BONUS_CAMEL_123: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Indices of the matched characters (not bytes) in the text.
+    pub positions: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    White,
+    NonWord,
+    Delimiter,
+    Lower,
+    Upper,
+    Number,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::White
+    } else if matches!(c, '/' | ',' | ':' | ';' | '|' | '-' | '_' | '.' | '\\') {
+        CharClass::Delimiter
+    } else if c.is_lowercase() {
+        CharClass::Lower
+    } else if c.is_uppercase() {
+        CharClass::Upper
+    } else if c.is_numeric() {
+        CharClass::Number
+    } else if c.is_alphabetic() {
+        // Scripts without case behave like lowercase letters
+        CharClass::Lower
+    } else {
+        CharClass::NonWord
+    }
+}
+
+/// Bonus for matching a character of class `class` that follows one of class `prev`.
+fn position_bonus(prev: CharClass, class: CharClass) -> i32 {
+    let is_word = |class| {
+        matches!(
+            class,
+            CharClass::Lower | CharClass::Upper | CharClass::Number
+        )
+    };
+
+    match (prev, class) {
+        (CharClass::White, class) if is_word(class) => BONUS_BOUNDARY_WHITE,
+        (CharClass::Delimiter, class) if is_word(class) => BONUS_BOUNDARY_DELIMITER,
+        (CharClass::NonWord, class) if is_word(class) => BONUS_BOUNDARY,
+        (CharClass::Lower, CharClass::Upper) => BONUS_CAMEL_123,
+        (prev, CharClass::Number) if prev != CharClass::Number => BONUS_CAMEL_123,
+        (_, CharClass::NonWord | CharClass::Delimiter) => BONUS_NON_WORD,
+        (_, CharClass::White) => BONUS_BOUNDARY_WHITE,
+        _ => 0,
+    }
+}
+
+/// Returns true when the pattern should be matched case-sensitively.
+pub fn is_case_sensitive(pattern: &str) -> bool {
+    pattern.chars().any(char::is_uppercase)
+}
+
+/// Scores `text` against `pattern`, returning `None` if not every pattern
+/// character appears in order.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = is_case_sensitive(pattern);
+    let normalize = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+
+    let pattern: Vec<char> = pattern.chars().map(normalize).collect();
+    let original: Vec<char> = text.chars().collect();
+    let text: Vec<char> = original.iter().copied().map(normalize).collect();
+
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    // Cheap subsequence check before paying for the full matrix
+    let mut remaining = pattern.iter().peekable();
+    for c in &text {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let n = pattern.len();
+    let m = text.len();
+
+    let mut bonus = Vec::with_capacity(m);
+    let mut prev_class = CharClass::White;
+    for c in &original {
+        let class = char_class(*c);
+        bonus.push(position_bonus(prev_class, class));
+        prev_class = class;
+    }
+
+    const NONE: i32 = i32::MIN / 2;
+
+    // score[i][j]: best score with pattern[i] matched at text[j]
+    // run_bonus[i][j]: bonus the match at (i, j) received, carried through consecutive runs
+    // from[i][j]: where pattern[i - 1] was matched on the best path to (i, j)
+    let mut score = vec![vec![NONE; m]; n];
+    let mut run_bonus = vec![vec![0; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+
+    for j in 0..m {
+        if text[j] == pattern[0] {
+            score[0][j] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+            run_bonus[0][j] = bonus[j];
+        }
+    }
+
+    for i in 1..n {
+        // Best score[i - 1][k] + gap penalty over all k at least two characters back
+        let mut gapped = NONE;
+        let mut gapped_from = 0;
+
+        for j in i..m {
+            if j >= 2 {
+                let k = j - 2;
+                let opened = score[i - 1][k] + SCORE_GAP_START;
+                if opened >= gapped + SCORE_GAP_EXTENSION {
+                    gapped = opened;
+                    gapped_from = k;
+                } else {
+                    gapped += SCORE_GAP_EXTENSION;
+                }
+            }
+
+            if text[j] != pattern[i] {
+                continue;
+            }
+
+            let consecutive = score[i - 1][j - 1];
+            let consecutive_bonus = bonus[j].max(run_bonus[i - 1][j - 1]).max(BONUS_CONSECUTIVE);
+
+            let via_consecutive = consecutive + consecutive_bonus;
+            let via_gap = gapped + bonus[j];
+
+            if consecutive > NONE && via_consecutive >= via_gap {
+                score[i][j] = SCORE_MATCH + via_consecutive;
+                run_bonus[i][j] = consecutive_bonus;
+                from[i][j] = j - 1;
+            } else if gapped > NONE {
+                score[i][j] = SCORE_MATCH + via_gap;
+                run_bonus[i][j] = bonus[j];
+                from[i][j] = gapped_from;
+            }
+        }
+    }
+
+    let (end, best) = score[n - 1]
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, score)| *score > NONE)
+        .max_by_key(|(j, score)| (*score, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![end; n];
+    for i in (1..n).rev() {
+        positions[i - 1] = from[i][positions[i]];
+    }
+
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> i32 {
+        fuzzy_match(pattern, text).unwrap().score
+    }
+
+    fn positions(pattern: &str, text: &str) -> Vec<usize> {
+        fuzzy_match(pattern, text).unwrap().positions
+    }
+
+    #[test]
+    fn needs_every_character_in_order() {
+        assert!(fuzzy_match("ba", "ab").is_none());
+        assert!(fuzzy_match("abcd", "abc").is_none());
+        assert_eq!(positions("ace", "abcde"), [0, 2, 4]);
+    }
+
+    #[test]
+    fn empty_pattern_matches_anything() {
+        assert_eq!(
+            fuzzy_match("", "anything"),
+            Some(FuzzyMatch {
+                score: 0,
+                positions: Vec::new()
+            })
+        );
+    }
+
+    #[test]
+    fn word_boundaries_score_higher() {
+        assert!(score("bar", "foo bar") > score("bar", "foobar"));
+        assert!(score("bar", "foo/bar") > score("bar", "foobar"));
+        assert!(score("b", "FooBar") > score("b", "foobar"));
+
+        // The boundary is worth skipping an earlier match for
+        assert_eq!(positions("b", "abc bcd"), [4]);
+        assert_eq!(positions("fb", "FooBar"), [0, 3]);
+    }
+
+    #[test]
+    fn consecutive_runs_score_higher() {
+        assert!(score("abc", "xabcx") > score("abc", "xaxbxcx"));
+        assert_eq!(positions("abc", "zaxbxcxabcz"), [7, 8, 9]);
+    }
+
+    #[test]
+    fn smart_case() {
+        assert!(!is_case_sensitive("readme"));
+        assert!(is_case_sensitive("README"));
+
+        assert_eq!(positions("readme", "README.md"), [0, 1, 2, 3, 4, 5]);
+        assert!(fuzzy_match("Readme", "readme.md").is_none());
+        assert!(fuzzy_match("Readme", "Readme.md").is_some());
+    }
+
+    #[test]
+    fn positions_count_characters() {
+        assert_eq!(positions("ße", "Grüße"), [3, 4]);
+    }
+}
@@ -0,0 +1,94 @@
+use image::RgbaImage;
+use resvg::{tiny_skia, usvg};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::scale;
+use crate::utils::freedesktop::{desktop_entry, icon_theme};
+
+/// Renders a window's `_NET_WM_ICON` as a `size` pixel PNG.
+///
+/// The property holds any number of images, each a width, a height and then
+/// that many non-premultiplied ARGB pixels. The one closest to `size` is used.
+pub fn from_net_wm_icon(data: &[u32], size: u32) -> Option<Vec<u8>> {
+    let mut images = Vec::new();
+    let mut rest = data;
+
+    while let [width, height, pixels @ ..] = rest {
+        let len = (*width as usize).saturating_mul(*height as usize);
+        if len == 0 || len > pixels.len() {
+            break;
+        }
+
+        let rgba = pixels[..len]
+            .iter()
+            .flat_map(|pixel| {
+                let [alpha, red, green, blue] = pixel.to_be_bytes();
+                [red, green, blue, alpha]
+            })
+            .collect();
+
+        images.extend(RgbaImage::from_raw(*width, *height, rgba));
+        rest = &pixels[len..];
+    }
+
+    let image = scale::best_source(&images, size, RgbaImage::dimensions)?;
+    scale::to_png(image, size)
+}
+
+/// Renders the icon of the application with window class or app id `app_id`
+/// as a `size` pixel PNG.
+///
+/// The icon comes from the application's desktop entry, or is assumed to be
+/// named after the app id when there is none, and is looked up in the icon themes.
+pub fn from_app_id(app_id: &str, size: u32) -> Option<Vec<u8>> {
+    let path = match desktop_entry::find(app_id).and_then(|entry| entry.icon) {
+        Some(icon) if Path::new(&icon).is_absolute() => PathBuf::from(icon),
+        Some(icon) => icon_theme::lookup(&icon, size)?,
+        None => icon_theme::lookup(app_id, size)
+            .or_else(|| icon_theme::lookup(&app_id.to_lowercase(), size))?,
+    };
+
+    let image = load_icon(&path, size)?;
+    scale::to_png(&image, size)
+}
+
+fn load_icon(path: &Path, size: u32) -> Option<RgbaImage> {
+    let data = fs::read(path).ok()?;
+
+    let is_svg = path
+        .extension()
+        .is_some_and(|extension| extension == "svg" || extension == "svgz");
+    if is_svg {
+        return render_svg(&data, size);
+    }
+
+    Some(image::load_from_memory(&data).ok()?.to_rgba8())
+}
+
+/// Rasterizes an SVG straight to `size` pixels, centered if it isn't square.
+fn render_svg(data: &[u8], size: u32) -> Option<RgbaImage> {
+    let tree = usvg::Tree::from_data(data, &usvg::Options::default()).ok()?;
+    let tree_size = tree.size();
+    let zoom = size as f32 / tree_size.width().max(tree_size.height());
+
+    let transform = tiny_skia::Transform::from_scale(zoom, zoom).post_translate(
+        (size as f32 - tree_size.width() * zoom) / 2.0,
+        (size as f32 - tree_size.height() * zoom) / 2.0,
+    );
+
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)?;
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia works in premultiplied alpha, PNGs don't
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    RgbaImage::from_raw(size, size, rgba)
+}
@@ -1,5 +0,0 @@
-use base64::{engine::general_purpose, Engine as _};
-
-pub fn get_window_icon(_window_id: u32) -> Option<String> {
-    None
-}
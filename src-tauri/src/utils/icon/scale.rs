@@ -0,0 +1,157 @@
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+
+/// Sizes icons are rendered at, requests are rounded up to one of these so
+/// the same icon at 150% and 200% scale doesn't get rendered at every size.
+pub const STANDARD_SIZES: [u32; 8] = [16, 24, 32, 48, 64, 96, 128, 256];
+
+/// Rounds `size` up to the nearest standard size.
+pub fn snap_size(size: u32) -> u32 {
+    STANDARD_SIZES
+        .into_iter()
+        .find(|standard| *standard >= size)
+        .unwrap_or(STANDARD_SIZES[STANDARD_SIZES.len() - 1])
+}
+
+/// Picks the source that scales best to `size`: the smallest one at least
+/// that big, or the biggest one if they are all smaller.
+pub fn best_source<T>(
+    sources: &[T],
+    size: u32,
+    dimensions: impl Fn(&T) -> (u32, u32),
+) -> Option<&T> {
+    let side = |source: &T| {
+        let (width, height) = dimensions(source);
+        width.max(height)
+    };
+
+    sources
+        .iter()
+        .filter(|source| side(source) >= size)
+        .min_by_key(|source| side(source))
+        .or_else(|| sources.iter().max_by_key(|source| side(source)))
+}
+
+/// Scales `image` to fit a `size` pixel square, centering it if it isn't square.
+pub fn fit(image: &RgbaImage, size: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width == size && height == size {
+        return image.clone();
+    }
+
+    let longest = width.max(height).max(1);
+    let scaled_width = (width * size / longest).max(1);
+    let scaled_height = (height * size / longest).max(1);
+
+    // Lanczos keeps downscaled icons sharp, but rings when blowing small ones up
+    let filter = if longest > size {
+        FilterType::Lanczos3
+    } else {
+        FilterType::CatmullRom
+    };
+    let scaled = imageops::resize(image, scaled_width, scaled_height, filter);
+
+    if scaled_width == size && scaled_height == size {
+        return scaled;
+    }
+
+    let mut canvas = RgbaImage::new(size, size);
+    imageops::overlay(
+        &mut canvas,
+        &scaled,
+        ((size - scaled_width) / 2) as i64,
+        ((size - scaled_height) / 2) as i64,
+    );
+    canvas
+}
+
+/// Scales `image` down to fit within `max_width` by `max_height`, keeping its
+/// aspect ratio. Images that already fit are left alone.
+pub fn fit_within(image: &RgbaImage, max_width: u32, max_height: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width <= max_width && height <= max_height {
+        return image.clone();
+    }
+
+    let zoom = f64::min(
+        max_width as f64 / width as f64,
+        max_height as f64 / height as f64,
+    );
+    let scaled_width = ((width as f64 * zoom).round() as u32).max(1);
+    let scaled_height = ((height as f64 * zoom).round() as u32).max(1);
+
+    // Screenshots are big, a triangle filter is much faster than Lanczos and looks the same at this scale
+    imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle)
+}
+
+pub fn encode_png(image: &RgbaImage) -> Option<Vec<u8>> {
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut cursor, image::ImageOutputFormat::Png)
+        .ok()?;
+
+    Some(cursor.into_inner())
+}
+
+/// Scales `image` to a `size` pixel square and encodes it as PNG.
+pub fn to_png(image: &RgbaImage, size: u32) -> Option<Vec<u8>> {
+    encode_png(&fit(image, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    #[test]
+    fn sizes_snap_up_to_a_standard_size() {
+        assert_eq!(snap_size(1), 16);
+        assert_eq!(snap_size(16), 16);
+        assert_eq!(snap_size(20), 24);
+        assert_eq!(snap_size(200), 256);
+        assert_eq!(snap_size(1024), 256);
+    }
+
+    #[test]
+    fn best_source_is_the_smallest_big_enough() {
+        let sources = [(32, 32), (16, 16), (256, 256), (48, 40)];
+        let best = |size| best_source(&sources, size, |source| *source).copied();
+
+        assert_eq!(best(16), Some((16, 16)));
+        assert_eq!(best(24), Some((32, 32)));
+        // The longer side counts
+        assert_eq!(best(40), Some((48, 40)));
+        assert_eq!(best(512), Some((256, 256)));
+        assert_eq!(best_source(&[], 16, |source: &(u32, u32)| *source), None);
+    }
+
+    #[test]
+    fn fit_scales_to_a_centered_square() {
+        let square = RgbaImage::from_pixel(64, 64, RED);
+        assert_eq!(fit(&square, 64), square);
+        assert_eq!(fit(&square, 16).dimensions(), (16, 16));
+        assert_eq!(
+            fit(&RgbaImage::from_pixel(8, 8, RED), 32).dimensions(),
+            (32, 32)
+        );
+
+        let wide = fit(&RgbaImage::from_pixel(64, 32, RED), 32);
+        assert_eq!(wide.dimensions(), (32, 32));
+        // Transparent above and below, the image in the middle
+        assert_eq!(wide.get_pixel(16, 2).0[3], 0);
+        assert_eq!(*wide.get_pixel(16, 16), RED);
+        assert_eq!(wide.get_pixel(16, 29).0[3], 0);
+    }
+
+    #[test]
+    fn fit_within_only_shrinks_keeping_the_aspect_ratio() {
+        let small = RgbaImage::from_pixel(100, 50, RED);
+        assert_eq!(fit_within(&small, 400, 400), small);
+
+        let screenshot = RgbaImage::from_pixel(1920, 1080, RED);
+        assert_eq!(fit_within(&screenshot, 400, 400).dimensions(), (400, 225));
+        assert_eq!(fit_within(&screenshot, 960, 270).dimensions(), (480, 270));
+    }
+}
@@ -1,39 +1,133 @@
-use base64::{engine::general_purpose, Engine as _};
-
+use image::RgbaImage;
 use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
 use windows::Win32::Graphics::Gdi::{
-    CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, SelectObject,
+    CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetObjectW,
+    ReleaseDC, SelectObject, BITMAP,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    GetClassLongPtrW, SendMessageW, GCLP_HICON, GCLP_HICONSM, HICON, ICON_BIG, ICON_SMALL,
-    WM_GETICON,
+    GetClassLongPtrW, GetIconInfo, SendMessageTimeoutW, GCLP_HICON, GCLP_HICONSM, HICON, ICONINFO,
+    ICON_BIG, ICON_SMALL, SMTO_ABORTIFHUNG, WM_GETICON,
 };
 
-pub unsafe fn get_window_icon(hwnd: HWND) -> Option<String> {
-    let mut h_icon =
-        HICON(SendMessageW(hwnd, WM_GETICON, WPARAM(ICON_SMALL as usize), LPARAM(0)).0);
-    if h_icon.0 == 0 {
-        h_icon = HICON(SendMessageW(hwnd, WM_GETICON, WPARAM(ICON_BIG as usize), LPARAM(0)).0);
-    }
+use super::scale;
+use crate::window::IconHandle;
+
+/// How long a window gets to answer `WM_GETICON`, so a hung one can't stall a refresh.
+const GET_ICON_TIMEOUT_MS: u32 = 50;
+
+/// The icon of `kind` the window set for itself, 0 if it set none or doesn't answer.
+unsafe fn own_icon(hwnd: HWND, kind: u32) -> usize {
+    let mut h_icon = 0usize;
+    SendMessageTimeoutW(
+        hwnd,
+        WM_GETICON,
+        WPARAM(kind as usize),
+        LPARAM(0),
+        SMTO_ABORTIFHUNG,
+        GET_ICON_TIMEOUT_MS,
+        Some(&mut h_icon as *mut usize),
+    );
+    h_icon
+}
 
-    if h_icon.0 == 0 {
-        h_icon = HICON(GetClassLongPtrW(hwnd, GCLP_HICONSM) as isize);
+/// Finds the icon handle `get_window_icon` would draw, without drawing it.
+pub unsafe fn get_icon_handle(hwnd: HWND) -> Option<IconHandle> {
+    for kind in [ICON_SMALL, ICON_BIG] {
+        let h_icon = own_icon(hwnd, kind);
+        if h_icon != 0 {
+            return Some(IconHandle {
+                own: true,
+                fingerprint: h_icon as u64,
+            });
+        }
     }
 
-    if h_icon.0 == 0 {
-        h_icon = HICON(GetClassLongPtrW(hwnd, GCLP_HICON) as isize);
+    [GCLP_HICONSM, GCLP_HICON]
+        .into_iter()
+        .map(|index| GetClassLongPtrW(hwnd, index))
+        .find(|h_icon| *h_icon != 0)
+        .map(|h_icon| IconHandle {
+            own: false,
+            fingerprint: h_icon as u64,
+        })
+}
+
+/// The icons a window offers, the class icons only if it doesn't set its own.
+unsafe fn icon_sources(hwnd: HWND) -> Vec<HICON> {
+    let own: Vec<HICON> = [ICON_SMALL, ICON_BIG]
+        .into_iter()
+        .map(|kind| HICON(own_icon(hwnd, kind) as isize))
+        .filter(|h_icon| h_icon.0 != 0)
+        .collect();
+
+    if !own.is_empty() {
+        return own;
     }
 
-    if h_icon.0 == 0 {
+    [GCLP_HICONSM, GCLP_HICON]
+        .into_iter()
+        .map(|index| HICON(GetClassLongPtrW(hwnd, index) as isize))
+        .filter(|h_icon| h_icon.0 != 0)
+        .collect()
+}
+
+/// The size an icon was made at, so it can be drawn without the system scaling it.
+unsafe fn icon_dimensions(h_icon: HICON) -> Option<(u32, u32)> {
+    let mut info = ICONINFO::default();
+    GetIconInfo(h_icon, &mut info).ok()?;
+
+    // Monochrome icons have no color bitmap and a mask twice their height
+    let (bitmap, halved) = if info.hbmColor.is_invalid() {
+        (info.hbmMask, true)
+    } else {
+        (info.hbmColor, false)
+    };
+
+    let mut bitmap_header = BITMAP::default();
+    let read = GetObjectW(
+        bitmap,
+        std::mem::size_of::<BITMAP>() as i32,
+        Some(&mut bitmap_header as *mut _ as *mut std::ffi::c_void),
+    );
+
+    let _ = DeleteObject(info.hbmColor);
+    let _ = DeleteObject(info.hbmMask);
+
+    if read == 0 {
         return None;
     }
 
-    // Convert icon to bitmap and then to base64
-    let icon_size = 16; // Small icon size
+    let height = if halved {
+        bitmap_header.bmHeight / 2
+    } else {
+        bitmap_header.bmHeight
+    };
+    Some((bitmap_header.bmWidth as u32, height as u32))
+}
+
+/// Renders the window icon as a `size` pixel PNG.
+///
+/// Draws whichever icon the window offers that is closest to `size` at its
+/// own size, then resamples it, which looks better than letting GDI stretch
+/// a 16 pixel icon on a scaled display.
+pub unsafe fn get_window_icon(hwnd: HWND, size: u32) -> Option<Vec<u8>> {
+    let sources: Vec<(HICON, (u32, u32))> = icon_sources(hwnd)
+        .into_iter()
+        .filter_map(|h_icon| Some((h_icon, icon_dimensions(h_icon)?)))
+        .collect();
+    let (h_icon, (width, height)) = *scale::best_source(&sources, size, |source| source.1)?;
+
+    let image = draw_icon(hwnd, h_icon, width as i32, height as i32)?;
+    scale::to_png(&image, size)
+}
+
+unsafe fn draw_icon(hwnd: HWND, h_icon: HICON, width: i32, height: i32) -> Option<RgbaImage> {
+    // Convert icon to bitmap
     let hdc = GetDC(hwnd);
     let hdc_mem = CreateCompatibleDC(hdc);
-    let h_bitmap = CreateCompatibleBitmap(hdc, icon_size, icon_size);
+    let h_bitmap = CreateCompatibleBitmap(hdc, width, height);
     let old_bitmap = SelectObject(hdc_mem, h_bitmap);
+    ReleaseDC(hwnd, hdc);
 
     // Draw icon to bitmap
     let result = windows::Win32::UI::WindowsAndMessaging::DrawIconEx(
@@ -41,8 +135,8 @@ pub unsafe fn get_window_icon(hwnd: HWND) -> Option<String> {
         0,
         0,
         h_icon,
-        icon_size,
-        icon_size,
+        width,
+        height,
         0,
         None,
         windows::Win32::UI::WindowsAndMessaging::DI_NORMAL,
@@ -61,15 +155,15 @@ pub unsafe fn get_window_icon(hwnd: HWND) -> Option<String> {
     }
 
     // Create buffer for bitmap data
-    let buffer_size = (icon_size * icon_size * 4) as usize;
+    let buffer_size = (width * height * 4) as usize;
     let mut buffer = vec![0u8; buffer_size];
 
     // Get bitmap data
     let mut bitmap_info = windows::Win32::Graphics::Gdi::BITMAPINFO {
         bmiHeader: windows::Win32::Graphics::Gdi::BITMAPINFOHEADER {
             biSize: std::mem::size_of::<windows::Win32::Graphics::Gdi::BITMAPINFOHEADER>() as u32,
-            biWidth: icon_size,
-            biHeight: -icon_size, // Negative for top-down
+            biWidth: width,
+            biHeight: -height, // Negative for top-down
             biPlanes: 1,
             biBitCount: 32,
             biCompression: windows::Win32::Graphics::Gdi::BI_RGB.0,
@@ -86,7 +180,7 @@ pub unsafe fn get_window_icon(hwnd: HWND) -> Option<String> {
         hdc_mem,
         h_bitmap,
         0,
-        icon_size as u32,
+        height as u32,
         Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
         &mut bitmap_info as *mut _,
         windows::Win32::Graphics::Gdi::DIB_RGB_COLORS,
@@ -107,20 +201,5 @@ pub unsafe fn get_window_icon(hwnd: HWND) -> Option<String> {
         pixel.swap(0, 2);
     }
 
-    // Encode as PNG and convert to base64
-    let png_data = match image::RgbaImage::from_raw(icon_size as u32, icon_size as u32, buffer) {
-        Some(img) => {
-            let mut cursor = std::io::Cursor::new(Vec::new());
-            if let Ok(_) = img.write_to(&mut cursor, image::ImageOutputFormat::Png) {
-                cursor.into_inner()
-            } else {
-                return None;
-            }
-        }
-        None => return None,
-    };
-
-    // Convert to base64
-    let base64_string = general_purpose::STANDARD.encode(png_data);
-    Some(format!("data:image/png;base64,{}", base64_string))
+    RgbaImage::from_raw(width as u32, height as u32, buffer)
 }
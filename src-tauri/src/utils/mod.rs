@@ -1,4 +1,8 @@
+pub mod capture;
 pub mod display;
+#[cfg(target_os = "linux")]
+pub mod freedesktop;
+pub mod fuzzy;
 pub mod icon;
 pub mod process;
 pub mod setup;
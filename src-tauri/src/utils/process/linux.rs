@@ -0,0 +1,408 @@
+use super::{ProcessInfo, Sandbox, SandboxKind};
+use crate::utils::freedesktop::parse_key_file;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Length of a clock tick in `/proc/<pid>/stat`, USER_HZ is 100 on every architecture Linux runs on.
+const TICKS_PER_SECOND: u64 = 100;
+
+pub fn get_process_name(process_id: u32) -> Option<String> {
+    // The exe link is unreadable for processes owned by other users, fall back to comm
+    if let Ok(path) = fs::read_link(format!("/proc/{process_id}/exe")) {
+        if let Some(name) = path.file_name() {
+            return Some(name.to_string_lossy().into_owned());
+        }
+    }
+
+    fs::read_to_string(format!("/proc/{process_id}/comm"))
+        .ok()
+        .map(|name| name.trim_end().to_string())
+}
+
+pub fn get_process_path(process_id: u32) -> Option<String> {
+    fs::read_link(format!("/proc/{process_id}/exe"))
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+pub fn get_parent_process_id(process_id: u32) -> Option<u32> {
+    let stat = fs::read_to_string(format!("/proc/{process_id}/stat")).ok()?;
+    let (_, fields) = stat.rsplit_once(')')?;
+
+    // The state comes first, then the parent
+    fields
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+        .filter(|pid| *pid != 0)
+}
+
+/// Reads everything `/proc` has on the process, `None` if it doesn't exist.
+pub fn get_process_info(process_id: u32) -> Option<ProcessInfo> {
+    let proc_dir = format!("/proc/{process_id}");
+
+    // Unlike most files here stat is readable for everyone's processes, so it tells whether the process exists
+    let stat = fs::read_to_string(format!("{proc_dir}/stat")).ok()?;
+    let status = fs::read_to_string(format!("{proc_dir}/status")).unwrap_or_default();
+
+    // The command name in parentheses can contain spaces and parentheses itself
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    // Field numbers from proc(5), the first two are before the split
+    let field = |number: usize| fields.get(number - 3)?.parse::<u64>().ok();
+
+    let command_line = fs::read(format!("{proc_dir}/cmdline"))
+        .map(|cmdline| {
+            cmdline
+                .split(|byte| *byte == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let start_time = field(22).and_then(|ticks| {
+        let boot_time = boot_time()?;
+        Some((boot_time * TICKS_PER_SECOND + ticks) * 1000 / TICKS_PER_SECOND)
+    });
+
+    let cpu_time = field(14)
+        .zip(field(15))
+        .map(|(user, system)| (user + system) * 1000 / TICKS_PER_SECOND);
+
+    let memory = status_field(&status, "VmRSS")
+        .and_then(|rss| rss.trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kilobytes| kilobytes * 1024);
+
+    let user = status_field(&status, "Uid")
+        .and_then(|uids| uids.split_whitespace().next())
+        .and_then(user_name);
+
+    Some(ProcessInfo {
+        pid: process_id,
+        parent_pid: field(4).map(|pid| pid as u32).filter(|pid| *pid != 0),
+        exe_path: get_process_path(process_id),
+        command_line,
+        working_dir: fs::read_link(format!("{proc_dir}/cwd"))
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned()),
+        user,
+        start_time,
+        memory,
+        cpu_time,
+    })
+}
+
+/// Works out whether the process runs in a Flatpak, a snap or a container,
+/// `None` for processes running on the host like we do.
+pub fn get_sandbox(process_id: u32) -> Option<Sandbox> {
+    let proc_dir = format!("/proc/{process_id}");
+    let cgroup = fs::read_to_string(format!("{proc_dir}/cgroup")).unwrap_or_default();
+
+    flatpak(&proc_dir, &cgroup)
+        .or_else(|| snap(&cgroup))
+        .or_else(|| container(&proc_dir, &cgroup))
+}
+
+fn flatpak(proc_dir: &str, cgroup: &str) -> Option<Sandbox> {
+    // Flatpak leaves a description of the sandbox in its root
+    let app_id = match fs::read_to_string(format!("{proc_dir}/root/.flatpak-info")) {
+        Ok(info) => parse_key_file(&info)
+            .remove("Application")
+            .and_then(|mut keys| keys.remove("name")),
+        // Other users' sandboxes can't be looked into, their systemd scope, app-flatpak-<id>-<n>.scope, still names them
+        Err(_) => {
+            let scope = cgroup_units(cgroup).find_map(|unit| {
+                unit.strip_prefix("app-flatpak-")?
+                    .strip_suffix(".scope")?
+                    .rsplit_once('-')
+            })?;
+            Some(scope.0.to_string())
+        }
+    };
+
+    Some(Sandbox {
+        kind: SandboxKind::Flatpak,
+        app_id,
+        container: None,
+    })
+}
+
+/// Snaps run in a unit named snap.<snap>.<app>, as a cgroup of its own or a systemd scope.
+fn snap(cgroup: &str) -> Option<Sandbox> {
+    let name = cgroup_units(cgroup).find_map(|unit| {
+        let (name, _) = unit.strip_prefix("snap.")?.split_once('.')?;
+        Some(name.to_string())
+    })?;
+
+    Some(Sandbox {
+        kind: SandboxKind::Snap,
+        app_id: Some(name),
+        container: None,
+    })
+}
+
+fn container(proc_dir: &str, cgroup: &str) -> Option<Sandbox> {
+    let root = format!("{proc_dir}/root");
+
+    // Processes sharing our root are on the host, or in the same container we are in
+    let (theirs, ours) = (
+        fs::metadata(&root).ok()?,
+        fs::metadata("/proc/self/root").ok()?,
+    );
+    if theirs.dev() == ours.dev() && theirs.ino() == ours.ino() {
+        return None;
+    }
+
+    let environ = fs::read(format!("{proc_dir}/environ")).unwrap_or_default();
+    let env = |name: &str| {
+        environ.split(|byte| *byte == 0).find_map(|variable| {
+            let value = variable.strip_prefix(name.as_bytes())?.strip_prefix(b"=")?;
+            Some(String::from_utf8_lossy(value).into_owned())
+        })
+    };
+
+    // Podman, which toolbox and most distroboxes run on, describes the container in /run/.containerenv
+    let container_env = fs::read_to_string(format!("{root}/run/.containerenv")).ok();
+    let container = env("CONTAINER_ID").or_else(|| {
+        container_env.as_deref()?.lines().find_map(|line| {
+            let name = line.strip_prefix("name=")?;
+            Some(name.trim_matches('"').to_string())
+        })
+    });
+
+    let kind = if Path::new(&format!("{root}/run/.toolboxenv")).exists() {
+        SandboxKind::Toolbox
+    } else if env("DISTROBOX_ENTER_PATH").is_some() || env("CONTAINER_ID").is_some() {
+        SandboxKind::Distrobox
+    } else if container_env.is_some()
+        || Path::new(&format!("{root}/.dockerenv")).exists()
+        || env("container").is_some()
+        || cgroup_units(cgroup)
+            .any(|unit| unit.starts_with("docker-") || unit.starts_with("libpod-"))
+    {
+        SandboxKind::Container
+    } else {
+        // A different root alone could be anything, a chroot or a build environment
+        return None;
+    };
+
+    Some(Sandbox {
+        kind,
+        app_id: None,
+        container,
+    })
+}
+
+/// The path components of every hierarchy in `/proc/<pid>/cgroup`, which name
+/// the slices, scopes and services the process runs in.
+fn cgroup_units(cgroup: &str) -> impl Iterator<Item = &str> {
+    cgroup
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .flat_map(|path| path.split('/'))
+}
+
+/// Returns the value of a `Key:\tvalue` line in `/proc/<pid>/status`.
+fn status_field<'a>(status: &'a str, key: &str) -> Option<&'a str> {
+    status.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name == key).then(|| value.trim())
+    })
+}
+
+/// When the system booted, in seconds since the epoch.
+fn boot_time() -> Option<u64> {
+    fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Looks up the name of a user id in /etc/passwd, the id itself if it's not there.
+fn user_name(uid: &str) -> Option<String> {
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+
+    let name = passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        (fields.nth(1)? == uid).then(|| name.to_string())
+    });
+
+    name.or_else(|| Some(uid.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn describes_the_current_process() {
+        let pid = std::process::id();
+        let exe = std::env::current_exe().unwrap();
+        let info = get_process_info(pid).unwrap();
+
+        assert_eq!(info.pid, pid);
+        assert_eq!(info.exe_path.as_deref(), exe.to_str());
+        assert_eq!(get_process_path(pid), info.exe_path);
+        assert_eq!(
+            get_process_name(pid).as_deref(),
+            exe.file_name().unwrap().to_str()
+        );
+        assert_eq!(info.command_line, std::env::args().collect::<Vec<_>>());
+        assert_eq!(
+            info.working_dir.as_deref(),
+            std::env::current_dir().unwrap().to_str()
+        );
+        assert_eq!(info.parent_pid, Some(std::os::unix::process::parent_id()));
+        assert_eq!(get_parent_process_id(pid), info.parent_pid);
+        assert!(info.user.is_some());
+        assert!(info.memory.unwrap() > 0);
+        assert!(info.cpu_time.is_some());
+
+        // Started moments ago, give or take the second btime is rounded to
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let start_time = info.start_time.unwrap();
+        assert!(start_time <= now + 1000, "{start_time} is after {now}");
+        assert!(
+            start_time + 10 * 60 * 1000 > now,
+            "{start_time} is long before {now}"
+        );
+    }
+
+    /// A stand-in for `/proc/<pid>` with `files` in it and a root of its own.
+    fn proc_dir(name: &str, files: &[(&str, &str)]) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("binocular-proc-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("root")).unwrap();
+
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir.to_string_lossy().into_owned()
+    }
+
+    fn sandbox(
+        kind: SandboxKind,
+        app_id: Option<&str>,
+        container: Option<&str>,
+    ) -> Option<Sandbox> {
+        Some(Sandbox {
+            kind,
+            app_id: app_id.map(String::from),
+            container: container.map(String::from),
+        })
+    }
+
+    const HOST_CGROUP: &str = "0::/user.slice/user-1000.slice/session-2.scope\n";
+
+    #[test]
+    fn flatpaks_are_named_by_their_info_or_scope() {
+        let dir = proc_dir(
+            "flatpak",
+            &[(
+                "root/.flatpak-info",
+                "[Application]\nname=org.gimp.GIMP\nruntime=runtime/org.gnome.Platform\n",
+            )],
+        );
+        assert_eq!(
+            flatpak(&dir, HOST_CGROUP),
+            sandbox(SandboxKind::Flatpak, Some("org.gimp.GIMP"), None)
+        );
+
+        // Another user's, its root can't be read
+        let cgroup = "0::/user.slice/user-1001.slice/user@1001.service/app.slice/app-flatpak-org.mozilla.firefox-5150.scope\n";
+        assert_eq!(
+            flatpak("/nonexistent", cgroup),
+            sandbox(SandboxKind::Flatpak, Some("org.mozilla.firefox"), None)
+        );
+        assert_eq!(flatpak("/nonexistent", HOST_CGROUP), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn snaps_are_named_by_their_unit() {
+        let scope = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/snap.firefox.firefox-2a3b.scope\n";
+        let legacy = "12:pids:/snap.spotify.spotify\n11:memory:/user.slice\n";
+
+        assert_eq!(
+            snap(scope),
+            sandbox(SandboxKind::Snap, Some("firefox"), None)
+        );
+        assert_eq!(
+            snap(legacy),
+            sandbox(SandboxKind::Snap, Some("spotify"), None)
+        );
+        assert_eq!(snap(HOST_CGROUP), None);
+    }
+
+    #[test]
+    fn containers_are_told_apart() {
+        let toolbox = proc_dir(
+            "toolbox",
+            &[
+                ("root/run/.toolboxenv", ""),
+                (
+                    "root/run/.containerenv",
+                    "engine=\"podman-4.9.0\"\nname=\"fedora-toolbox-40\"\n",
+                ),
+            ],
+        );
+        let distrobox = proc_dir(
+            "distrobox",
+            &[("environ", "HOME=/home/user\0CONTAINER_ID=arch\0DISTROBOX_ENTER_PATH=/usr/bin/distrobox-enter\0")],
+        );
+        let docker = proc_dir("docker", &[("root/.dockerenv", "")]);
+        let chroot = proc_dir("chroot", &[]);
+
+        assert_eq!(
+            container(&toolbox, HOST_CGROUP),
+            sandbox(SandboxKind::Toolbox, None, Some("fedora-toolbox-40"))
+        );
+        assert_eq!(
+            container(&distrobox, HOST_CGROUP),
+            sandbox(SandboxKind::Distrobox, None, Some("arch"))
+        );
+        assert_eq!(
+            container(&docker, HOST_CGROUP),
+            sandbox(SandboxKind::Container, None, None)
+        );
+        assert_eq!(
+            container(&chroot, "0::/system.slice/docker-0123abcd.scope\n"),
+            sandbox(SandboxKind::Container, None, None)
+        );
+        assert_eq!(container(&chroot, HOST_CGROUP), None);
+        // Whatever the cgroup says, sharing our root means it's where we are
+        assert_eq!(
+            container("/proc/self", "0::/system.slice/docker-0123abcd.scope\n"),
+            None
+        );
+
+        for dir in [toolbox, distrobox, docker, chroot] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn nothing_about_processes_that_dont_exist() {
+        assert_eq!(get_process_info(u32::MAX), None);
+        assert_eq!(get_process_name(u32::MAX), None);
+        assert_eq!(get_process_path(u32::MAX), None);
+        assert_eq!(get_parent_process_id(u32::MAX), None);
+    }
+}
@@ -1,4 +0,0 @@
-pub fn get_process_name(process_id: u32) -> Option<String> {
-    // TODO: Implement proper macOS process name retrieval
-    None
-}
@@ -3,7 +3,65 @@ mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::*;
 
-#[cfg(target_os = "macos")]
-mod macos;
-#[cfg(target_os = "macos")]
-pub use macos::*;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
+use serde::{Deserialize, Serialize};
+
+/// Details about the process owning a window, loaded on demand since some
+/// of them take several system calls to gather. Anything the process doesn't
+/// let us read is left empty.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub exe_path: Option<String>,
+    /// The executable followed by its arguments.
+    pub command_line: Vec<String>,
+    pub working_dir: Option<String>,
+    /// Name of the user the process runs as.
+    pub user: Option<String>,
+    /// Unix timestamp in milliseconds.
+    pub start_time: Option<u64>,
+    /// Resident memory in bytes.
+    pub memory: Option<u64>,
+    /// User and kernel CPU time in milliseconds since the process started.
+    pub cpu_time: Option<u64>,
+}
+
+/// The kind of sandbox or container a process runs in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxKind {
+    Flatpak,
+    Snap,
+    Toolbox,
+    Distrobox,
+    /// Any other container, e.g. one started with podman or docker.
+    Container,
+}
+
+impl SandboxKind {
+    /// The name it's serialized as, which is also what gets searched.
+    pub fn label(&self) -> &'static str {
+        match self {
+            SandboxKind::Flatpak => "flatpak",
+            SandboxKind::Snap => "snap",
+            SandboxKind::Toolbox => "toolbox",
+            SandboxKind::Distrobox => "distrobox",
+            SandboxKind::Container => "container",
+        }
+    }
+}
+
+/// Where a process that doesn't run straight on the host comes from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Sandbox {
+    pub kind: SandboxKind,
+    /// The Flatpak app id or the snap name, what the process shows up as tells little.
+    pub app_id: Option<String>,
+    /// Name of the toolbox, distrobox or other container.
+    pub container: Option<String>,
+}
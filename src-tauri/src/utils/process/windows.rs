@@ -1,20 +1,302 @@
-use windows::Win32::System::{
-    ProcessStatus::K32GetModuleFileNameExW,
-    Threading::{OpenProcess, PROCESS_QUERY_INFORMATION},
+use std::path::Path;
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Wdk::System::Threading::{NtQueryInformationProcess, PROCESSINFOCLASS};
+use windows::Win32::Foundation::{
+    CloseHandle, LocalFree, FILETIME, HANDLE, HLOCAL, UNICODE_STRING,
+};
+use windows::Win32::Security::{
+    GetTokenInformation, LookupAccountSidW, TokenUser, SID_NAME_USE, TOKEN_QUERY, TOKEN_USER,
+};
+use windows::Win32::Storage::FileSystem::{
+    GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
+};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+use windows::Win32::System::Threading::{
+    GetProcessTimes, OpenProcess, OpenProcessToken, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
 };
+use windows::Win32::UI::Shell::CommandLineToArgvW;
+
+use super::ProcessInfo;
+
+/// Windows 8.1 and up hand out the command line with just limited query rights.
+/// Missing from the metadata `windows` is generated from.
+const PROCESS_COMMAND_LINE_INFORMATION: PROCESSINFOCLASS = PROCESSINFOCLASS(60);
+
+/// Milliseconds between 1601, where FILETIMEs start, and 1970.
+const FILETIME_UNIX_EPOCH_MILLIS: u64 = 11_644_473_600_000;
+
+/// A process handle that is closed when dropped.
+struct ProcessHandle(HANDLE);
+
+impl ProcessHandle {
+    unsafe fn open(process_id: u32) -> Option<Self> {
+        // Limited rights are granted for elevated processes too, unlike PROCESS_QUERY_INFORMATION
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)
+            .ok()
+            .map(Self)
+    }
+}
+
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
 
 pub unsafe fn get_process_name(process_id: u32) -> Option<String> {
-    let process_handle = OpenProcess(PROCESS_QUERY_INFORMATION, false, process_id);
+    let path = get_process_path(process_id)?;
+    Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+pub unsafe fn get_process_path(process_id: u32) -> Option<String> {
+    let process = ProcessHandle::open(process_id)?;
+    image_path(&process)
+}
+
+unsafe fn image_path(process: &ProcessHandle) -> Option<String> {
+    // Long path aware processes can live deeper than MAX_PATH
+    let mut buffer = vec![0u16; 32_768];
+    let mut len = buffer.len() as u32;
+
+    QueryFullProcessImageNameW(
+        process.0,
+        PROCESS_NAME_WIN32,
+        PWSTR(buffer.as_mut_ptr()),
+        &mut len,
+    )
+    .ok()?;
+
+    Some(String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+/// Reads the `FileDescription` from an executable's version resource, e.g.
+/// "Google Chrome" for `chrome.exe`.
+pub unsafe fn get_file_description(path: &str) -> Option<String> {
+    let path: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+    let path = PCWSTR(path.as_ptr());
+
+    let size = GetFileVersionInfoSizeW(path, None);
+    if size == 0 {
+        return None;
+    }
+
+    let mut data = vec![0u8; size as usize];
+    GetFileVersionInfoW(path, 0, size, data.as_mut_ptr() as *mut std::ffi::c_void).ok()?;
+
+    // Pairs of language and code page the strings are available in, the first one is the main one
+    let translations = query_version_value(&data, "\\VarFileInfo\\Translation")?;
+    let (language, code_page) = match translations {
+        [language_low, language_high, code_page_low, code_page_high, ..] => (
+            u16::from_le_bytes([*language_low, *language_high]),
+            u16::from_le_bytes([*code_page_low, *code_page_high]),
+        ),
+        _ => return None,
+    };
+
+    let description = query_version_value(
+        &data,
+        &format!("\\StringFileInfo\\{language:04x}{code_page:04x}\\FileDescription"),
+    )?;
+
+    let description: Vec<u16> = description
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|c| *c != 0)
+        .collect();
+
+    let description = String::from_utf16_lossy(&description).trim().to_string();
+    (!description.is_empty()).then_some(description)
+}
+
+/// Looks up a value in a version resource, returning the bytes it points at.
+unsafe fn query_version_value<'a>(data: &'a [u8], sub_block: &str) -> Option<&'a [u8]> {
+    let sub_block: Vec<u16> = sub_block.encode_utf16().chain(Some(0)).collect();
+    let mut value = std::ptr::null_mut();
+    let mut len = 0u32;
 
-    if let Ok(handle) = process_handle {
-        let mut buffer = [0u16; 260]; // MAX_PATH
-        let len = K32GetModuleFileNameExW(handle, None, &mut buffer);
+    if !VerQueryValueW(
+        data.as_ptr() as *const std::ffi::c_void,
+        PCWSTR(sub_block.as_ptr()),
+        &mut value,
+        &mut len,
+    )
+    .as_bool()
+        || value.is_null()
+    {
+        return None;
+    }
+
+    // Strings are measured in UTF-16 characters and binary values in bytes, take the
+    // larger of the two while staying inside the resource
+    let start = value as usize - data.as_ptr() as usize;
+    let len = (len as usize * 2).min(data.len().saturating_sub(start));
+    Some(&data[start..start + len])
+}
+
+pub unsafe fn get_process_info(process_id: u32) -> Option<ProcessInfo> {
+    let process = ProcessHandle::open(process_id)?;
+
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    let times =
+        GetProcessTimes(process.0, &mut creation, &mut exit, &mut kernel, &mut user).is_ok();
 
-        if len > 0 {
-            let path = String::from_utf16_lossy(&buffer[..len as usize]);
-            // Extract just the file name from the path
-            return Some(path.split('\\').last().unwrap_or("Unknown").to_string());
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+    let memory = K32GetProcessMemoryInfo(
+        process.0,
+        &mut counters,
+        std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+    )
+    .as_bool()
+    .then_some(counters.WorkingSetSize as u64);
+
+    Some(ProcessInfo {
+        pid: process_id,
+        parent_pid: get_parent_process_id(process_id),
+        exe_path: image_path(&process),
+        command_line: command_line(&process).unwrap_or_default(),
+        // Only readable from the process' memory, which needs far more access than we ask for
+        working_dir: None,
+        user: user_name(&process),
+        start_time: times
+            .then(|| (filetime_to_u64(creation) / 10_000).checked_sub(FILETIME_UNIX_EPOCH_MILLIS))
+            .flatten(),
+        memory,
+        cpu_time: times.then(|| (filetime_to_u64(kernel) + filetime_to_u64(user)) / 10_000),
+    })
+}
+
+/// FILETIMEs count 100 nanosecond intervals.
+fn filetime_to_u64(time: FILETIME) -> u64 {
+    ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64
+}
+
+pub unsafe fn get_parent_process_id(process_id: u32) -> Option<u32> {
+    let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).ok()?;
+
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut parent = None;
+    let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+    while found {
+        if entry.th32ProcessID == process_id {
+            parent = Some(entry.th32ParentProcessID).filter(|pid| *pid != 0);
+            break;
         }
+        found = Process32NextW(snapshot, &mut entry).is_ok();
+    }
+
+    let _ = CloseHandle(snapshot);
+    parent
+}
+
+unsafe fn command_line(process: &ProcessHandle) -> Option<Vec<String>> {
+    let mut len = 0u32;
+    // The first call fails but reports how big the buffer has to be
+    let _ = NtQueryInformationProcess(
+        process.0,
+        PROCESS_COMMAND_LINE_INFORMATION,
+        std::ptr::null_mut(),
+        0,
+        &mut len,
+    );
+    if len == 0 {
+        return None;
     }
-    None
+
+    // u64s keep the UNICODE_STRING at the start of the buffer aligned
+    let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+    let queried = NtQueryInformationProcess(
+        process.0,
+        PROCESS_COMMAND_LINE_INFORMATION,
+        buffer.as_mut_ptr() as *mut std::ffi::c_void,
+        len,
+        &mut len,
+    );
+    if queried.is_err() {
+        return None;
+    }
+
+    let command_line = &*(buffer.as_ptr() as *const UNICODE_STRING);
+    let chars = std::slice::from_raw_parts(command_line.Buffer.0, command_line.Length as usize / 2);
+
+    // CommandLineToArgvW wants it null-terminated
+    let mut command_line: Vec<u16> = chars.to_vec();
+    command_line.push(0);
+
+    let mut argc = 0;
+    let argv = CommandLineToArgvW(PCWSTR(command_line.as_ptr()), &mut argc);
+    if argv.is_null() {
+        return None;
+    }
+
+    let args = std::slice::from_raw_parts(argv, argc as usize)
+        .iter()
+        .map(|arg| String::from_utf16_lossy(arg.as_wide()))
+        .collect();
+
+    let _ = LocalFree(HLOCAL(argv as isize));
+    Some(args)
+}
+
+unsafe fn user_name(process: &ProcessHandle) -> Option<String> {
+    let mut token = HANDLE::default();
+    OpenProcessToken(process.0, TOKEN_QUERY, &mut token).ok()?;
+
+    let mut len = 0u32;
+    let _ = GetTokenInformation(token, TokenUser, None, 0, &mut len);
+
+    // u64s keep the TOKEN_USER at the start of the buffer aligned
+    let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+    let queried = GetTokenInformation(
+        token,
+        TokenUser,
+        Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
+        len,
+        &mut len,
+    );
+    let _ = CloseHandle(token);
+    queried.ok()?;
+
+    let token_user = &*(buffer.as_ptr() as *const TOKEN_USER);
+
+    let mut name = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain = [0u16; 256];
+    let mut domain_len = domain.len() as u32;
+    let mut sid_type = SID_NAME_USE::default();
+
+    LookupAccountSidW(
+        PCWSTR::null(),
+        token_user.User.Sid,
+        PWSTR(name.as_mut_ptr()),
+        &mut name_len,
+        PWSTR(domain.as_mut_ptr()),
+        &mut domain_len,
+        &mut sid_type,
+    )
+    .ok()?;
+
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+
+    Some(if domain.is_empty() {
+        name
+    } else {
+        format!("{domain}\\{name}")
+    })
 }
@@ -1,9 +0,0 @@
-pub fn setup_window_event_listener(app: &tauri::App) {
-    // TODO: Implement macOS window event listener
-    // This would require using Objective-C bindings to register for window notifications
-    // For now, this is a dummy implementation
-    println!("setup_window_event_listener called on macOS - not yet implemented");
-
-    // We could potentially use NSWorkspace notifications or Accessibility API
-    // to monitor window events on macOS, but this requires proper Objective-C bindings
-}
@@ -3,16 +3,11 @@ mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::*;
 
-#[cfg(target_os = "macos")]
-mod macos;
-#[cfg(target_os = "macos")]
-pub use macos::*;
-
 pub fn setup_autostart(app: &tauri::App) {
     // Only enable autostart in release builds
     #[cfg(not(debug_assertions))]
     {
-        use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+        use tauri_plugin_autostart::MacosLauncher;
 
         app.handle()
             .plugin(tauri_plugin_autostart::init(
@@ -20,19 +15,28 @@ pub fn setup_autostart(app: &tauri::App) {
                 Some(vec!["--flag1", "--flag2"]),
             ))
             .unwrap();
+    }
+    #[cfg(debug_assertions)]
+    let _ = app;
+}
+
+/// Turns launching at login on or off, does nothing in debug builds.
+pub fn set_autostart(app: &tauri::AppHandle, enabled: bool) {
+    #[cfg(not(debug_assertions))]
+    {
+        use tauri_plugin_autostart::ManagerExt;
 
         // Get the autostart manager
         let autostart_manager = app.autolaunch();
-        // Enable autostart
-        let _ = autostart_manager.enable();
-        // Check enable state
-        match autostart_manager.is_enabled() {
-            Ok(enabled) => {
-                println!("Autostart enabled: {}", enabled);
-            }
-            Err(e) => {
-                println!("failed to enable autostart: {}", e);
-            }
+        let result = if enabled {
+            autostart_manager.enable()
+        } else {
+            autostart_manager.disable()
+        };
+        if let Err(e) = result {
+            println!("failed to update autostart: {}", e);
         }
     }
+    #[cfg(debug_assertions)]
+    let _ = (app, enabled);
 }
@@ -1,4 +1,4 @@
-use crate::window_manager::WindowManager;
+use crate::window::{EventCallback, WindowEvent};
 use ::windows::{
     core::{w, PCWSTR},
     Win32::{
@@ -6,13 +6,16 @@ use ::windows::{
         UI::WindowsAndMessaging::{
             CreateWindowExW, DefWindowProcW, GetWindowLongPtrW, RegisterClassW,
             RegisterShellHookWindow, RegisterWindowMessageW, SetWindowLongPtrW, CS_HREDRAW,
-            CS_VREDRAW, CW_USEDEFAULT, GWLP_USERDATA, HSHELL_WINDOWCREATED, HSHELL_WINDOWDESTROYED,
-            HWND_MESSAGE, WNDCLASSW, WS_OVERLAPPEDWINDOW,
+            CS_VREDRAW, CW_USEDEFAULT, GWLP_USERDATA, HSHELL_HIGHBIT, HSHELL_WINDOWACTIVATED,
+            HSHELL_WINDOWCREATED, HSHELL_WINDOWDESTROYED, HWND_MESSAGE, WNDCLASSW,
+            WS_OVERLAPPEDWINDOW,
         },
     },
 };
 use std::mem::zeroed;
-use tauri::{Emitter, Manager};
+
+/// Sent instead of HSHELL_WINDOWACTIVATED when a full screen app is running.
+const HSHELL_RUDEAPPACTIVATED: u32 = HSHELL_WINDOWACTIVATED | HSHELL_HIGHBIT;
 
 unsafe extern "system" fn window_proc(
     hwnd: HWND,
@@ -22,10 +25,9 @@ unsafe extern "system" fn window_proc(
 ) -> LRESULT {
     static mut SHELL_MESSAGE: u32 = 0;
 
-    let app_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut tauri::App;
-    if !app_ptr.is_null() {
-        let app = app_ptr.as_mut().unwrap();
-        let state = app.state::<WindowManager>();
+    let callback_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const EventCallback;
+    if !callback_ptr.is_null() {
+        let callback = &*callback_ptr;
 
         // Initialize the shell message ID if we haven't yet
         if SHELL_MESSAGE == 0 {
@@ -35,19 +37,12 @@ unsafe extern "system" fn window_proc(
         if msg == SHELL_MESSAGE {
             let event = wparam.0 as u32;
 
-            // Only process visible windows
+            // lparam holds the handle of the window the event is about
             match event {
-                HSHELL_WINDOWCREATED => {
-                    state.inner().refresh_window_list();
-
-                    app.emit("windows-updated", state.windows.lock().unwrap().clone())
-                        .unwrap();
-                }
-                HSHELL_WINDOWDESTROYED => {
-                    state.inner().refresh_window_list();
-
-                    app.emit("windows-updated", state.windows.lock().unwrap().clone())
-                        .unwrap();
+                HSHELL_WINDOWCREATED => callback(WindowEvent::Created(lparam.0)),
+                HSHELL_WINDOWDESTROYED => callback(WindowEvent::Destroyed(lparam.0)),
+                HSHELL_WINDOWACTIVATED | HSHELL_RUDEAPPACTIVATED => {
+                    callback(WindowEvent::Activated(lparam.0))
                 }
                 _ => {}
             }
@@ -57,7 +52,10 @@ unsafe extern "system" fn window_proc(
     DefWindowProcW(hwnd, msg, wparam, lparam)
 }
 
-pub fn setup_window_event_listener(app: &tauri::App) {
+/// Registers a message-only shell hook window that forwards window events to `callback`.
+///
+/// Must be called from a thread that pumps messages, the main thread does.
+pub fn setup_window_event_listener(callback: EventCallback) -> Result<(), String> {
     unsafe {
         let class_name = w!("ShellHookWindow");
         let wc = WNDCLASSW {
@@ -86,39 +84,19 @@ pub fn setup_window_event_listener(app: &tauri::App) {
             None,
         );
 
-        // Pass app to window_proc
-        SetWindowLongPtrW(hwnd, GWLP_USERDATA, app as *const _ as isize);
-
-        // Register for shell hooks
-        RegisterShellHookWindow(hwnd);
-    }
-}
-
-pub fn setup_autostart(app: &tauri::App) {
-    // Only enable autostart in release builds
-    #[cfg(not(debug_assertions))]
-    {
-        use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+        if hwnd.0 == 0 {
+            return Err("Failed to create shell hook window".to_string());
+        }
 
-        app.handle()
-            .plugin(tauri_plugin_autostart::init(
-                MacosLauncher::LaunchAgent,
-                Some(vec!["--flag1", "--flag2"]),
-            ))
-            .unwrap();
+        // Pass the callback to window_proc, it lives as long as the window does
+        let callback_ptr = Box::into_raw(Box::new(callback));
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, callback_ptr as isize);
 
-        // Get the autostart manager
-        let autostart_manager = app.autolaunch();
-        // Enable autostart
-        let _ = autostart_manager.enable();
-        // Check enable state
-        match autostart_manager.is_enabled() {
-            Ok(enabled) => {
-                println!("Autostart enabled: {}", enabled);
-            }
-            Err(e) => {
-                println!("failed to enable autostart: {}", e);
-            }
+        // Register for shell hooks
+        if !RegisterShellHookWindow(hwnd).as_bool() {
+            return Err("Failed to register shell hook window".to_string());
         }
     }
+
+    Ok(())
 }
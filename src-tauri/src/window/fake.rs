@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MonitorGeometry, MoveTarget, WindowAction,
+    WindowBackend, WindowCapabilities, WindowEvent, WindowId, Workspace,
+};
+use crate::utils::process::{ProcessInfo, Sandbox, SandboxKind};
+use crate::window_manager::Window;
+use image::RgbaImage;
+
+/// Process names of the sample windows with the desktop file id and name of their application.
+const SAMPLE_APPS: &[(&str, &str, &str)] = &[
+    ("mail", "org.gnome.Evolution", "Evolution"),
+    ("code", "code", "Visual Studio Code"),
+    ("alacritty", "Alacritty", "Alacritty"),
+    ("chrome", "google-chrome", "Google Chrome"),
+];
+
+/// Outputs and workspaces windows can be moved between. The pointer is on the
+/// first output, workspaces go by their name.
+const SAMPLE_OUTPUTS: &[&str] = &["FAKE-1", "FAKE-2"];
+const SAMPLE_WORKSPACES: &[&str] = &["1", "2", "3"];
+
+/// In-memory backend for tests, it doesn't talk to any window system.
+///
+/// Windows are kept in z-order, focusing one moves it to the front and
+/// closing one removes it, so the manager and the commands behave the same
+/// way they do against a real desktop. Clones share their windows, so a test
+/// can keep one to open windows while the manager owns the other.
+#[derive(Default, Clone)]
+pub struct FakeBackend {
+    windows: Arc<Mutex<Vec<Window>>>,
+    callback: Arc<Mutex<Option<EventCallback>>>,
+    next_id: Arc<Mutex<WindowId>>,
+    /// Parent process ids, processes not in here were started by init.
+    parents: Arc<Mutex<HashMap<u32, u32>>>,
+    /// Index of the workspace being looked at, where new windows open.
+    current_workspace: Arc<Mutex<usize>>,
+    /// How often an executable path was looked up.
+    process_lookups: Arc<Mutex<usize>>,
+    /// Icon fingerprints of the windows that have an icon.
+    icons: Arc<Mutex<HashMap<WindowId, u64>>>,
+    /// How often an icon was rendered.
+    icon_renders: Arc<Mutex<usize>>,
+}
+
+impl FakeBackend {
+    pub fn with_sample_windows() -> Self {
+        let backend = Self::default();
+
+        backend.add_window("Inbox - Mail", 1001, "mail");
+        backend.add_window("README.md - binocular - Visual Studio Code", 1002, "code");
+        let terminal = backend.add_window("~/binocular", 1003, "alacritty");
+        backend.add_window("GitHub - Google Chrome", 1004, "chrome");
+        let devtools = backend.add_window("DevTools - Google Chrome", 1005, "chrome");
+        backend.set_parent_process(1005, 1004);
+        let _ = backend.move_window(devtools, &MoveTarget::Workspace("2".to_string()));
+        let _ = backend.move_window(terminal, &MoveTarget::Monitor("FAKE-2".to_string()));
+
+        backend
+    }
+
+    /// Opens a new window on top of the others and returns its id.
+    pub fn add_window(&self, title: &str, process_id: u32, process_name: &str) -> WindowId {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let workspace = SAMPLE_WORKSPACES[*self.current_workspace.lock().unwrap()];
+
+        self.windows.lock().unwrap().insert(
+            0,
+            Window {
+                id,
+                title: title.to_string(),
+                process_id,
+                process_name: Some(process_name.to_string()),
+                workspace_id: Some(workspace.to_string()),
+                workspace_name: Some(workspace.to_string()),
+                output: Some(SAMPLE_OUTPUTS[0].to_string()),
+                ..Default::default()
+            },
+        );
+
+        self.notify(WindowEvent::Created(id));
+        id
+    }
+
+    pub fn set_parent_process(&self, process_id: u32, parent_id: u32) {
+        self.parents.lock().unwrap().insert(process_id, parent_id);
+    }
+
+    /// Puts the window on another output, or on none like a minimized one.
+    pub fn set_output(&self, id: WindowId, output: Option<&str>) {
+        if let Some(window) = self.windows.lock().unwrap().iter_mut().find(|w| w.id == id) {
+            window.output = output.map(str::to_string);
+        }
+    }
+
+    pub fn process_lookups(&self) -> usize {
+        *self.process_lookups.lock().unwrap()
+    }
+
+    /// Gives the window an icon shared with its application, rendered as the fingerprint's bytes.
+    pub fn set_icon(&self, id: WindowId, fingerprint: u64) {
+        self.icons.lock().unwrap().insert(id, fingerprint);
+    }
+
+    pub fn icon_renders(&self) -> usize {
+        *self.icon_renders.lock().unwrap()
+    }
+
+    fn notify(&self, event: WindowEvent) {
+        if let Some(callback) = self.callback.lock().unwrap().as_ref() {
+            callback(event);
+        }
+    }
+}
+
+impl WindowBackend for FakeBackend {
+    fn get_windows(&self, current_pid: u32) -> Vec<Window> {
+        self.windows
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|window| window.process_id != current_pid)
+            .map(|window| Window {
+                process_name: None,
+                ..window.clone()
+            })
+            .collect()
+    }
+
+    /// The window on top has focus.
+    fn get_active_window(&self) -> Option<WindowId> {
+        self.windows.lock().unwrap().first().map(|window| window.id)
+    }
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        let mut windows = self.windows.lock().unwrap();
+        let index = windows
+            .iter()
+            .position(|window| window.id == id)
+            .ok_or_else(|| format!("No window with id {id}"))?;
+
+        let window = windows.remove(index);
+        windows.insert(0, window);
+        drop(windows);
+
+        self.notify(WindowEvent::Activated(id));
+        Ok(())
+    }
+
+    fn close_window(&self, id: WindowId) -> Result<(), String> {
+        {
+            let mut windows = self.windows.lock().unwrap();
+            let index = windows
+                .iter()
+                .position(|window| window.id == id)
+                .ok_or_else(|| format!("No window with id {id}"))?;
+
+            windows.remove(index);
+        }
+
+        self.notify(WindowEvent::Destroyed(id));
+        Ok(())
+    }
+
+    /// Only checks the window exists, the fake windows have no state to change.
+    fn window_action(&self, id: WindowId, _action: WindowAction) -> Result<(), String> {
+        if !self
+            .windows
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|window| window.id == id)
+        {
+            return Err(format!("No window with id {id}"));
+        }
+
+        self.notify(WindowEvent::Changed(id));
+        Ok(())
+    }
+
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities {
+            minimize: true,
+            maximize: true,
+            restore: true,
+            fullscreen: true,
+            always_on_top: true,
+            pin: true,
+            shade: true,
+            opacity: true,
+            summon: true,
+            preview: true,
+        }
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        let outputs = SAMPLE_OUTPUTS
+            .iter()
+            .map(|output| MoveTarget::Monitor(output.to_string()));
+        let workspaces = SAMPLE_WORKSPACES
+            .iter()
+            .map(|workspace| MoveTarget::Workspace(workspace.to_string()));
+
+        outputs.chain(workspaces).collect()
+    }
+
+    fn move_window(&self, id: WindowId, target: &MoveTarget) -> Result<(), String> {
+        {
+            let mut windows = self.windows.lock().unwrap();
+            let window = windows
+                .iter_mut()
+                .find(|window| window.id == id)
+                .ok_or_else(|| format!("No window with id {id}"))?;
+
+            match target {
+                MoveTarget::Monitor(name) if SAMPLE_OUTPUTS.contains(&name.as_str()) => {
+                    window.output = Some(name.clone());
+                }
+                MoveTarget::Workspace(name) if SAMPLE_WORKSPACES.contains(&name.as_str()) => {
+                    window.workspace_id = Some(name.clone());
+                    window.workspace_name = Some(name.clone());
+                }
+                _ => return Err(format!("Nowhere to move to called {target:?}")),
+            }
+        }
+
+        self.notify(WindowEvent::Changed(id));
+        Ok(())
+    }
+
+    fn summon_window(&self, id: WindowId) -> Result<(), String> {
+        let workspace = SAMPLE_WORKSPACES[*self.current_workspace.lock().unwrap()];
+        self.move_window(id, &MoveTarget::Workspace(workspace.to_string()))?;
+        self.move_window(id, &MoveTarget::Monitor(SAMPLE_OUTPUTS[0].to_string()))?;
+        self.focus_window(id)
+    }
+
+    fn get_window_icon(&self, window: &Window, _size: u32) -> Option<Vec<u8>> {
+        let fingerprint = *self.icons.lock().unwrap().get(&window.id)?;
+        *self.icon_renders.lock().unwrap() += 1;
+        Some(fingerprint.to_le_bytes().to_vec())
+    }
+
+    fn get_icon_handle(&self, id: WindowId) -> Option<IconHandle> {
+        let fingerprint = *self.icons.lock().unwrap().get(&id)?;
+        Some(IconHandle {
+            own: false,
+            fingerprint,
+        })
+    }
+
+    /// A flat color per window, so switching between previews is visible.
+    fn capture_window(&self, id: WindowId) -> Result<RgbaImage, String> {
+        if !self
+            .windows
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|window| window.id == id)
+        {
+            return Err(format!("No window with id {id}"));
+        }
+
+        let hue = (id as u32).wrapping_mul(0x9e37_79b9).to_le_bytes();
+        Ok(RgbaImage::from_pixel(
+            1280,
+            800,
+            image::Rgba([hue[1], hue[2], hue[3], 255]),
+        ))
+    }
+
+    fn get_process_name(&self, process_id: u32) -> Option<String> {
+        self.windows
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|window| window.process_id == process_id)
+            .and_then(|window| window.process_name.clone())
+    }
+
+    fn get_process_path(&self, process_id: u32) -> Option<String> {
+        *self.process_lookups.lock().unwrap() += 1;
+        let process_name = self.get_process_name(process_id)?;
+        Some(format!("/usr/bin/{process_name}"))
+    }
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32> {
+        Some(
+            self.parents
+                .lock()
+                .unwrap()
+                .get(&process_id)
+                .copied()
+                .unwrap_or(1),
+        )
+    }
+
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo> {
+        let exe_path = self.get_process_path(process_id)?;
+
+        Some(ProcessInfo {
+            pid: process_id,
+            parent_pid: self.get_parent_process_id(process_id),
+            command_line: vec![exe_path.clone()],
+            exe_path: Some(exe_path),
+            working_dir: Some("/home/user".to_string()),
+            user: Some("user".to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// The mail client is a Flatpak.
+    fn get_sandbox(&self, process_id: u32) -> Option<Sandbox> {
+        (self.get_process_name(process_id)? == "mail").then(|| Sandbox {
+            kind: SandboxKind::Flatpak,
+            app_id: Some("org.gnome.Evolution".to_string()),
+            container: None,
+        })
+    }
+
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        let current = *self.current_workspace.lock().unwrap();
+
+        SAMPLE_WORKSPACES
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Workspace {
+                id: name.to_string(),
+                name: name.to_string(),
+                visible: index == current,
+                window_count: 0,
+            })
+            .collect()
+    }
+
+    fn switch_workspace(&self, id: &str) -> Result<(), String> {
+        *self.current_workspace.lock().unwrap() = SAMPLE_WORKSPACES
+            .iter()
+            .position(|workspace| *workspace == id)
+            .ok_or_else(|| format!("No workspace with id {id}"))?;
+        Ok(())
+    }
+
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity> {
+        let process_name = window.process_name.as_deref()?;
+        let (_, app_id, app_name) = SAMPLE_APPS
+            .iter()
+            .find(|(process, _, _)| *process == process_name)?;
+
+        Some(AppIdentity {
+            app_id: Some(app_id.to_string()),
+            app_name: Some(app_name.to_string()),
+            desktop_file: Some(format!("/usr/share/applications/{app_id}.desktop")),
+        })
+    }
+
+    /// A laptop panel with a top bar, and a scaled external display to its right.
+    fn get_monitors(&self) -> Vec<Monitor> {
+        let laptop = MonitorGeometry {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let external = MonitorGeometry {
+            x: 1920,
+            y: 0,
+            width: 2560,
+            height: 1440,
+        };
+
+        vec![
+            Monitor {
+                name: SAMPLE_OUTPUTS[0].to_string(),
+                geometry: laptop,
+                work_area: MonitorGeometry {
+                    y: 32,
+                    height: 1048,
+                    ..laptop
+                },
+                scale: 1.0,
+                primary: true,
+                active: true,
+            },
+            Monitor {
+                name: SAMPLE_OUTPUTS[1].to_string(),
+                geometry: external,
+                work_area: external,
+                scale: 1.25,
+                primary: false,
+                active: false,
+            },
+        ]
+    }
+
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        *self.callback.lock().unwrap() = Some(callback);
+        Ok(())
+    }
+}
@@ -0,0 +1,827 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use image::RgbaImage;
+use serde::Deserialize;
+
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MonitorGeometry, MoveTarget, WindowAction,
+    WindowBackend, WindowCapabilities, WindowEvent, WindowId, Workspace,
+};
+use crate::utils::capture::linux_wayland::Region;
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::utils::{capture, icon, process};
+use crate::window_manager::Window;
+
+/// A client as reported by `j/clients`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HyprClient {
+    address: String,
+    mapped: bool,
+    hidden: bool,
+    workspace: HyprWorkspaceRef,
+    floating: bool,
+    monitor: i64,
+    /// Position in layout coordinates.
+    at: [i32; 2],
+    size: [i32; 2],
+    class: String,
+    title: String,
+    pid: i64,
+}
+
+#[derive(Deserialize)]
+struct HyprWorkspaceRef {
+    id: i64,
+    name: String,
+}
+
+/// A position as reported by `j/cursorpos`.
+#[derive(Deserialize)]
+struct HyprPosition {
+    x: i32,
+    y: i32,
+}
+
+/// A monitor as reported by `j/monitors`.
+#[derive(Deserialize)]
+struct HyprMonitor {
+    id: i64,
+    name: String,
+    /// Position in layout coordinates.
+    x: i32,
+    y: i32,
+    /// Size of the current mode in pixels, before scaling and rotation.
+    width: u32,
+    height: u32,
+    focused: bool,
+    scale: f64,
+    /// wl_output transform, odd ones are rotated by 90 or 270 degrees.
+    #[serde(default)]
+    transform: u8,
+    /// Space kept for bars along the left, top, right and bottom edges.
+    #[serde(default)]
+    reserved: [u32; 4],
+    #[serde(rename = "activeWorkspace")]
+    active_workspace: HyprWorkspaceRef,
+}
+
+/// Backend for Hyprland, driven through its request and event sockets.
+pub struct HyprlandBackend {
+    socket_dir: PathBuf,
+}
+
+impl HyprlandBackend {
+    pub fn new() -> Result<Self, String> {
+        let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE")
+            .map_err(|_| "HYPRLAND_INSTANCE_SIGNATURE is not set".to_string())?;
+
+        // Hyprland moved its sockets from /tmp into the runtime dir in v0.40
+        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_default();
+        let socket_dir = [
+            PathBuf::from(runtime_dir).join("hypr").join(&signature),
+            PathBuf::from("/tmp/hypr").join(&signature),
+        ]
+        .into_iter()
+        .find(|dir| dir.join(".socket.sock").exists())
+        .ok_or_else(|| format!("No Hyprland socket found for instance {signature}"))?;
+
+        Ok(Self { socket_dir })
+    }
+
+    /// Sends a single request over `.socket.sock`, which answers and closes the connection.
+    fn request(&self, command: &str) -> Result<String, String> {
+        let mut stream =
+            UnixStream::connect(self.socket_dir.join(".socket.sock")).map_err(|e| e.to_string())?;
+
+        stream
+            .write_all(command.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let mut reply = String::new();
+        stream
+            .read_to_string(&mut reply)
+            .map_err(|e| e.to_string())?;
+
+        Ok(reply)
+    }
+
+    fn request_json<T: for<'de> Deserialize<'de>>(&self, command: &str) -> Result<T, String> {
+        let reply = self.request(command)?;
+        serde_json::from_str(&reply).map_err(|e| e.to_string())
+    }
+
+    fn find_client(&self, id: WindowId) -> Result<HyprClient, String> {
+        let clients: Vec<HyprClient> = self.request_json("j/clients")?;
+        clients
+            .into_iter()
+            .find(|client| parse_address(&client.address) == Some(id))
+            .ok_or_else(|| format!("No window with id {id}"))
+    }
+
+    /// Moves a client to the workspace shown on `target`. Floating clients
+    /// take up the same part of it they took up on their old monitor, tiled
+    /// ones get laid out anew.
+    fn move_to_monitor(
+        &self,
+        client: &HyprClient,
+        monitors: &[HyprMonitor],
+        target: &HyprMonitor,
+    ) -> Result<(), String> {
+        let address = &client.address;
+
+        if client.monitor == target.id {
+            return Ok(());
+        }
+
+        self.dispatch(&format!(
+            "movetoworkspacesilent {},address:{address}",
+            target.active_workspace.id
+        ))?;
+
+        let Some(source) = monitors.iter().find(|monitor| monitor.id == client.monitor) else {
+            return Ok(());
+        };
+        if !client.floating {
+            return Ok(());
+        }
+
+        let rect = geometry(source).map_rect(
+            MonitorGeometry {
+                x: client.at[0],
+                y: client.at[1],
+                width: client.size[0].max(0) as u32,
+                height: client.size[1].max(0) as u32,
+            },
+            &geometry(target),
+        );
+
+        self.dispatch(&format!(
+            "resizewindowpixel exact {} {},address:{address}",
+            rect.width, rect.height
+        ))?;
+        self.dispatch(&format!(
+            "movewindowpixel exact {} {},address:{address}",
+            rect.x, rect.y
+        ))
+    }
+
+    fn dispatch(&self, dispatcher: &str) -> Result<(), String> {
+        let reply = self.request(&format!("dispatch {dispatcher}"))?;
+
+        match reply.trim() {
+            "ok" => Ok(()),
+            error => Err(error.to_string()),
+        }
+    }
+}
+
+impl WindowBackend for HyprlandBackend {
+    fn get_windows(&self, current_pid: u32) -> Vec<Window> {
+        let clients: Vec<HyprClient> = match self.request_json("j/clients") {
+            Ok(clients) => clients,
+            Err(e) => {
+                println!("Failed to list Hyprland clients: {e}");
+                return Vec::new();
+            }
+        };
+
+        let monitors: Vec<HyprMonitor> = self.request_json("j/monitors").unwrap_or_default();
+
+        clients
+            .into_iter()
+            .filter(|client| client.mapped && !client.hidden)
+            .filter(|client| client.pid != current_pid as i64)
+            .filter_map(|client| {
+                let output = monitors
+                    .iter()
+                    .find(|monitor| monitor.id == client.monitor)
+                    .map(|monitor| monitor.name.clone());
+
+                Some(Window {
+                    id: parse_address(&client.address)?,
+                    title: client.title,
+                    process_id: client.pid.max(0) as u32,
+                    app_id: Some(client.class).filter(|class| !class.is_empty()),
+                    workspace_id: Some(client.workspace.id.to_string()),
+                    workspace_name: Some(client.workspace.name),
+                    output,
+                    floating: Some(client.floating),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// `j/activewindow` is an empty object when nothing has focus.
+    fn get_active_window(&self) -> Option<WindowId> {
+        let active: serde_json::Value = self.request_json("j/activewindow").ok()?;
+        parse_address(active["address"].as_str()?)
+    }
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        self.dispatch(&format!("focuswindow address:0x{id:x}"))
+    }
+
+    fn close_window(&self, id: WindowId) -> Result<(), String> {
+        self.dispatch(&format!("closewindow address:0x{id:x}"))
+    }
+
+    fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String> {
+        match action {
+            WindowAction::ToggleFullscreen => {
+                // The fullscreen dispatcher only acts on the focused window
+                self.focus_window(id)?;
+                self.dispatch("fullscreen 0")
+            }
+            WindowAction::TogglePin => self.dispatch(&format!("pin address:0x{id:x}")),
+            WindowAction::SetOpacity(opacity) => {
+                // A request of its own up to Hyprland 0.47, a dispatcher since
+                let setprop = format!("setprop address:0x{id:x} alpha {opacity}");
+
+                match self.request(&setprop)?.trim() {
+                    "ok" => Ok(()),
+                    _ => self.dispatch(&setprop),
+                }
+            }
+            _ => Err(action.unsupported()),
+        }
+    }
+
+    /// Hyprland has no minimizing, and maximizing is a fullscreen mode.
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities {
+            fullscreen: true,
+            pin: true,
+            opacity: true,
+            summon: true,
+            preview: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        let monitors: Vec<HyprMonitor> = self.request_json("j/monitors").unwrap_or_default();
+        let workspaces: Vec<HyprWorkspaceRef> =
+            self.request_json("j/workspaces").unwrap_or_default();
+
+        let monitors = monitors
+            .into_iter()
+            .map(|monitor| MoveTarget::Monitor(monitor.name));
+        // Special workspaces are scratchpads, not places to keep a window
+        let workspaces = workspaces
+            .into_iter()
+            .filter(|workspace| workspace.id > 0)
+            .map(|workspace| MoveTarget::Workspace(workspace.name));
+
+        monitors.chain(workspaces).collect()
+    }
+
+    fn move_window(&self, id: WindowId, target: &MoveTarget) -> Result<(), String> {
+        match target {
+            MoveTarget::Monitor(name) => {
+                let client = self.find_client(id)?;
+                let monitors: Vec<HyprMonitor> = self.request_json("j/monitors")?;
+                let monitor = monitors
+                    .iter()
+                    .find(|monitor| &monitor.name == name)
+                    .ok_or_else(|| format!("No monitor named {name}"))?;
+
+                self.move_to_monitor(&client, &monitors, monitor)
+            }
+            MoveTarget::Workspace(name) => self.dispatch(&format!(
+                "movetoworkspacesilent name:{name},address:0x{id:x}"
+            )),
+        }
+    }
+
+    fn summon_window(&self, id: WindowId) -> Result<(), String> {
+        let client = self.find_client(id)?;
+        let monitors: Vec<HyprMonitor> = self.request_json("j/monitors")?;
+        let cursor: HyprPosition = self.request_json("j/cursorpos")?;
+
+        let monitor = monitors
+            .iter()
+            .find(|monitor| geometry(monitor).contains(cursor.x, cursor.y))
+            .or_else(|| monitors.iter().find(|monitor| monitor.focused))
+            .ok_or_else(|| "No monitor under the pointer".to_string())?;
+
+        if client.workspace.id != monitor.active_workspace.id {
+            if client.monitor == monitor.id {
+                self.dispatch(&format!(
+                    "movetoworkspacesilent {},address:{}",
+                    monitor.active_workspace.id, client.address
+                ))?;
+            } else {
+                self.move_to_monitor(&client, &monitors, monitor)?;
+            }
+        }
+
+        self.focus_window(id)
+    }
+
+    fn get_window_icon(&self, window: &Window, size: u32) -> Option<Vec<u8>> {
+        icon::from_app_id(window.app_id.as_deref()?, size)
+    }
+
+    fn get_icon_handle(&self, _id: WindowId) -> Option<IconHandle> {
+        None
+    }
+
+    /// Screencopies the window's area of its monitor, so it has to be on a visible workspace.
+    fn capture_window(&self, id: WindowId) -> Result<RgbaImage, String> {
+        let client = self.find_client(id)?;
+
+        let monitors: Vec<HyprMonitor> = self.request_json("j/monitors")?;
+        let monitor = monitors
+            .into_iter()
+            .find(|monitor| monitor.id == client.monitor)
+            .ok_or_else(|| "The window isn't on a monitor".to_string())?;
+
+        if monitor.active_workspace.id != client.workspace.id {
+            return Err("The window isn't on a visible workspace".to_string());
+        }
+
+        capture::linux_wayland::capture_output_region(
+            &monitor.name,
+            Region {
+                x: client.at[0] - monitor.x,
+                y: client.at[1] - monitor.y,
+                width: client.size[0],
+                height: client.size[1],
+            },
+        )
+    }
+
+    fn get_process_name(&self, process_id: u32) -> Option<String> {
+        process::get_process_name(process_id)
+    }
+
+    fn get_process_path(&self, process_id: u32) -> Option<String> {
+        process::get_process_path(process_id)
+    }
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32> {
+        process::get_parent_process_id(process_id)
+    }
+
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo> {
+        process::get_process_info(process_id)
+    }
+
+    fn get_sandbox(&self, process_id: u32) -> Option<Sandbox> {
+        process::get_sandbox(process_id)
+    }
+
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity> {
+        super::resolve_desktop_entry(window)
+    }
+
+    /// Hyprland has no primary monitor.
+    fn get_monitors(&self) -> Vec<Monitor> {
+        let monitors: Vec<HyprMonitor> = match self.request_json("j/monitors") {
+            Ok(monitors) => monitors,
+            Err(e) => {
+                println!("Failed to list Hyprland monitors: {e}");
+                return Vec::new();
+            }
+        };
+
+        monitors
+            .into_iter()
+            .map(|monitor| {
+                let geometry = geometry(&monitor);
+                let [left, top, right, bottom] = monitor.reserved;
+
+                Monitor {
+                    name: monitor.name,
+                    geometry,
+                    work_area: MonitorGeometry {
+                        x: geometry.x + left as i32,
+                        y: geometry.y + top as i32,
+                        width: geometry.width.saturating_sub(left + right),
+                        height: geometry.height.saturating_sub(top + bottom),
+                    },
+                    scale: monitor.scale,
+                    primary: false,
+                    active: monitor.focused,
+                }
+            })
+            .collect()
+    }
+
+    /// Special workspaces are left out, they're scratchpads toggled over the others.
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        let workspaces: Vec<HyprWorkspaceRef> = match self.request_json("j/workspaces") {
+            Ok(workspaces) => workspaces,
+            Err(e) => {
+                println!("Failed to list Hyprland workspaces: {e}");
+                return Vec::new();
+            }
+        };
+        let monitors: Vec<HyprMonitor> = self.request_json("j/monitors").unwrap_or_default();
+
+        let mut workspaces: Vec<Workspace> = workspaces
+            .into_iter()
+            .filter(|workspace| workspace.id > 0)
+            .map(|workspace| Workspace {
+                id: workspace.id.to_string(),
+                name: workspace.name,
+                visible: monitors
+                    .iter()
+                    .any(|monitor| monitor.active_workspace.id == workspace.id),
+                window_count: 0,
+            })
+            .collect();
+
+        // Listed in creation order, numbered ones read better in order
+        workspaces.sort_by_key(|workspace| workspace.id.parse::<i64>().unwrap_or_default());
+        workspaces
+    }
+
+    fn switch_workspace(&self, id: &str) -> Result<(), String> {
+        let id: i64 = id
+            .parse()
+            .map_err(|_| format!("No workspace with id {id}"))?;
+        self.dispatch(&format!("workspace {id}"))
+    }
+
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        let stream = UnixStream::connect(self.socket_dir.join(".socket2.sock"))
+            .map_err(|e| e.to_string())?;
+
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        println!("Lost connection to Hyprland: {e}");
+                        return;
+                    }
+                };
+
+                if let Some(event) = parse_event(&line) {
+                    callback(event);
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Parses a `.socket2.sock` line of the form `EVENT>>DATA`.
+fn parse_event(line: &str) -> Option<WindowEvent> {
+    let (name, data) = line.split_once(">>")?;
+
+    // Event addresses are bare hex, the first field for all the events we care about
+    let address = data.split(',').next()?;
+    let id = || WindowId::from_str_radix(address, 16).ok();
+
+    match name {
+        "openwindow" => Some(WindowEvent::Created(id()?)),
+        "closewindow" => Some(WindowEvent::Destroyed(id()?)),
+        "windowtitle" => Some(WindowEvent::Changed(id()?)),
+        // activewindow only carries class and title, its v2 twin carries the address
+        "activewindowv2" => Some(WindowEvent::Activated(id()?)),
+        _ => None,
+    }
+}
+
+/// The monitor's place in layout coordinates, where clients are positioned.
+fn geometry(monitor: &HyprMonitor) -> MonitorGeometry {
+    let (width, height) = match monitor.transform % 2 {
+        1 => (monitor.height, monitor.width),
+        _ => (monitor.width, monitor.height),
+    };
+    let scale = if monitor.scale > 0.0 {
+        monitor.scale
+    } else {
+        1.0
+    };
+
+    MonitorGeometry {
+        x: monitor.x,
+        y: monitor.y,
+        width: (width as f64 / scale).round() as u32,
+        height: (height as f64 / scale).round() as u32,
+    }
+}
+
+/// Parses a `0x`-prefixed client address.
+fn parse_address(address: &str) -> Option<WindowId> {
+    WindowId::from_str_radix(address.trim_start_matches("0x"), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::os::unix::net::UnixListener;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Duration;
+
+    static NEXT_INSTANCE: AtomicU32 = AtomicU32::new(0);
+
+    /// A laptop panel at scale 2 left of a 1440p monitor turned on its side.
+    const MONITORS: &str = r#"[
+        {"id": 0, "name": "eDP-1", "x": 0, "y": 0, "width": 2880, "height": 1800,
+         "focused": true, "scale": 2.0, "transform": 0, "reserved": [0, 30, 0, 0],
+         "activeWorkspace": {"id": 1, "name": "1"}},
+        {"id": 1, "name": "DP-1", "x": 1440, "y": 0, "width": 2560, "height": 1440,
+         "focused": false, "scale": 1.0, "transform": 1, "reserved": [0, 0, 0, 0],
+         "activeWorkspace": {"id": 3, "name": "web"}}
+    ]"#;
+
+    const CLIENTS: &str = r#"[
+        {"address": "0x55d1e0a0", "mapped": true, "hidden": false,
+         "workspace": {"id": 1, "name": "1"}, "floating": false, "monitor": 0,
+         "at": [0, 30], "size": [1440, 870], "class": "foot", "title": "~", "pid": 2001},
+        {"address": "0x55d1e0b0", "mapped": true, "hidden": false,
+         "workspace": {"id": 3, "name": "web"}, "floating": true, "monitor": 1,
+         "at": [1800, 400], "size": [720, 1280], "class": "firefox", "title": "Mozilla Firefox",
+         "pid": 2002},
+        {"address": "0x55d1e0c0", "mapped": false, "hidden": false,
+         "workspace": {"id": 3, "name": "web"}, "floating": true, "monitor": 1,
+         "at": [0, 0], "size": [0, 0], "class": "", "title": "", "pid": 2003},
+        {"address": "0x55d1e0d0", "mapped": true, "hidden": true,
+         "workspace": {"id": -98, "name": "special:scratch"}, "floating": true, "monitor": 0,
+         "at": [0, 0], "size": [400, 300], "class": "pavucontrol", "title": "Volume",
+         "pid": 2004},
+        {"address": "0x55d1e0e0", "mapped": true, "hidden": false,
+         "workspace": {"id": 1, "name": "1"}, "floating": false, "monitor": 0,
+         "at": [720, 30], "size": [720, 870], "class": "binocular", "title": "binocular",
+         "pid": 2005}
+    ]"#;
+
+    const WORKSPACES: &str = r#"[
+        {"id": 3, "name": "web"},
+        {"id": -98, "name": "special:scratch"},
+        {"id": 1, "name": "1"}
+    ]"#;
+
+    const FOOT: WindowId = 0x55d1e0a0;
+    const FIREFOX: WindowId = 0x55d1e0b0;
+
+    /// Hyprland's two sockets in a directory of their own. Requests get the
+    /// canned replies, dispatchers and unknown requests are kept to look at.
+    struct MockHyprland {
+        socket_dir: PathBuf,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockHyprland {
+        fn start(replies: HashMap<&'static str, &'static str>, events: &'static str) -> Self {
+            let socket_dir = std::env::temp_dir().join(format!(
+                "binocular-hypr-{}-{}",
+                std::process::id(),
+                NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&socket_dir).unwrap();
+
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let listener = UnixListener::bind(socket_dir.join(".socket.sock")).unwrap();
+            let received = requests.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut request = [0u8; 1024];
+                    let length = stream.read(&mut request).unwrap();
+                    let request = String::from_utf8_lossy(&request[..length]).into_owned();
+
+                    let reply = match replies.get(request.as_str()) {
+                        Some(reply) => reply,
+                        None if request.starts_with("dispatch ") => "ok",
+                        None => "unknown request",
+                    };
+                    if !request.starts_with("j/") {
+                        received.lock().unwrap().push(request);
+                    }
+                    stream.write_all(reply.as_bytes()).unwrap();
+                }
+            });
+
+            let listener = UnixListener::bind(socket_dir.join(".socket2.sock")).unwrap();
+            std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                stream.write_all(events.as_bytes()).unwrap();
+            });
+
+            Self {
+                socket_dir,
+                requests,
+            }
+        }
+
+        fn backend(&self) -> HyprlandBackend {
+            HyprlandBackend {
+                socket_dir: self.socket_dir.clone(),
+            }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for MockHyprland {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.socket_dir);
+        }
+    }
+
+    fn hyprland() -> MockHyprland {
+        MockHyprland::start(
+            HashMap::from([
+                ("j/clients", CLIENTS),
+                ("j/monitors", MONITORS),
+                ("j/workspaces", WORKSPACES),
+                ("j/cursorpos", r#"{"x": 400, "y": 300}"#),
+            ]),
+            "",
+        )
+    }
+
+    #[test]
+    fn parses_events() {
+        assert_eq!(
+            parse_event("openwindow>>55d1e0a0,1,foot,~"),
+            Some(WindowEvent::Created(FOOT))
+        );
+        assert_eq!(
+            parse_event("closewindow>>55d1e0a0"),
+            Some(WindowEvent::Destroyed(FOOT))
+        );
+        assert_eq!(
+            parse_event("windowtitle>>55d1e0b0"),
+            Some(WindowEvent::Changed(FIREFOX))
+        );
+        assert_eq!(
+            parse_event("activewindowv2>>55d1e0b0"),
+            Some(WindowEvent::Activated(FIREFOX))
+        );
+
+        assert!(parse_event("activewindow>>firefox,Mozilla Firefox").is_none());
+        assert!(parse_event("workspace>>2").is_none());
+        assert!(parse_event("openwindow>>not an address,1,foot,~").is_none());
+        assert!(parse_event("garbage").is_none());
+    }
+
+    #[test]
+    fn parses_addresses() {
+        assert_eq!(parse_address("0x55d1e0a0"), Some(FOOT));
+        assert_eq!(parse_address("55d1e0a0"), Some(FOOT));
+        assert_eq!(parse_address("0xnope"), None);
+    }
+
+    #[test]
+    fn monitors_are_in_layout_coordinates() {
+        let hyprland = hyprland();
+        let monitors = hyprland.backend().get_monitors();
+
+        let laptop = &monitors[0];
+        assert_eq!(laptop.name, "eDP-1");
+        assert_eq!(
+            laptop.geometry,
+            MonitorGeometry {
+                x: 0,
+                y: 0,
+                width: 1440,
+                height: 900
+            }
+        );
+        assert_eq!(laptop.work_area.y, 30);
+        assert_eq!(laptop.work_area.height, 870);
+        assert_eq!(laptop.scale, 2.0);
+        assert!(laptop.active);
+
+        // Rotated, so it's taller than wide
+        let side = &monitors[1];
+        assert_eq!((side.geometry.width, side.geometry.height), (1440, 2560));
+        assert!(!side.active && !side.primary);
+    }
+
+    #[test]
+    fn lists_mapped_clients() {
+        let hyprland = hyprland();
+        let windows = hyprland.backend().get_windows(2005);
+
+        let ids: Vec<_> = windows.iter().map(|window| window.id).collect();
+        assert_eq!(ids, [FOOT, FIREFOX]);
+
+        let firefox = &windows[1];
+        assert_eq!(firefox.title, "Mozilla Firefox");
+        assert_eq!(firefox.process_id, 2002);
+        assert_eq!(firefox.app_id.as_deref(), Some("firefox"));
+        assert_eq!(firefox.workspace_id.as_deref(), Some("3"));
+        assert_eq!(firefox.workspace_name.as_deref(), Some("web"));
+        assert_eq!(firefox.output.as_deref(), Some("DP-1"));
+        assert_eq!(firefox.floating, Some(true));
+    }
+
+    #[test]
+    fn lists_regular_workspaces_in_order() {
+        let hyprland = hyprland();
+        let workspaces = hyprland.backend().get_workspaces();
+
+        let listed: Vec<_> = workspaces
+            .iter()
+            .map(|workspace| (workspace.id.as_str(), workspace.visible))
+            .collect();
+        assert_eq!(listed, [("1", true), ("3", true)]);
+    }
+
+    #[test]
+    fn summoning_moves_to_the_monitor_under_the_pointer() {
+        let hyprland = hyprland();
+        hyprland.backend().summon_window(FIREFOX).unwrap();
+
+        // Half the width and height of the side monitor on the laptop panel
+        assert_eq!(
+            hyprland.requests(),
+            [
+                "dispatch movetoworkspacesilent 1,address:0x55d1e0b0",
+                "dispatch resizewindowpixel exact 720 450,address:0x55d1e0b0",
+                "dispatch movewindowpixel exact 360 141,address:0x55d1e0b0",
+                "dispatch focuswindow address:0x55d1e0b0",
+            ]
+        );
+    }
+
+    #[test]
+    fn window_actions_dispatch() {
+        let hyprland = hyprland();
+        let backend = hyprland.backend();
+
+        backend.close_window(FOOT).unwrap();
+        backend
+            .window_action(FOOT, WindowAction::TogglePin)
+            .unwrap();
+        backend
+            .window_action(FOOT, WindowAction::SetOpacity(0.5))
+            .unwrap();
+        backend
+            .move_window(FOOT, &MoveTarget::Workspace("web".to_string()))
+            .unwrap();
+        assert!(backend.window_action(FOOT, WindowAction::Minimize).is_err());
+        assert!(backend.switch_workspace("web").is_err());
+
+        // Newer Hyprland only knows setprop as a dispatcher
+        assert_eq!(
+            hyprland.requests(),
+            [
+                "dispatch closewindow address:0x55d1e0a0",
+                "dispatch pin address:0x55d1e0a0",
+                "setprop address:0x55d1e0a0 alpha 0.5",
+                "dispatch setprop address:0x55d1e0a0 alpha 0.5",
+                "dispatch movetoworkspacesilent name:web,address:0x55d1e0a0",
+            ]
+        );
+    }
+
+    #[test]
+    fn dispatch_errors_come_back() {
+        let hyprland = MockHyprland::start(
+            HashMap::from([("dispatch focuswindow address:0x1", "No such window found")]),
+            "",
+        );
+
+        assert_eq!(
+            hyprland.backend().focus_window(1),
+            Err("No such window found".to_string())
+        );
+    }
+
+    #[test]
+    fn streams_window_events() {
+        let hyprland = MockHyprland::start(
+            HashMap::new(),
+            "workspace>>2\nopenwindow>>55d1e0a0,1,foot,~\nwindowtitle>>55d1e0a0\n\
+             activewindowv2>>55d1e0a0\nclosewindow>>55d1e0a0\n",
+        );
+        let (sender, events) = mpsc::channel();
+
+        hyprland
+            .backend()
+            .subscribe(Box::new(move |event| {
+                let _ = sender.send(event);
+            }))
+            .unwrap();
+
+        let events: Vec<_> = (0..4)
+            .map(|_| events.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        assert_eq!(
+            events,
+            [
+                WindowEvent::Created(FOOT),
+                WindowEvent::Changed(FOOT),
+                WindowEvent::Activated(FOOT),
+                WindowEvent::Destroyed(FOOT),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1041 @@
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::Mutex;
+
+use image::RgbaImage;
+use serde_json::Value;
+use x11rb::protocol::xproto::AtomEnum;
+
+use super::linux_x11::{self, X11Session};
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MonitorGeometry, MoveTarget, WindowAction,
+    WindowBackend, WindowCapabilities, WindowEvent, WindowId, WindowType, Workspace,
+};
+use crate::utils::capture::linux_wayland::Region;
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::utils::{capture, icon, process};
+use crate::window_manager::Window;
+
+const MAGIC: &[u8; 6] = b"i3-ipc";
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_OUTPUTS: u32 = 3;
+const GET_TREE: u32 = 4;
+
+/// Event replies have the high bit set, the rest is the event type.
+const EVENT_WINDOW: u32 = 0x8000_0003;
+
+/// A connection to the sway / i3 IPC socket.
+pub struct I3Ipc {
+    stream: UnixStream,
+}
+
+impl I3Ipc {
+    pub fn connect(path: &str) -> Result<Self, String> {
+        let stream = UnixStream::connect(path).map_err(|e| format!("{path}: {e}"))?;
+        Ok(Self { stream })
+    }
+
+    fn send(&mut self, message_type: u32, payload: &str) -> io::Result<()> {
+        let mut message = Vec::with_capacity(14 + payload.len());
+        message.extend_from_slice(MAGIC);
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&message_type.to_ne_bytes());
+        message.extend_from_slice(payload.as_bytes());
+
+        self.stream.write_all(&message)
+    }
+
+    fn receive(&mut self) -> io::Result<(u32, Vec<u8>)> {
+        let mut header = [0u8; 14];
+        self.stream.read_exact(&mut header)?;
+
+        if &header[..6] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid i3-ipc header",
+            ));
+        }
+
+        let length = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+        let message_type = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+
+        let mut payload = vec![0u8; length as usize];
+        self.stream.read_exact(&mut payload)?;
+
+        Ok((message_type, payload))
+    }
+
+    pub fn request(&mut self, message_type: u32, payload: &str) -> Result<Value, String> {
+        self.send(message_type, payload)
+            .map_err(|e| e.to_string())?;
+
+        let (_, reply) = self.receive().map_err(|e| e.to_string())?;
+        serde_json::from_slice(&reply).map_err(|e| e.to_string())
+    }
+
+    /// Runs a command and reports the first failure, if any.
+    pub fn run_command(&mut self, command: &str) -> Result<(), String> {
+        let reply = self.request(RUN_COMMAND, command)?;
+
+        let failure = reply
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|result| result["success"] != Value::Bool(true));
+
+        match failure {
+            Some(result) => Err(result["error"]
+                .as_str()
+                .unwrap_or("command failed")
+                .to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Which of the two speaks on the other end of the socket.
+enum Flavor {
+    Sway,
+    /// i3 runs on X11 and leaves pids out of its tree, the session looks
+    /// them up on the windows themselves.
+    I3(Option<Box<X11Session>>),
+}
+
+/// Backend for sway and i3, driven through their IPC socket.
+///
+/// Unlike the generic protocols this sees the whole layout tree, so windows
+/// carry their workspace, output, floating state and marks.
+pub struct SwayBackend {
+    socket: String,
+    ipc: Mutex<I3Ipc>,
+    flavor: Flavor,
+}
+
+impl SwayBackend {
+    pub fn new() -> Result<Self, String> {
+        // sway sets I3SOCK as well, only SWAYSOCK tells them apart
+        if let Ok(socket) = std::env::var("SWAYSOCK") {
+            return Self::connect(socket, Flavor::Sway);
+        }
+
+        let socket = std::env::var("I3SOCK")
+            .map_err(|_| "Neither SWAYSOCK nor I3SOCK is set".to_string())?;
+        let x11 = match linux_x11::connect() {
+            Ok(session) => Some(Box::new(session)),
+            Err(e) => {
+                println!("Failed to connect to X server, windows will have no process: {e}");
+                None
+            }
+        };
+
+        Self::connect(socket, Flavor::I3(x11))
+    }
+
+    fn connect(socket: String, flavor: Flavor) -> Result<Self, String> {
+        Ok(Self {
+            ipc: Mutex::new(I3Ipc::connect(&socket)?),
+            socket,
+            flavor,
+        })
+    }
+
+    /// The `_NET_WM_PID` of an X11 window under i3.
+    fn x11_pid(&self, window: u32) -> Option<u32> {
+        let Flavor::I3(Some(session)) = &self.flavor else {
+            return None;
+        };
+
+        linux_x11::get_property32(
+            &session.conn,
+            window,
+            session.atoms._NET_WM_PID,
+            AtomEnum::CARDINAL.into(),
+        )?
+        .first()
+        .copied()
+    }
+}
+
+impl WindowBackend for SwayBackend {
+    fn get_windows(&self, current_pid: u32) -> Vec<Window> {
+        let tree = match self.ipc.lock().unwrap().request(GET_TREE, "") {
+            Ok(tree) => tree,
+            Err(e) => {
+                println!("Failed to get sway tree: {e}");
+                return Vec::new();
+            }
+        };
+
+        let mut windows = Vec::new();
+        collect_windows(
+            &tree,
+            &Location::default(),
+            &|window| self.x11_pid(window),
+            &mut windows,
+        );
+
+        windows.retain(|window| window.process_id != current_pid);
+        windows
+    }
+
+    fn get_active_window(&self) -> Option<WindowId> {
+        let tree = self.ipc.lock().unwrap().request(GET_TREE, "").ok()?;
+        find_focused(&tree)
+    }
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        self.ipc
+            .lock()
+            .unwrap()
+            .run_command(&format!("[con_id={id}] focus"))
+    }
+
+    fn close_window(&self, id: WindowId) -> Result<(), String> {
+        self.ipc
+            .lock()
+            .unwrap()
+            .run_command(&format!("[con_id={id}] kill"))
+    }
+
+    fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String> {
+        let command = match action {
+            WindowAction::ToggleFullscreen => "fullscreen toggle".to_string(),
+            // Only has an effect on floating windows
+            WindowAction::TogglePin => "sticky toggle".to_string(),
+            WindowAction::SetOpacity(opacity) => format!("opacity {opacity}"),
+            _ => return Err(action.unsupported()),
+        };
+
+        self.ipc
+            .lock()
+            .unwrap()
+            .run_command(&format!("[con_id={id}] {command}"))
+    }
+
+    /// Tiling leaves nothing to minimize or maximize.
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities {
+            fullscreen: true,
+            pin: true,
+            // i3 has neither an opacity command nor screencopy
+            opacity: matches!(self.flavor, Flavor::Sway),
+            summon: true,
+            preview: matches!(self.flavor, Flavor::Sway),
+            ..Default::default()
+        }
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        let mut ipc = self.ipc.lock().unwrap();
+
+        let outputs = ipc.request(GET_OUTPUTS, "").unwrap_or_default();
+        let workspaces = ipc.request(GET_WORKSPACES, "").unwrap_or_default();
+
+        let outputs = outputs
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|output| output["active"] == Value::Bool(true))
+            .filter_map(|output| output["name"].as_str())
+            .map(|name| MoveTarget::Monitor(name.to_string()));
+        let workspaces = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|workspace| workspace["name"].as_str())
+            .map(|name| MoveTarget::Workspace(name.to_string()));
+
+        outputs.chain(workspaces).collect()
+    }
+
+    fn move_window(&self, id: WindowId, target: &MoveTarget) -> Result<(), String> {
+        let mut ipc = self.ipc.lock().unwrap();
+
+        let command = match target {
+            MoveTarget::Monitor(name) => {
+                let tree = ipc.request(GET_TREE, "")?;
+                let outputs = ipc.request(GET_OUTPUTS, "")?;
+
+                let (node, location) = find_window(&tree, id, &Location::default())
+                    .ok_or_else(|| format!("No window with id {id}"))?;
+                let output =
+                    output_rect(&outputs, name).ok_or_else(|| format!("No output named {name}"))?;
+
+                format!(
+                    "move container to output {}{}",
+                    quote(name),
+                    keep_relative(node, &location, &outputs, &output)
+                )
+            }
+            MoveTarget::Workspace(name) => {
+                format!("move container to workspace {}", quote(name))
+            }
+        };
+
+        ipc.run_command(&format!("[con_id={id}] {command}"))
+    }
+
+    /// sway has no pointer query, but its focused output follows the pointer by default.
+    fn summon_window(&self, id: WindowId) -> Result<(), String> {
+        let mut ipc = self.ipc.lock().unwrap();
+
+        let tree = ipc.request(GET_TREE, "")?;
+        let outputs = ipc.request(GET_OUTPUTS, "")?;
+        let workspaces = ipc.request(GET_WORKSPACES, "")?;
+
+        let (node, location) = find_window(&tree, id, &Location::default())
+            .ok_or_else(|| format!("No window with id {id}"))?;
+        let workspace = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|workspace| workspace["focused"] == Value::Bool(true))
+            .ok_or_else(|| "No workspace has focus".to_string())?;
+        let name = workspace["name"].as_str().unwrap_or_default();
+        let output = workspace["output"]
+            .as_str()
+            .and_then(|output| output_rect(&outputs, output))
+            .ok_or_else(|| "The focused workspace isn't on an output".to_string())?;
+
+        ipc.run_command(&format!(
+            "[con_id={id}] move container to workspace {}{}, focus",
+            quote(name),
+            keep_relative(node, &location, &outputs, &output)
+        ))
+    }
+
+    fn get_window_icon(&self, window: &Window, size: u32) -> Option<Vec<u8>> {
+        icon::from_app_id(window.app_id.as_deref()?, size)
+    }
+
+    fn get_icon_handle(&self, _id: WindowId) -> Option<IconHandle> {
+        None
+    }
+
+    /// Screencopies the window's area of its output, so it has to be on a visible workspace.
+    fn capture_window(&self, id: WindowId) -> Result<RgbaImage, String> {
+        if let Flavor::I3(_) = self.flavor {
+            return Err("Window previews aren't supported on i3".to_string());
+        }
+
+        let (tree, outputs) = {
+            let mut ipc = self.ipc.lock().unwrap();
+            (ipc.request(GET_TREE, "")?, ipc.request(GET_OUTPUTS, "")?)
+        };
+
+        let (node, location) = find_window(&tree, id, &Location::default())
+            .ok_or_else(|| format!("No window with id {id}"))?;
+
+        if node["visible"] != Value::Bool(true) {
+            return Err("The window isn't on a visible workspace".to_string());
+        }
+
+        let output_name = location
+            .output
+            .ok_or_else(|| "The window isn't on an output".to_string())?;
+        let output = output_rect(&outputs, &output_name)
+            .ok_or_else(|| format!("No output named {output_name}"))?;
+        let rect = parse_rect(&node["rect"]).ok_or_else(|| "The window has no size".to_string())?;
+
+        // The tree is in layout coordinates, screencopy wants them relative to the output
+        capture::linux_wayland::capture_output_region(
+            &output_name,
+            Region {
+                x: rect.x - output.x,
+                y: rect.y - output.y,
+                width: rect.width as i32,
+                height: rect.height as i32,
+            },
+        )
+    }
+
+    fn get_process_name(&self, process_id: u32) -> Option<String> {
+        process::get_process_name(process_id)
+    }
+
+    fn get_process_path(&self, process_id: u32) -> Option<String> {
+        process::get_process_path(process_id)
+    }
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32> {
+        process::get_parent_process_id(process_id)
+    }
+
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo> {
+        process::get_process_info(process_id)
+    }
+
+    fn get_sandbox(&self, process_id: u32) -> Option<Sandbox> {
+        process::get_sandbox(process_id)
+    }
+
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity> {
+        super::resolve_desktop_entry(window)
+    }
+
+    /// Workspace rects leave out the bars, so the visible workspace of an
+    /// output gives its work area.
+    fn get_monitors(&self) -> Vec<Monitor> {
+        let (outputs, workspaces) = {
+            let mut ipc = self.ipc.lock().unwrap();
+            match (
+                ipc.request(GET_OUTPUTS, ""),
+                ipc.request(GET_WORKSPACES, ""),
+            ) {
+                (Ok(outputs), Ok(workspaces)) => (outputs, workspaces),
+                (Err(e), _) | (_, Err(e)) => {
+                    println!("Failed to get sway outputs: {e}");
+                    return Vec::new();
+                }
+            }
+        };
+        let workspaces = workspaces.as_array().cloned().unwrap_or_default();
+
+        outputs
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|output| output["active"] == Value::Bool(true))
+            .filter_map(|output| {
+                let name = output["name"].as_str()?;
+                let geometry = parse_rect(&output["rect"])?;
+                let workspace = workspaces.iter().find(|workspace| {
+                    workspace["output"].as_str() == Some(name)
+                        && workspace["visible"] == Value::Bool(true)
+                });
+
+                Some(Monitor {
+                    name: name.to_string(),
+                    geometry,
+                    work_area: workspace
+                        .and_then(|workspace| parse_rect(&workspace["rect"]))
+                        .unwrap_or(geometry),
+                    // i3 doesn't scale outputs
+                    scale: output["scale"].as_f64().unwrap_or(1.0),
+                    primary: output["primary"] == Value::Bool(true),
+                    active: workspace
+                        .is_some_and(|workspace| workspace["focused"] == Value::Bool(true)),
+                })
+            })
+            .collect()
+    }
+
+    /// Workspaces go by container id, names can change through `rename workspace`.
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        let workspaces = match self.ipc.lock().unwrap().request(GET_WORKSPACES, "") {
+            Ok(workspaces) => workspaces,
+            Err(e) => {
+                println!("Failed to get sway workspaces: {e}");
+                return Vec::new();
+            }
+        };
+
+        workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|workspace| {
+                Some(Workspace {
+                    id: workspace["id"].as_i64()?.to_string(),
+                    name: workspace["name"].as_str()?.to_string(),
+                    visible: workspace["visible"] == Value::Bool(true),
+                    window_count: 0,
+                })
+            })
+            .collect()
+    }
+
+    fn switch_workspace(&self, id: &str) -> Result<(), String> {
+        let workspace = self
+            .get_workspaces()
+            .into_iter()
+            .find(|workspace| workspace.id == id)
+            .ok_or_else(|| format!("No workspace with id {id}"))?;
+
+        self.ipc
+            .lock()
+            .unwrap()
+            .run_command(&format!("workspace {}", quote(&workspace.name)))
+    }
+
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        // Events arrive on a dedicated connection so they never interleave with replies
+        let mut ipc = I3Ipc::connect(&self.socket)?;
+
+        let reply = ipc.request(SUBSCRIBE, r#"["window"]"#)?;
+        if reply["success"] != Value::Bool(true) {
+            return Err("Failed to subscribe to window events".to_string());
+        }
+
+        std::thread::spawn(move || loop {
+            let (message_type, payload) = match ipc.receive() {
+                Ok(message) => message,
+                Err(e) => {
+                    println!("Lost connection to sway: {e}");
+                    return;
+                }
+            };
+
+            if message_type != EVENT_WINDOW {
+                continue;
+            }
+
+            let Ok(event) = serde_json::from_slice::<Value>(&payload) else {
+                continue;
+            };
+
+            let Some(id) = event["container"]["id"].as_i64() else {
+                continue;
+            };
+            let id = id as WindowId;
+
+            callback(match event["change"].as_str() {
+                Some("new") => WindowEvent::Created(id),
+                Some("close") => WindowEvent::Destroyed(id),
+                Some("focus") => WindowEvent::Activated(id),
+                _ => WindowEvent::Changed(id),
+            });
+        });
+
+        Ok(())
+    }
+}
+
+/// Where in the tree a container sits.
+#[derive(Default, Clone)]
+struct Location {
+    output: Option<String>,
+    workspace_id: Option<String>,
+    workspace: Option<String>,
+    floating: bool,
+}
+
+/// Collects the views under `node`, `x11_pid` looks up the pid of views that
+/// only come with an X11 window id.
+fn collect_windows(
+    node: &Value,
+    location: &Location,
+    x11_pid: &dyn Fn(u32) -> Option<u32>,
+    windows: &mut Vec<Window>,
+) {
+    let mut location = location.clone();
+
+    match node["type"].as_str() {
+        Some("output") => location.output = node["name"].as_str().map(str::to_string),
+        Some("workspace") => {
+            location.workspace_id = node["id"].as_i64().map(|id| id.to_string());
+            location.workspace = node["name"].as_str().map(str::to_string);
+        }
+        Some("floating_con") => location.floating = true,
+        _ => {}
+    }
+
+    let nodes = node["nodes"].as_array().into_iter().flatten();
+    let floating_nodes = node["floating_nodes"].as_array().into_iter().flatten();
+    let mut children = nodes.chain(floating_nodes).peekable();
+
+    // Views are the leaves: they have a pid (sway) or an X11 window id (i3)
+    if children.peek().is_none() && (node["pid"].is_u64() || node["window"].is_u64()) {
+        windows.push(parse_window(node, &location, x11_pid));
+        return;
+    }
+
+    for child in children {
+        collect_windows(child, &location, x11_pid, windows);
+    }
+}
+
+/// Finds the view with container id `id` and where it sits in the tree.
+fn find_window<'a>(
+    node: &'a Value,
+    id: WindowId,
+    location: &Location,
+) -> Option<(&'a Value, Location)> {
+    let mut location = location.clone();
+
+    match node["type"].as_str() {
+        Some("output") => location.output = node["name"].as_str().map(str::to_string),
+        Some("workspace") => {
+            location.workspace_id = node["id"].as_i64().map(|id| id.to_string());
+            location.workspace = node["name"].as_str().map(str::to_string);
+        }
+        Some("floating_con") => location.floating = true,
+        _ => {}
+    }
+
+    if node["id"].as_i64() == Some(id as i64) {
+        return Some((node, location));
+    }
+
+    let nodes = node["nodes"].as_array().into_iter().flatten();
+    let floating_nodes = node["floating_nodes"].as_array().into_iter().flatten();
+
+    nodes
+        .chain(floating_nodes)
+        .find_map(|child| find_window(child, id, &location))
+}
+
+/// Finds the focused view, workspaces have focus when they're empty.
+fn find_focused(node: &Value) -> Option<WindowId> {
+    let is_view = node["pid"].is_u64() || node["window"].is_u64();
+    if node["focused"] == Value::Bool(true) && is_view {
+        return node["id"].as_i64().map(|id| id as WindowId);
+    }
+
+    let nodes = node["nodes"].as_array().into_iter().flatten();
+    let floating_nodes = node["floating_nodes"].as_array().into_iter().flatten();
+
+    nodes.chain(floating_nodes).find_map(find_focused)
+}
+
+fn parse_window(node: &Value, location: &Location, x11_pid: &dyn Fn(u32) -> Option<u32>) -> Window {
+    // sway reports app_id for native Wayland clients and window_properties for XWayland ones
+    let app_id = node["app_id"]
+        .as_str()
+        .or_else(|| node["window_properties"]["class"].as_str())
+        .map(str::to_string);
+
+    // Only XWayland windows carry a window type, native ones are all normal
+    let window_type = match node["window_type"].as_str() {
+        Some("dialog") => WindowType::Dialog,
+        Some("utility" | "toolbar" | "menu" | "dropdown_menu" | "popup_menu") => {
+            WindowType::Utility
+        }
+        Some("splash" | "tooltip" | "notification" | "dock" | "desktop") => WindowType::Popup,
+        _ => WindowType::Normal,
+    };
+
+    let marks = node["marks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|mark| mark.as_str().map(str::to_string))
+        .collect();
+
+    Window {
+        id: node["id"].as_i64().unwrap_or_default() as WindowId,
+        title: node["name"].as_str().unwrap_or_default().to_string(),
+        process_id: node["pid"]
+            .as_u64()
+            .map(|pid| pid as u32)
+            .or_else(|| x11_pid(node["window"].as_u64()? as u32))
+            .unwrap_or_default(),
+        app_id,
+        window_type,
+        workspace_id: location.workspace_id.clone(),
+        workspace_name: location.workspace.clone(),
+        output: location.output.clone(),
+        floating: Some(location.floating),
+        marks,
+        ..Default::default()
+    }
+}
+
+/// Looks up an output in a `GET_OUTPUTS` reply.
+fn output_rect(outputs: &Value, name: &str) -> Option<MonitorGeometry> {
+    outputs
+        .as_array()?
+        .iter()
+        .find(|output| output["name"].as_str() == Some(name))
+        .and_then(|output| parse_rect(&output["rect"]))
+}
+
+/// Commands to chain onto a move so a floating window takes up the same part
+/// of `target` it took up on its old output. Tiled windows get laid out anew.
+fn keep_relative(
+    node: &Value,
+    location: &Location,
+    outputs: &Value,
+    target: &MonitorGeometry,
+) -> String {
+    let moved = location
+        .output
+        .as_deref()
+        .filter(|_| location.floating)
+        .and_then(|output| output_rect(outputs, output))
+        .zip(parse_rect(&node["rect"]))
+        .map(|(source, rect)| source.map_rect(rect, target));
+
+    match moved {
+        Some(rect) => format!(
+            ", move absolute position {} {}, resize set {} px {} px",
+            rect.x, rect.y, rect.width, rect.height
+        ),
+        None => String::new(),
+    }
+}
+
+/// Quotes a workspace or output name for a command.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn parse_rect(rect: &Value) -> Option<MonitorGeometry> {
+    Some(MonitorGeometry {
+        x: rect["x"].as_i64()? as i32,
+        y: rect["y"].as_i64()? as i32,
+        width: rect["width"].as_u64()? as u32,
+        height: rect["height"].as_u64()? as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    static NEXT_SOCKET: AtomicU32 = AtomicU32::new(0);
+
+    /// A sway tree: one output with a tiled and a floating window on
+    /// workspace 1, and an XWayland dialog on workspace 2.
+    fn sway_tree() -> Value {
+        json!({
+            "id": 1, "type": "root", "name": "root",
+            "nodes": [{
+                "id": 2, "type": "output", "name": "DP-1",
+                "nodes": [
+                    {
+                        "id": 3, "type": "workspace", "name": "1",
+                        "nodes": [{
+                            "id": 10, "type": "con", "name": "notes.txt - Editor",
+                            "pid": 1010, "app_id": "org.example.Editor",
+                            "marks": ["e"], "visible": true,
+                            "rect": {"x": 1920, "y": 0, "width": 960, "height": 1080},
+                            "nodes": [], "floating_nodes": []
+                        }],
+                        "floating_nodes": [{
+                            "id": 11, "type": "floating_con", "name": "Calculator",
+                            "pid": 1011, "app_id": "calc", "marks": [], "visible": true,
+                            "rect": {"x": 2000, "y": 100, "width": 400, "height": 300},
+                            "nodes": [], "floating_nodes": []
+                        }]
+                    },
+                    {
+                        "id": 4, "type": "workspace", "name": "2: web",
+                        "nodes": [{
+                            "id": 12, "type": "con", "name": "Save As", "pid": 1012,
+                            "app_id": null, "window": 4194305, "window_type": "dialog",
+                            "window_properties": {"class": "firefox"},
+                            "marks": [], "visible": false,
+                            "rect": {"x": 1920, "y": 0, "width": 1920, "height": 1080},
+                            "nodes": [], "floating_nodes": []
+                        }],
+                        "floating_nodes": []
+                    }
+                ],
+                "floating_nodes": []
+            }],
+            "floating_nodes": []
+        })
+    }
+
+    /// An i3 tree, its views come with X11 window ids but no pids.
+    fn i3_tree() -> Value {
+        json!({
+            "id": 1, "type": "root", "name": "root",
+            "nodes": [{
+                "id": 2, "type": "output", "name": "HDMI-1",
+                "nodes": [{
+                    "id": 3, "type": "workspace", "name": "1",
+                    "nodes": [
+                        {
+                            "id": 10, "type": "con", "name": "~/binocular", "window": 2097153,
+                            "window_properties": {"class": "Alacritty"},
+                            "nodes": [], "floating_nodes": []
+                        },
+                        {
+                            "id": 11, "type": "con", "name": "Unknown", "window": 2097154,
+                            "nodes": [], "floating_nodes": []
+                        }
+                    ],
+                    "floating_nodes": []
+                }],
+                "floating_nodes": []
+            }],
+            "floating_nodes": []
+        })
+    }
+
+    fn outputs() -> Value {
+        json!([
+            {"name": "eDP-1", "active": true, "primary": false, "scale": 2.0,
+             "rect": {"x": 0, "y": 0, "width": 1920, "height": 1080}},
+            {"name": "DP-1", "active": true, "primary": true, "scale": 1.0,
+             "rect": {"x": 1920, "y": 0, "width": 1920, "height": 1080}},
+            {"name": "DP-2", "active": false, "rect": {"x": 0, "y": 0, "width": 0, "height": 0}}
+        ])
+    }
+
+    fn workspaces() -> Value {
+        json!([
+            {"id": 3, "name": "1", "output": "DP-1", "visible": true, "focused": true,
+             "rect": {"x": 1920, "y": 30, "width": 1920, "height": 1050}},
+            {"id": 4, "name": "2: web", "output": "DP-1", "visible": false, "focused": false,
+             "rect": {"x": 1920, "y": 30, "width": 1920, "height": 1050}}
+        ])
+    }
+
+    fn no_x11(_window: u32) -> Option<u32> {
+        None
+    }
+
+    /// Answers requests with canned replies and keeps the commands it was
+    /// sent, like a sway that never changes.
+    struct MockSway {
+        path: PathBuf,
+        commands: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockSway {
+        fn start(replies: HashMap<u32, Value>) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "binocular-i3-ipc-{}-{}.sock",
+                std::process::id(),
+                NEXT_SOCKET.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = std::fs::remove_file(&path);
+            let listener = UnixListener::bind(&path).unwrap();
+            let commands = Arc::new(Mutex::new(Vec::new()));
+            let replies = Arc::new(replies);
+
+            let received = commands.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut client = I3Ipc {
+                        stream: stream.unwrap(),
+                    };
+                    let replies = replies.clone();
+                    let received = received.clone();
+
+                    std::thread::spawn(move || {
+                        while let Ok((message_type, payload)) = client.receive() {
+                            let payload = String::from_utf8(payload).unwrap();
+                            let reply = match message_type {
+                                RUN_COMMAND => {
+                                    let success = !payload.contains("[con_id=99]");
+                                    received.lock().unwrap().push(payload);
+                                    match success {
+                                        true => json!([{"success": true}]),
+                                        false => {
+                                            json!([{"success": false, "error": "No matching node."}])
+                                        }
+                                    }
+                                }
+                                SUBSCRIBE => json!({"success": true}),
+                                _ => replies[&message_type].clone(),
+                            };
+                            client.send(message_type, &reply.to_string()).unwrap();
+
+                            if message_type == SUBSCRIBE {
+                                for (change, id) in
+                                    [("new", 20), ("title", 20), ("focus", 20), ("close", 20)]
+                                {
+                                    let event = json!({"change": change, "container": {"id": id}});
+                                    client.send(EVENT_WINDOW, &event.to_string()).unwrap();
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+
+            Self { path, commands }
+        }
+
+        fn backend(&self, flavor: Flavor) -> SwayBackend {
+            SwayBackend::connect(self.path.to_string_lossy().into_owned(), flavor).unwrap()
+        }
+
+        fn commands(&self) -> Vec<String> {
+            self.commands.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for MockSway {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn sway() -> MockSway {
+        MockSway::start(HashMap::from([
+            (GET_TREE, sway_tree()),
+            (GET_OUTPUTS, outputs()),
+            (GET_WORKSPACES, workspaces()),
+        ]))
+    }
+
+    #[test]
+    fn collects_views_with_where_they_sit() {
+        let mut windows = Vec::new();
+        collect_windows(&sway_tree(), &Location::default(), &no_x11, &mut windows);
+
+        let editor = &windows[0];
+        assert_eq!(editor.id, 10);
+        assert_eq!(editor.title, "notes.txt - Editor");
+        assert_eq!(editor.process_id, 1010);
+        assert_eq!(editor.app_id.as_deref(), Some("org.example.Editor"));
+        assert_eq!(editor.output.as_deref(), Some("DP-1"));
+        assert_eq!(editor.workspace_id.as_deref(), Some("3"));
+        assert_eq!(editor.workspace_name.as_deref(), Some("1"));
+        assert_eq!(editor.floating, Some(false));
+        assert_eq!(editor.marks, ["e"]);
+
+        let calculator = &windows[1];
+        assert_eq!(calculator.id, 11);
+        assert_eq!(calculator.floating, Some(true));
+
+        // XWayland windows are known by their class
+        let dialog = &windows[2];
+        assert_eq!(dialog.app_id.as_deref(), Some("firefox"));
+        assert_eq!(dialog.window_type, WindowType::Dialog);
+        assert_eq!(dialog.workspace_name.as_deref(), Some("2: web"));
+
+        assert_eq!(windows.len(), 3);
+    }
+
+    #[test]
+    fn i3_views_get_their_pid_from_x11() {
+        let x11_pids = |window| (window == 2097153).then_some(4242);
+        let mut windows = Vec::new();
+        collect_windows(&i3_tree(), &Location::default(), &x11_pids, &mut windows);
+
+        let pids: Vec<_> = windows
+            .iter()
+            .map(|window| (window.id, window.process_id))
+            .collect();
+        assert_eq!(pids, [(10, 4242), (11, 0)]);
+        assert_eq!(windows[0].app_id.as_deref(), Some("Alacritty"));
+    }
+
+    #[test]
+    fn finds_windows_by_container_id() {
+        let tree = sway_tree();
+
+        let (node, location) = find_window(&tree, 11, &Location::default()).unwrap();
+        assert_eq!(node["name"], "Calculator");
+        assert!(location.floating);
+        assert_eq!(location.output.as_deref(), Some("DP-1"));
+
+        assert!(find_window(&tree, 99, &Location::default()).is_none());
+    }
+
+    #[test]
+    fn lists_windows_and_monitors_over_ipc() {
+        let sway = sway();
+        let backend = sway.backend(Flavor::Sway);
+
+        let windows = backend.get_windows(1011);
+        let ids: Vec<_> = windows.iter().map(|window| window.id).collect();
+        assert_eq!(ids, [10, 12]);
+
+        let monitors = backend.get_monitors();
+        let names: Vec<_> = monitors
+            .iter()
+            .map(|monitor| monitor.name.as_str())
+            .collect();
+        assert_eq!(names, ["eDP-1", "DP-1"]);
+        assert_eq!(monitors[0].scale, 2.0);
+        assert!(monitors[1].primary && monitors[1].active);
+        assert_eq!(monitors[1].work_area.y, 30);
+
+        let workspaces = backend.get_workspaces();
+        assert_eq!(workspaces[1].id, "4");
+        assert_eq!(workspaces[1].name, "2: web");
+    }
+
+    #[test]
+    fn sends_commands_for_a_container() {
+        let sway = sway();
+        let backend = sway.backend(Flavor::Sway);
+
+        backend.focus_window(10).unwrap();
+        backend.close_window(12).unwrap();
+        backend
+            .window_action(10, WindowAction::SetOpacity(0.5))
+            .unwrap();
+        backend.switch_workspace("4").unwrap();
+        backend
+            .move_window(10, &MoveTarget::Workspace("2: web".to_string()))
+            .unwrap();
+        // Floating windows keep their place relative to the output
+        backend
+            .move_window(11, &MoveTarget::Monitor("eDP-1".to_string()))
+            .unwrap();
+
+        assert_eq!(
+            sway.commands(),
+            [
+                "[con_id=10] focus",
+                "[con_id=12] kill",
+                "[con_id=10] opacity 0.5",
+                "workspace \"2: web\"",
+                "[con_id=10] move container to workspace \"2: web\"",
+                "[con_id=11] move container to output \"eDP-1\", \
+                 move absolute position 80 100, resize set 400 px 300 px",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_failed_commands() {
+        let sway = sway();
+        let backend = sway.backend(Flavor::Sway);
+
+        assert_eq!(
+            backend.focus_window(99),
+            Err("No matching node.".to_string())
+        );
+        assert!(backend.window_action(10, WindowAction::Minimize).is_err());
+        assert!(backend.switch_workspace("42").is_err());
+    }
+
+    #[test]
+    fn i3_has_no_previews_or_opacity() {
+        let sway = sway();
+        let backend = sway.backend(Flavor::I3(None));
+
+        let capabilities = backend.capabilities();
+        assert!(!capabilities.preview && !capabilities.opacity);
+        assert!(capabilities.fullscreen && capabilities.summon);
+        assert_eq!(
+            backend.capture_window(10).unwrap_err(),
+            "Window previews aren't supported on i3"
+        );
+
+        let capabilities = sway.backend(Flavor::Sway).capabilities();
+        assert!(capabilities.preview && capabilities.opacity);
+    }
+
+    #[test]
+    fn streams_window_events() {
+        let sway = sway();
+        let backend = sway.backend(Flavor::Sway);
+        let (sender, events) = std::sync::mpsc::channel();
+
+        backend
+            .subscribe(Box::new(move |event| {
+                let _ = sender.send(event);
+            }))
+            .unwrap();
+
+        let events: Vec<_> = events.iter().take(4).collect();
+        assert_eq!(
+            events,
+            [
+                WindowEvent::Created(20),
+                WindowEvent::Changed(20),
+                WindowEvent::Activated(20),
+                WindowEvent::Destroyed(20),
+            ]
+        );
+    }
+}
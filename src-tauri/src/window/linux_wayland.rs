@@ -0,0 +1,979 @@
+use std::sync::{Arc, Mutex};
+
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_output, wl_registry, wl_seat};
+use wayland_client::{event_created_child, Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MonitorGeometry, MoveTarget, WindowAction,
+    WindowBackend, WindowCapabilities, WindowEvent, WindowId, Workspace, MOVE_UNSUPPORTED,
+    WORKSPACES_UNSUPPORTED,
+};
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::utils::{icon, process};
+use crate::window_manager::Window;
+use image::RgbaImage;
+
+/// A toplevel as announced by the compositor.
+///
+/// Title, app id and state updates arrive piecemeal and only apply once the
+/// compositor sends `done`, so the incoming values are staged in `pending`.
+struct Toplevel {
+    id: WindowId,
+    handle: ZwlrForeignToplevelHandleV1,
+    title: String,
+    app_id: String,
+    states: ToplevelStates,
+    pending: PendingState,
+    mapped: bool,
+    /// Outputs the toplevel is visible on, in the order it entered them.
+    outputs: Vec<wl_output::WlOutput>,
+}
+
+#[derive(Default)]
+struct PendingState {
+    title: Option<String>,
+    app_id: Option<String>,
+    states: Option<ToplevelStates>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct ToplevelStates {
+    activated: bool,
+    maximized: bool,
+    minimized: bool,
+    fullscreen: bool,
+}
+
+/// A `wl_output` global. Its properties are sent once on binding and again
+/// whenever they change.
+struct Output {
+    output: wl_output::WlOutput,
+    /// Registry name of the global, to tell which output went away.
+    global: u32,
+    /// Connector name like `DP-1`, only sent from version 4 on.
+    name: Option<String>,
+    make_model: String,
+    x: i32,
+    y: i32,
+    /// Current mode in pixels, before scaling and rotation.
+    mode: (i32, i32),
+    transform: wl_output::Transform,
+    scale: i32,
+}
+
+impl Output {
+    fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.make_model.clone())
+    }
+
+    /// Approximates the logical size from integer scale and rotation. Output
+    /// management protocols would know the fractional scale, wl_output doesn't.
+    fn geometry(&self) -> MonitorGeometry {
+        let (width, height) = match self.transform {
+            wl_output::Transform::_90
+            | wl_output::Transform::_270
+            | wl_output::Transform::Flipped90
+            | wl_output::Transform::Flipped270 => (self.mode.1, self.mode.0),
+            _ => self.mode,
+        };
+        let scale = self.scale.max(1);
+
+        MonitorGeometry {
+            x: self.x,
+            y: self.y,
+            width: (width / scale).max(0) as u32,
+            height: (height / scale).max(0) as u32,
+        }
+    }
+}
+
+/// State owned by the dispatch thread.
+struct WaylandState {
+    toplevels: Arc<Mutex<Vec<Toplevel>>>,
+    outputs: Arc<Mutex<Vec<Output>>>,
+    callback: Arc<Mutex<Option<EventCallback>>>,
+    next_id: WindowId,
+}
+
+impl WaylandState {
+    fn notify(&self, event: WindowEvent) {
+        if let Some(callback) = self.callback.lock().unwrap().as_ref() {
+            callback(event);
+        }
+    }
+}
+
+/// Backend for wlroots-based compositors (sway, river, Hyprland, ...) that
+/// implement `wlr-foreign-toplevel-management`.
+pub struct WaylandBackend {
+    conn: Connection,
+    /// Fullscreen requests came with version 2 of the protocol.
+    manager_version: u32,
+    seat: wl_seat::WlSeat,
+    toplevels: Arc<Mutex<Vec<Toplevel>>>,
+    outputs: Arc<Mutex<Vec<Output>>>,
+    callback: Arc<Mutex<Option<EventCallback>>>,
+    /// The protocol doesn't expose pids, so our own toplevel is told apart
+    /// by app id. Tauri leaves it unset and GTK falls back to the program name.
+    own_app_id: Option<String>,
+}
+
+impl WaylandBackend {
+    pub fn new() -> Result<Self, String> {
+        Self::with_connection(Connection::connect_to_env().map_err(|e| e.to_string())?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        let (globals, mut event_queue) =
+            registry_queue_init::<WaylandState>(&conn).map_err(|e| e.to_string())?;
+        let qh = event_queue.handle();
+
+        let manager: ZwlrForeignToplevelManagerV1 = globals
+            .bind(&qh, 1..=3, ())
+            .map_err(|e| format!("Compositor does not support wlr-foreign-toplevel: {e}"))?;
+        let manager_version = manager.version();
+        let seat: wl_seat::WlSeat = globals.bind(&qh, 1..=1, ()).map_err(|e| e.to_string())?;
+
+        let toplevels = Arc::new(Mutex::new(Vec::new()));
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let callback = Arc::new(Mutex::new(None));
+
+        // Outputs plugged in later are bound as the registry announces them
+        for global in globals.contents().clone_list() {
+            if global.interface == wl_output::WlOutput::interface().name {
+                bind_output(
+                    &mut outputs.lock().unwrap(),
+                    globals.registry(),
+                    &qh,
+                    global.name,
+                    global.version,
+                );
+            }
+        }
+
+        let mut state = WaylandState {
+            toplevels: toplevels.clone(),
+            outputs: outputs.clone(),
+            callback: callback.clone(),
+            next_id: 0,
+        };
+
+        // Receive the initial set of toplevels before anyone asks for them
+        event_queue
+            .roundtrip(&mut state)
+            .map_err(|e| e.to_string())?;
+
+        std::thread::spawn(move || loop {
+            if let Err(e) = event_queue.blocking_dispatch(&mut state) {
+                println!("Lost connection to Wayland compositor: {e}");
+                return;
+            }
+        });
+
+        Ok(Self {
+            conn,
+            manager_version,
+            seat,
+            toplevels,
+            outputs,
+            callback,
+            own_app_id: std::env::current_exe()
+                .ok()
+                .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned())),
+        })
+    }
+
+    fn with_toplevel(&self, id: WindowId, request: impl FnOnce(&Toplevel)) -> Result<(), String> {
+        {
+            let toplevels = self.toplevels.lock().unwrap();
+            let toplevel = toplevels
+                .iter()
+                .find(|toplevel| toplevel.id == id)
+                .ok_or_else(|| format!("No window with id {id}"))?;
+
+            request(toplevel);
+        }
+
+        self.conn.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl WindowBackend for WaylandBackend {
+    fn get_windows(&self, _current_pid: u32) -> Vec<Window> {
+        let outputs = self.outputs.lock().unwrap();
+
+        self.toplevels
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|toplevel| toplevel.mapped)
+            .filter(|toplevel| Some(&toplevel.app_id) != self.own_app_id.as_ref())
+            .map(|toplevel| Window {
+                id: toplevel.id,
+                title: toplevel.title.clone(),
+                // Unknown, the protocol doesn't tell
+                process_id: 0,
+                app_id: Some(toplevel.app_id.clone()).filter(|app_id| !app_id.is_empty()),
+                output: toplevel.outputs.first().and_then(|entered| {
+                    outputs
+                        .iter()
+                        .find(|output| &output.output == entered)
+                        .map(Output::name)
+                }),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn get_active_window(&self) -> Option<WindowId> {
+        self.toplevels
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|toplevel| toplevel.mapped && toplevel.states.activated)
+            .map(|toplevel| toplevel.id)
+    }
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        self.with_toplevel(id, |toplevel| {
+            toplevel.handle.activate(&self.seat);
+        })
+    }
+
+    fn close_window(&self, id: WindowId) -> Result<(), String> {
+        self.with_toplevel(id, |toplevel| toplevel.handle.close())
+    }
+
+    fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String> {
+        match action {
+            WindowAction::Minimize => {
+                self.with_toplevel(id, |toplevel| toplevel.handle.set_minimized())
+            }
+            WindowAction::Maximize => {
+                self.with_toplevel(id, |toplevel| toplevel.handle.set_maximized())
+            }
+            WindowAction::Restore => self.with_toplevel(id, |toplevel| {
+                if toplevel.states.minimized {
+                    toplevel.handle.unset_minimized();
+                }
+                if toplevel.states.maximized {
+                    toplevel.handle.unset_maximized();
+                }
+            }),
+            WindowAction::ToggleFullscreen => self.with_toplevel(id, |toplevel| {
+                if toplevel.states.fullscreen {
+                    toplevel.handle.unset_fullscreen();
+                } else {
+                    // Leaves the output up to the compositor
+                    toplevel.handle.set_fullscreen(None);
+                }
+            }),
+            _ => Err(action.unsupported()),
+        }
+    }
+
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities {
+            minimize: true,
+            maximize: true,
+            restore: true,
+            fullscreen: self.manager_version >= 2,
+            ..Default::default()
+        }
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        Vec::new()
+    }
+
+    fn move_window(&self, _id: WindowId, _target: &MoveTarget) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn summon_window(&self, _id: WindowId) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        Vec::new()
+    }
+
+    fn switch_workspace(&self, _id: &str) -> Result<(), String> {
+        Err(WORKSPACES_UNSUPPORTED.to_string())
+    }
+
+    fn get_window_icon(&self, window: &Window, size: u32) -> Option<Vec<u8>> {
+        icon::from_app_id(window.app_id.as_deref()?, size)
+    }
+
+    fn get_icon_handle(&self, _id: WindowId) -> Option<IconHandle> {
+        None
+    }
+
+    fn capture_window(&self, _id: WindowId) -> Result<RgbaImage, String> {
+        Err(
+            "Window previews need the window position, which wlr-foreign-toplevel doesn't report"
+                .to_string(),
+        )
+    }
+
+    fn get_process_name(&self, process_id: u32) -> Option<String> {
+        process::get_process_name(process_id)
+    }
+
+    fn get_process_path(&self, process_id: u32) -> Option<String> {
+        process::get_process_path(process_id)
+    }
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32> {
+        process::get_parent_process_id(process_id)
+    }
+
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo> {
+        process::get_process_info(process_id)
+    }
+
+    fn get_sandbox(&self, process_id: u32) -> Option<Sandbox> {
+        process::get_sandbox(process_id)
+    }
+
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity> {
+        super::resolve_desktop_entry(window)
+    }
+
+    /// wl_output says nothing about panels or which output is primary. The
+    /// active one is the first output the activated toplevel entered.
+    fn get_monitors(&self) -> Vec<Monitor> {
+        let active_output = self
+            .toplevels
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|toplevel| toplevel.mapped && toplevel.states.activated)
+            .and_then(|toplevel| toplevel.outputs.first().cloned());
+
+        self.outputs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|output| Monitor {
+                name: output.name(),
+                geometry: output.geometry(),
+                work_area: output.geometry(),
+                scale: output.scale.max(1).into(),
+                primary: false,
+                active: active_output.as_ref() == Some(&output.output),
+            })
+            .collect()
+    }
+
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        *self.callback.lock().unwrap() = Some(callback);
+        Ok(())
+    }
+}
+
+/// Binds an output global, names only come with version 4.
+fn bind_output(
+    outputs: &mut Vec<Output>,
+    registry: &wl_registry::WlRegistry,
+    qh: &QueueHandle<WaylandState>,
+    global: u32,
+    version: u32,
+) {
+    outputs.push(Output {
+        output: registry.bind(global, version.min(4), qh, ()),
+        global,
+        name: None,
+        make_model: String::new(),
+        x: 0,
+        y: 0,
+        mode: (0, 0),
+        transform: wl_output::Transform::Normal,
+        scale: 1,
+    });
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == wl_output::WlOutput::interface().name => {
+                bind_output(&mut state.outputs.lock().unwrap(), proxy, qh, name, version);
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                let mut outputs = state.outputs.lock().unwrap();
+                if let Some(index) = outputs.iter().position(|output| output.global == name) {
+                    let output = outputs.remove(index);
+                    if output.output.version() >= 3 {
+                        output.output.release();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let mut outputs = state.outputs.lock().unwrap();
+        let Some(output) = outputs.iter_mut().find(|output| &output.output == proxy) else {
+            return;
+        };
+
+        match event {
+            wl_output::Event::Geometry {
+                x,
+                y,
+                make,
+                model,
+                transform,
+                ..
+            } => {
+                output.x = x;
+                output.y = y;
+                output.make_model = format!("{make} {model}");
+                if let WEnum::Value(transform) = transform {
+                    output.transform = transform;
+                }
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                output.mode = (width, height);
+            }
+            wl_output::Event::Scale { factor } => output.scale = factor,
+            wl_output::Event::Name { name } => output.name = Some(name),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_seat::WlSeat,
+        _event: wl_seat::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state.next_id += 1;
+
+                state.toplevels.lock().unwrap().push(Toplevel {
+                    id: state.next_id,
+                    handle: toplevel,
+                    title: String::new(),
+                    app_id: String::new(),
+                    states: ToplevelStates::default(),
+                    pending: PendingState::default(),
+                    mapped: false,
+                    outputs: Vec::new(),
+                });
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                println!("Compositor stopped sending toplevel updates");
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandState, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // The callback refreshes the window list, so it must run without the lock held
+        let notifications = {
+            let mut toplevels = state.toplevels.lock().unwrap();
+            let Some(index) = toplevels
+                .iter()
+                .position(|toplevel| &toplevel.handle == proxy)
+            else {
+                return;
+            };
+            let toplevel = &mut toplevels[index];
+
+            match event {
+                zwlr_foreign_toplevel_handle_v1::Event::Title { title } => {
+                    toplevel.pending.title = Some(title);
+                    Vec::new()
+                }
+                zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                    toplevel.pending.app_id = Some(app_id);
+                    Vec::new()
+                }
+                zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                    // An array of native endian u32 state values
+                    let has = |wanted: zwlr_foreign_toplevel_handle_v1::State| {
+                        state
+                            .chunks_exact(4)
+                            .any(|value| value == (wanted as u32).to_ne_bytes())
+                    };
+                    toplevel.pending.states = Some(ToplevelStates {
+                        activated: has(zwlr_foreign_toplevel_handle_v1::State::Activated),
+                        maximized: has(zwlr_foreign_toplevel_handle_v1::State::Maximized),
+                        minimized: has(zwlr_foreign_toplevel_handle_v1::State::Minimized),
+                        fullscreen: has(zwlr_foreign_toplevel_handle_v1::State::Fullscreen),
+                    });
+                    Vec::new()
+                }
+                zwlr_foreign_toplevel_handle_v1::Event::OutputEnter { output } => {
+                    toplevel.outputs.push(output);
+                    Vec::new()
+                }
+                zwlr_foreign_toplevel_handle_v1::Event::OutputLeave { output } => {
+                    toplevel.outputs.retain(|entered| entered != &output);
+                    Vec::new()
+                }
+                zwlr_foreign_toplevel_handle_v1::Event::Done => {
+                    let pending = std::mem::take(&mut toplevel.pending);
+
+                    if let Some(title) = pending.title {
+                        toplevel.title = title;
+                    }
+                    if let Some(app_id) = pending.app_id {
+                        toplevel.app_id = app_id;
+                    }
+
+                    let mut notifications = if toplevel.mapped {
+                        vec![WindowEvent::Changed(toplevel.id)]
+                    } else {
+                        toplevel.mapped = true;
+                        vec![WindowEvent::Created(toplevel.id)]
+                    };
+
+                    if let Some(states) = pending.states {
+                        if states.activated && !toplevel.states.activated {
+                            notifications.push(WindowEvent::Activated(toplevel.id));
+                        }
+                        toplevel.states = states;
+                    }
+
+                    notifications
+                }
+                zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                    let toplevel = toplevels.remove(index);
+                    toplevel.handle.destroy();
+
+                    vec![WindowEvent::Destroyed(toplevel.id)]
+                }
+                _ => Vec::new(),
+            }
+        };
+
+        for event in notifications {
+            state.notify(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::os::fd::AsFd;
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use std::process::{Child, Command};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+    use wayland_client::delegate_noop;
+    use wayland_client::protocol::{wl_buffer, wl_compositor, wl_shm, wl_shm_pool, wl_surface};
+    use wayland_client::EventQueue;
+    use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+
+    static NEXT_RUNTIME_DIR: AtomicU32 = AtomicU32::new(0);
+
+    /// A headless sway in a runtime directory of its own, so it doesn't get in
+    /// the way of the desktop the tests run on.
+    struct Sway {
+        server: Child,
+        runtime_dir: PathBuf,
+    }
+
+    impl Sway {
+        fn start() -> Self {
+            let runtime_dir = std::env::temp_dir().join(format!(
+                "binocular-sway-{}-{}",
+                std::process::id(),
+                NEXT_RUNTIME_DIR.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&runtime_dir).unwrap();
+            let config = runtime_dir.join("config");
+            std::fs::write(&config, "").unwrap();
+
+            let server = Command::new("sway")
+                .arg("--config")
+                .arg(&config)
+                .env("XDG_RUNTIME_DIR", &runtime_dir)
+                .env("WLR_BACKENDS", "headless")
+                .env("WLR_HEADLESS_OUTPUTS", "1")
+                .env("WLR_LIBINPUT_NO_DEVICES", "1")
+                .env("WLR_RENDERER", "pixman")
+                .env_remove("WAYLAND_DISPLAY")
+                .env_remove("DISPLAY")
+                .env_remove("SWAYSOCK")
+                .spawn()
+                .expect("sway should be installed");
+
+            let sway = Self {
+                server,
+                runtime_dir,
+            };
+            sway.wait_until("sway to come up", || {
+                UnixStream::connect(sway.runtime_dir.join("wayland-1")).is_ok()
+            });
+            sway
+        }
+
+        fn connect(&self) -> Connection {
+            let stream = UnixStream::connect(self.runtime_dir.join("wayland-1")).unwrap();
+            Connection::from_socket(stream).unwrap()
+        }
+
+        fn backend(&self) -> WaylandBackend {
+            WaylandBackend::with_connection(self.connect()).unwrap()
+        }
+
+        /// Maps an xdg toplevel with the given title and app id.
+        fn client(&self, title: &str, app_id: &str) -> Client {
+            Client::map(self, title, app_id)
+        }
+
+        fn wait_until(&self, what: &str, mut done: impl FnMut() -> bool) {
+            let started = Instant::now();
+
+            while !done() {
+                assert!(
+                    started.elapsed() < Duration::from_secs(5),
+                    "Timed out waiting for {what}"
+                );
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        }
+    }
+
+    impl Drop for Sway {
+        fn drop(&mut self) {
+            let _ = self.server.kill();
+            let _ = self.server.wait();
+            let _ = std::fs::remove_dir_all(&self.runtime_dir);
+        }
+    }
+
+    #[derive(Default)]
+    struct ClientState {
+        configured: bool,
+        close_requested: bool,
+        activated: bool,
+    }
+
+    /// A toplevel with a blank buffer, dispatched on the test thread whenever
+    /// the test wants to know what the compositor asked of it.
+    struct Client {
+        queue: EventQueue<ClientState>,
+        state: ClientState,
+        surface: wl_surface::WlSurface,
+        toplevel: xdg_toplevel::XdgToplevel,
+        _buffer: wl_buffer::WlBuffer,
+    }
+
+    impl Client {
+        const SIZE: i32 = 64;
+
+        fn map(sway: &Sway, title: &str, app_id: &str) -> Self {
+            let conn = sway.connect();
+            let (globals, mut queue) = registry_queue_init::<ClientState>(&conn).unwrap();
+            let qh = queue.handle();
+            let compositor: wl_compositor::WlCompositor = globals.bind(&qh, 1..=4, ()).unwrap();
+            let shm: wl_shm::WlShm = globals.bind(&qh, 1..=1, ()).unwrap();
+            let wm_base: xdg_wm_base::XdgWmBase = globals.bind(&qh, 1..=1, ()).unwrap();
+
+            let surface = compositor.create_surface(&qh, ());
+            let toplevel = wm_base
+                .get_xdg_surface(&surface, &qh, ())
+                .get_toplevel(&qh, ());
+            toplevel.set_title(title.to_string());
+            toplevel.set_app_id(app_id.to_string());
+            surface.commit();
+
+            let mut state = ClientState::default();
+            while !state.configured {
+                queue.blocking_dispatch(&mut state).unwrap();
+            }
+
+            // Only a surface with a buffer attached is mapped
+            let stride = Self::SIZE * 4;
+            let file = tempfile_in(&sway.runtime_dir);
+            file.set_len((stride * Self::SIZE) as u64).unwrap();
+            let pool = shm.create_pool(file.as_fd(), stride * Self::SIZE, &qh, ());
+            let buffer = pool.create_buffer(
+                0,
+                Self::SIZE,
+                Self::SIZE,
+                stride,
+                wl_shm::Format::Argb8888,
+                &qh,
+                (),
+            );
+            pool.destroy();
+            surface.attach(Some(&buffer), 0, 0);
+            surface.commit();
+            queue.roundtrip(&mut state).unwrap();
+
+            Self {
+                queue,
+                state,
+                surface,
+                toplevel,
+                _buffer: buffer,
+            }
+        }
+
+        fn set_title(&mut self, title: &str) {
+            self.toplevel.set_title(title.to_string());
+            self.surface.commit();
+            self.queue.roundtrip(&mut self.state).unwrap();
+        }
+
+        /// What the compositor asked of the toplevel so far.
+        fn state(&mut self) -> &ClientState {
+            self.queue.roundtrip(&mut self.state).unwrap();
+            &self.state
+        }
+    }
+
+    /// An unlinked file to back the shared memory pool.
+    fn tempfile_in(dir: &std::path::Path) -> File {
+        let path = dir.join(format!(
+            "buffer-{}",
+            NEXT_RUNTIME_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+        file
+    }
+
+    impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for ClientState {
+        fn event(
+            _state: &mut Self,
+            _proxy: &wl_registry::WlRegistry,
+            _event: wl_registry::Event,
+            _data: &GlobalListContents,
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<xdg_wm_base::XdgWmBase, ()> for ClientState {
+        fn event(
+            _state: &mut Self,
+            proxy: &xdg_wm_base::XdgWmBase,
+            event: xdg_wm_base::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+            if let xdg_wm_base::Event::Ping { serial } = event {
+                proxy.pong(serial);
+            }
+        }
+    }
+
+    impl Dispatch<xdg_surface::XdgSurface, ()> for ClientState {
+        fn event(
+            state: &mut Self,
+            proxy: &xdg_surface::XdgSurface,
+            event: xdg_surface::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+            if let xdg_surface::Event::Configure { serial } = event {
+                proxy.ack_configure(serial);
+                state.configured = true;
+            }
+        }
+    }
+
+    impl Dispatch<xdg_toplevel::XdgToplevel, ()> for ClientState {
+        fn event(
+            state: &mut Self,
+            _proxy: &xdg_toplevel::XdgToplevel,
+            event: xdg_toplevel::Event,
+            _data: &(),
+            _conn: &Connection,
+            _qh: &QueueHandle<Self>,
+        ) {
+            match event {
+                xdg_toplevel::Event::Configure { states, .. } => {
+                    state.activated = states.chunks_exact(4).any(|value| {
+                        value == (xdg_toplevel::State::Activated as u32).to_ne_bytes()
+                    });
+                }
+                xdg_toplevel::Event::Close => state.close_requested = true,
+                _ => {}
+            }
+        }
+    }
+
+    delegate_noop!(ClientState: ignore wl_compositor::WlCompositor);
+    delegate_noop!(ClientState: ignore wl_surface::WlSurface);
+    delegate_noop!(ClientState: ignore wl_shm::WlShm);
+    delegate_noop!(ClientState: ignore wl_shm_pool::WlShmPool);
+    delegate_noop!(ClientState: ignore wl_buffer::WlBuffer);
+
+    fn titles(backend: &WaylandBackend) -> Vec<String> {
+        backend
+            .get_windows(0)
+            .into_iter()
+            .map(|window| window.title)
+            .collect()
+    }
+
+    #[test]
+    #[ignore = "needs sway"]
+    fn lists_toplevels_newest_first_without_our_own() {
+        let sway = Sway::start();
+        let _editor = sway.client("Editor", "org.example.Editor");
+        let _terminal = sway.client("Terminal", "foot");
+        let own_app_id = std::env::current_exe().unwrap();
+        let _own = sway.client(
+            "binocular",
+            &own_app_id.file_name().unwrap().to_string_lossy(),
+        );
+        let backend = sway.backend();
+
+        let windows = backend.get_windows(0);
+        let listed: Vec<_> = windows
+            .iter()
+            .map(|window| (window.title.as_str(), window.app_id.as_deref()))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("Terminal", Some("foot")),
+                ("Editor", Some("org.example.Editor"))
+            ]
+        );
+        assert!(windows.iter().all(|window| window.process_id == 0));
+        assert_eq!(windows[0].output.as_deref(), Some("HEADLESS-1"));
+        assert_eq!(backend.get_monitors().len(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs sway"]
+    fn focus_and_close_reach_the_client() {
+        let sway = Sway::start();
+        let mut editor = sway.client("Editor", "org.example.Editor");
+        let mut terminal = sway.client("Terminal", "foot");
+        let backend = sway.backend();
+        let id = |title: &str| {
+            backend
+                .get_windows(0)
+                .into_iter()
+                .find(|window| window.title == title)
+                .unwrap()
+                .id
+        };
+
+        backend.focus_window(id("Editor")).unwrap();
+        sway.wait_until("the editor to be activated", || editor.state().activated);
+        assert!(!terminal.state().activated);
+        sway.wait_until("its output to be active", || {
+            backend.get_monitors().iter().any(|monitor| monitor.active)
+        });
+
+        backend.close_window(id("Terminal")).unwrap();
+        sway.wait_until("the terminal to be asked to close", || {
+            terminal.state().close_requested
+        });
+        assert!(backend.close_window(42).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs sway"]
+    fn streams_changes() {
+        let sway = Sway::start();
+        let backend = sway.backend();
+        let (sender, events) = mpsc::channel();
+        backend
+            .subscribe(Box::new(move |event| {
+                let _ = sender.send(event);
+            }))
+            .unwrap();
+
+        let mut editor = sway.client("Editor", "org.example.Editor");
+        let created = events.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(created, WindowEvent::Created(_)));
+
+        editor.set_title("Editor - notes.txt");
+        sway.wait_until("the new title", || {
+            titles(&backend) == ["Editor - notes.txt"]
+        });
+
+        drop(editor);
+        sway.wait_until("the editor to go away", || titles(&backend).is_empty());
+        assert!(events
+            .try_iter()
+            .any(|event| matches!(event, WindowEvent::Destroyed(_))));
+    }
+}
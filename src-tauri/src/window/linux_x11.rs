@@ -0,0 +1,1620 @@
+use image::RgbaImage;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::{
+    self, AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt as _, EventMask,
+    PropMode,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MonitorGeometry, MoveTarget, WindowAction,
+    WindowBackend, WindowCapabilities, WindowEvent, WindowId, WindowType, Workspace,
+};
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::utils::{capture, icon, process};
+use crate::window_manager::Window;
+
+x11rb::atom_manager! {
+    pub Atoms: AtomsCookie {
+        UTF8_STRING,
+        WM_CHANGE_STATE,
+        _NET_SUPPORTED,
+        _NET_CLIENT_LIST,
+        _NET_ACTIVE_WINDOW,
+        _NET_CLOSE_WINDOW,
+        _NET_CURRENT_DESKTOP,
+        _NET_NUMBER_OF_DESKTOPS,
+        _NET_DESKTOP_NAMES,
+        _NET_WORKAREA,
+        _NET_MOVERESIZE_WINDOW,
+        _NET_FRAME_EXTENTS,
+        _NET_WM_DESKTOP,
+        _NET_WM_ICON,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_SKIP_TASKBAR,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_SHADED,
+        _NET_WM_WINDOW_OPACITY,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_NORMAL,
+        _NET_WM_WINDOW_TYPE_DIALOG,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        _NET_WM_WINDOW_TYPE_TOOLBAR,
+        _NET_WM_WINDOW_TYPE_MENU,
+    }
+}
+
+/// How a `_NET_WM_STATE` client message changes the states it names.
+const STATE_REMOVE: u32 = 0;
+const STATE_ADD: u32 = 1;
+const STATE_TOGGLE: u32 = 2;
+
+/// The ICCCM IconicState, asking for it through WM_CHANGE_STATE minimizes a window.
+const ICONIC_STATE: u32 = 3;
+
+/// The `_NET_WM_DESKTOP` of windows shown on every desktop.
+const ALL_DESKTOPS: u32 = 0xFFFF_FFFF;
+
+/// `_NET_MOVERESIZE_WINDOW` flags: NorthWest gravity, x, y, width and height
+/// given, sent by a pager.
+const MOVERESIZE_FLAGS: u32 = 1 | 0b1111 << 8 | 2 << 12;
+
+/// How much of `_NET_WM_ICON` to fingerprint, in 32-bit values. Enough for the
+/// first image or two, an icon changing changes those as well.
+const ICON_FINGERPRINT_LENGTH: u32 = 1024;
+
+/// An open connection to the X server together with the root window and
+/// the EWMH atoms we need.
+pub struct X11Session {
+    pub conn: RustConnection,
+    pub root: xproto::Window,
+    pub atoms: Atoms,
+}
+
+pub fn connect() -> Result<X11Session, String> {
+    connect_to(None)
+}
+
+/// Connects to `display`, or to `$DISPLAY` when it's `None`.
+fn connect_to(display: Option<&str>) -> Result<X11Session, String> {
+    let (conn, screen_num) = x11rb::connect(display).map_err(|e| e.to_string())?;
+    let root = conn.setup().roots[screen_num].root;
+    let atoms = Atoms::new(&conn)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    Ok(X11Session { conn, root, atoms })
+}
+
+pub struct X11Backend {
+    session: X11Session,
+    /// The display `session` is on, for the event listener's own connection.
+    display: Option<String>,
+}
+
+impl X11Backend {
+    pub fn new() -> Result<Self, String> {
+        Self::with_display(None)
+    }
+
+    fn with_display(display: Option<&str>) -> Result<Self, String> {
+        Ok(Self {
+            session: connect_to(display)?,
+            display: display.map(str::to_string),
+        })
+    }
+}
+
+impl WindowBackend for X11Backend {
+    fn get_windows(&self, current_pid: u32) -> Vec<Window> {
+        let session = &self.session;
+
+        let clients = match get_client_list(session) {
+            Some(clients) => clients,
+            None => {
+                println!("Window manager does not expose _NET_CLIENT_LIST");
+                return Vec::new();
+            }
+        };
+
+        let desktops = get_desktop_names(session);
+        let monitors = randr_monitors(session);
+
+        // _NET_CLIENT_LIST is in mapping order, put the newest windows first like EnumWindows does
+        clients
+            .into_iter()
+            .rev()
+            .filter_map(|window| read_window(session, window, current_pid, &desktops, &monitors))
+            .collect()
+    }
+
+    fn get_active_window(&self) -> Option<WindowId> {
+        let session = &self.session;
+
+        get_property32(
+            &session.conn,
+            session.root,
+            session.atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW.into(),
+        )?
+        .first()
+        .copied()
+        .filter(|&window| window != 0)
+        .map(|window| window as WindowId)
+    }
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        // source indication 2 = pager, so window managers don't apply focus stealing prevention
+        let data = [2, x11rb::CURRENT_TIME, 0, 0, 0];
+        send_root_message(
+            &self.session,
+            id as u32,
+            self.session.atoms._NET_ACTIVE_WINDOW,
+            data,
+        )
+    }
+
+    fn close_window(&self, id: WindowId) -> Result<(), String> {
+        let data = [x11rb::CURRENT_TIME, 2, 0, 0, 0];
+        send_root_message(
+            &self.session,
+            id as u32,
+            self.session.atoms._NET_CLOSE_WINDOW,
+            data,
+        )
+    }
+
+    fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String> {
+        let session = &self.session;
+        let atoms = &session.atoms;
+        let window = id as u32;
+
+        // Source indication 2 = pager, like for focusing
+        let set_state = |change: u32, first: xproto::Atom, second: xproto::Atom| {
+            send_root_message(
+                session,
+                window,
+                atoms._NET_WM_STATE,
+                [change, first, second, 2, 0],
+            )
+        };
+
+        match action {
+            WindowAction::Minimize => send_root_message(
+                session,
+                window,
+                atoms.WM_CHANGE_STATE,
+                [ICONIC_STATE, 0, 0, 0, 0],
+            ),
+            WindowAction::Maximize => set_state(
+                STATE_ADD,
+                atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+            ),
+            WindowAction::Restore => {
+                set_state(
+                    STATE_REMOVE,
+                    atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                    atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+                )?;
+
+                // Minimized windows come back by being activated
+                let states = get_property32(
+                    &session.conn,
+                    window,
+                    atoms._NET_WM_STATE,
+                    AtomEnum::ATOM.into(),
+                )
+                .unwrap_or_default();
+
+                if states.contains(&atoms._NET_WM_STATE_HIDDEN) {
+                    self.focus_window(id)?;
+                }
+
+                Ok(())
+            }
+            WindowAction::ToggleFullscreen => {
+                set_state(STATE_TOGGLE, atoms._NET_WM_STATE_FULLSCREEN, 0)
+            }
+            WindowAction::ToggleAlwaysOnTop => {
+                set_state(STATE_TOGGLE, atoms._NET_WM_STATE_ABOVE, 0)
+            }
+            WindowAction::ToggleShade => set_state(STATE_TOGGLE, atoms._NET_WM_STATE_SHADED, 0),
+            WindowAction::TogglePin => {
+                // Unpinning puts the window on the desktop we're looking at
+                let target =
+                    if get_cardinal(session, window, atoms._NET_WM_DESKTOP) == Some(ALL_DESKTOPS) {
+                        get_cardinal(session, session.root, atoms._NET_CURRENT_DESKTOP).unwrap_or(0)
+                    } else {
+                        ALL_DESKTOPS
+                    };
+
+                send_root_message(session, window, atoms._NET_WM_DESKTOP, [target, 2, 0, 0, 0])
+            }
+            WindowAction::SetOpacity(opacity) => {
+                // Compositors scale 0 to u32::MAX, opaque windows go without the property
+                if opacity >= 1.0 {
+                    session
+                        .conn
+                        .delete_property(window, atoms._NET_WM_WINDOW_OPACITY)
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    session
+                        .conn
+                        .change_property32(
+                            PropMode::REPLACE,
+                            window,
+                            atoms._NET_WM_WINDOW_OPACITY,
+                            AtomEnum::CARDINAL,
+                            &[(opacity * u32::MAX as f64) as u32],
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+
+                session.conn.flush().map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// Asks the window manager what it supports through `_NET_SUPPORTED`.
+    fn capabilities(&self) -> WindowCapabilities {
+        let session = &self.session;
+        let atoms = &session.atoms;
+
+        let supported = get_property32(
+            &session.conn,
+            session.root,
+            atoms._NET_SUPPORTED,
+            AtomEnum::ATOM.into(),
+        )
+        .unwrap_or_default();
+        let supports = |atom| supported.contains(&atom);
+
+        WindowCapabilities {
+            // Part of ICCCM, which every window manager implements
+            minimize: true,
+            maximize: supports(atoms._NET_WM_STATE_MAXIMIZED_VERT)
+                && supports(atoms._NET_WM_STATE_MAXIMIZED_HORZ),
+            restore: true,
+            fullscreen: supports(atoms._NET_WM_STATE_FULLSCREEN),
+            always_on_top: supports(atoms._NET_WM_STATE_ABOVE),
+            pin: supports(atoms._NET_WM_DESKTOP),
+            shade: supports(atoms._NET_WM_STATE_SHADED),
+            opacity: has_compositor(session),
+            summon: supports(atoms._NET_WM_DESKTOP) && supports(atoms._NET_MOVERESIZE_WINDOW),
+            preview: true,
+        }
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        let monitors = randr_monitors(&self.session)
+            .into_iter()
+            .map(|monitor| MoveTarget::Monitor(monitor.name));
+        let desktops = get_desktop_names(&self.session)
+            .into_iter()
+            .map(MoveTarget::Workspace);
+
+        monitors.chain(desktops).collect()
+    }
+
+    fn move_window(&self, id: WindowId, target: &MoveTarget) -> Result<(), String> {
+        let session = &self.session;
+        let window = id as u32;
+
+        match target {
+            MoveTarget::Monitor(name) => {
+                let monitor = randr_monitors(session)
+                    .into_iter()
+                    .find(|monitor| &monitor.name == name)
+                    .map(|monitor| monitor.geometry)
+                    .ok_or_else(|| format!("No monitor named {name}"))?;
+
+                move_to_monitor(session, window, &monitor)
+            }
+            MoveTarget::Workspace(name) => {
+                let desktop = get_desktop_names(session)
+                    .iter()
+                    .position(|desktop| desktop == name)
+                    .ok_or_else(|| format!("No desktop named {name}"))?;
+
+                send_root_message(
+                    session,
+                    window,
+                    session.atoms._NET_WM_DESKTOP,
+                    [desktop as u32, 2, 0, 0, 0],
+                )
+            }
+        }
+    }
+
+    fn summon_window(&self, id: WindowId) -> Result<(), String> {
+        let session = &self.session;
+        let atoms = &session.atoms;
+        let window = id as u32;
+
+        // Pinned windows are on the current desktop already
+        let current = get_cardinal(session, session.root, atoms._NET_CURRENT_DESKTOP);
+        let desktop = get_cardinal(session, window, atoms._NET_WM_DESKTOP);
+        if let Some(current) = current.filter(|_| desktop != Some(ALL_DESKTOPS)) {
+            if desktop != Some(current) {
+                send_root_message(
+                    session,
+                    window,
+                    atoms._NET_WM_DESKTOP,
+                    [current, 2, 0, 0, 0],
+                )?;
+            }
+        }
+
+        let pointer = session
+            .conn
+            .query_pointer(session.root)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+
+        let monitor = randr_monitors(session)
+            .into_iter()
+            .map(|monitor| monitor.geometry)
+            .find(|monitor| monitor.contains(pointer.root_x.into(), pointer.root_y.into()));
+        if let Some(monitor) = monitor {
+            move_to_monitor(session, window, &monitor)?;
+        }
+
+        self.focus_window(id)
+    }
+
+    fn get_window_icon(&self, window: &Window, size: u32) -> Option<Vec<u8>> {
+        let session = &self.session;
+
+        get_property32(
+            &session.conn,
+            window.id as u32,
+            session.atoms._NET_WM_ICON,
+            AtomEnum::CARDINAL.into(),
+        )
+        .and_then(|data| icon::from_net_wm_icon(&data, size))
+        .or_else(|| icon::from_app_id(window.app_id.as_deref()?, size))
+    }
+
+    /// Fingerprints the start and length of `_NET_WM_ICON`, so every refresh
+    /// doesn't transfer every size of every icon.
+    fn get_icon_handle(&self, id: WindowId) -> Option<IconHandle> {
+        let session = &self.session;
+        let reply = session
+            .conn
+            .get_property(
+                false,
+                id as u32,
+                session.atoms._NET_WM_ICON,
+                AtomEnum::CARDINAL,
+                0,
+                ICON_FINGERPRINT_LENGTH,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+
+        let mut hasher = DefaultHasher::new();
+        reply.bytes_after.hash(&mut hasher);
+        reply.value.hash(&mut hasher);
+
+        Some(IconHandle {
+            own: false,
+            fingerprint: hasher.finish(),
+        })
+    }
+
+    fn capture_window(&self, id: WindowId) -> Result<RgbaImage, String> {
+        capture::linux_x11::capture_window(&self.session.conn, self.session.root, id as u32)
+    }
+
+    fn get_process_name(&self, process_id: u32) -> Option<String> {
+        process::get_process_name(process_id)
+    }
+
+    fn get_process_path(&self, process_id: u32) -> Option<String> {
+        process::get_process_path(process_id)
+    }
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32> {
+        process::get_parent_process_id(process_id)
+    }
+
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo> {
+        process::get_process_info(process_id)
+    }
+
+    fn get_sandbox(&self, process_id: u32) -> Option<Sandbox> {
+        process::get_sandbox(process_id)
+    }
+
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity> {
+        super::resolve_desktop_entry(window)
+    }
+
+    /// The active monitor holds the middle of the active window, or the
+    /// pointer when no window is active.
+    fn get_monitors(&self) -> Vec<Monitor> {
+        let session = &self.session;
+        let mut monitors = randr_monitors(session);
+
+        let active_window = get_property32(
+            &session.conn,
+            session.root,
+            session.atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW.into(),
+        )
+        .and_then(|window| window.first().copied())
+        .filter(|window| *window != x11rb::NONE);
+
+        let point = match active_window.and_then(|window| client_rect(session, window).ok()) {
+            Some(rect) => Some(rect.center()),
+            None => session
+                .conn
+                .query_pointer(session.root)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .map(|pointer| (pointer.root_x.into(), pointer.root_y.into())),
+        };
+
+        if let Some((x, y)) = point {
+            if let Some(monitor) = monitors
+                .iter_mut()
+                .find(|monitor| monitor.geometry.contains(x, y))
+            {
+                monitor.active = true;
+            }
+        }
+
+        monitors
+    }
+
+    /// Desktops go by their number, counting from 0 like `_NET_WM_DESKTOP` does.
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        let session = &self.session;
+        let current = get_cardinal(session, session.root, session.atoms._NET_CURRENT_DESKTOP);
+
+        get_desktop_names(session)
+            .into_iter()
+            .enumerate()
+            .map(|(desktop, name)| Workspace {
+                id: desktop.to_string(),
+                name,
+                visible: current == Some(desktop as u32),
+                window_count: 0,
+            })
+            .collect()
+    }
+
+    fn switch_workspace(&self, id: &str) -> Result<(), String> {
+        let desktop: u32 = id.parse().map_err(|_| format!("No desktop {id}"))?;
+
+        send_root_message(
+            &self.session,
+            self.session.root,
+            self.session.atoms._NET_CURRENT_DESKTOP,
+            [desktop, x11rb::CURRENT_TIME, 0, 0, 0],
+        )
+    }
+
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        // The listener blocks on its own connection so requests on ours are never starved
+        let session = connect_to(self.display.as_deref())?;
+
+        // _NET_CLIENT_LIST and _NET_ACTIVE_WINDOW live on the root window, titles
+        // and states on the clients, watch all of them for property changes
+        let attributes = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+        session
+            .conn
+            .change_window_attributes(session.root, &attributes)
+            .map_err(|e| e.to_string())?;
+        let mut clients = get_client_list(&session).unwrap_or_default();
+        for window in &clients {
+            watch_properties(&session, *window);
+        }
+        session.conn.flush().map_err(|e| e.to_string())?;
+
+        let changes = [
+            session.atoms._NET_WM_NAME,
+            AtomEnum::WM_NAME.into(),
+            session.atoms._NET_WM_DESKTOP,
+            session.atoms._NET_WM_STATE,
+            session.atoms._NET_WM_ICON,
+        ];
+
+        std::thread::spawn(move || loop {
+            let event = match session.conn.wait_for_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    println!("Lost connection to X server: {e}");
+                    return;
+                }
+            };
+
+            let Event::PropertyNotify(event) = event else {
+                continue;
+            };
+
+            if event.window != session.root {
+                if changes.contains(&event.atom) {
+                    callback(WindowEvent::Changed(event.window as WindowId));
+                }
+                continue;
+            }
+
+            if event.atom == session.atoms._NET_ACTIVE_WINDOW {
+                let active = get_property32(
+                    &session.conn,
+                    session.root,
+                    session.atoms._NET_ACTIVE_WINDOW,
+                    AtomEnum::WINDOW.into(),
+                )
+                .and_then(|active| active.first().copied())
+                .unwrap_or(0);
+
+                if active != 0 {
+                    callback(WindowEvent::Activated(active as WindowId));
+                }
+                continue;
+            }
+
+            if event.atom != session.atoms._NET_CLIENT_LIST {
+                continue;
+            }
+
+            let new_clients = get_client_list(&session).unwrap_or_default();
+
+            for window in new_clients.iter().filter(|w| !clients.contains(w)) {
+                watch_properties(&session, *window);
+                callback(WindowEvent::Created(*window as WindowId));
+            }
+            let _ = session.conn.flush();
+
+            for window in clients.iter().filter(|w| !new_clients.contains(w)) {
+                callback(WindowEvent::Destroyed(*window as WindowId));
+            }
+
+            clients = new_clients;
+        });
+
+        Ok(())
+    }
+}
+
+fn get_client_list(session: &X11Session) -> Option<Vec<xproto::Window>> {
+    get_property32(
+        &session.conn,
+        session.root,
+        session.atoms._NET_CLIENT_LIST,
+        AtomEnum::WINDOW.into(),
+    )
+}
+
+/// Reads a client window, `desktops` are the desktop names by number.
+fn read_window(
+    session: &X11Session,
+    window: xproto::Window,
+    current_pid: u32,
+    desktops: &[String],
+    monitors: &[Monitor],
+) -> Option<Window> {
+    let atoms = &session.atoms;
+
+    // if the window is the binocular process, skip it
+    let process_id = get_property32(
+        &session.conn,
+        window,
+        atoms._NET_WM_PID,
+        AtomEnum::CARDINAL.into(),
+    )
+    .and_then(|pid| pid.first().copied())
+    .unwrap_or(0);
+
+    if process_id == current_pid {
+        return None;
+    }
+
+    let window_types = get_property32(
+        &session.conn,
+        window,
+        atoms._NET_WM_WINDOW_TYPE,
+        AtomEnum::ATOM.into(),
+    )
+    .unwrap_or_default();
+
+    let states = get_property32(
+        &session.conn,
+        window,
+        atoms._NET_WM_STATE,
+        AtomEnum::ATOM.into(),
+    )
+    .unwrap_or_default();
+
+    let mut window_type = get_window_type(atoms, &window_types);
+
+    // Windows spanning monitors belong to the one holding their middle
+    let output = client_rect(session, window).ok().and_then(|rect| {
+        let (x, y) = rect.center();
+        monitors
+            .iter()
+            .find(|monitor| monitor.geometry.contains(x, y))
+            .map(|monitor| monitor.name.clone())
+    });
+
+    // Pinned windows are on every desktop, which is as good as on none in particular
+    let desktop = get_cardinal(session, window, atoms._NET_WM_DESKTOP)
+        .filter(|desktop| *desktop != ALL_DESKTOPS);
+
+    // Windows that ask to stay out of the taskbar don't want to be switched to either
+    if window_type == WindowType::Normal && states.contains(&atoms._NET_WM_STATE_SKIP_TASKBAR) {
+        window_type = WindowType::Utility;
+    }
+
+    Some(Window {
+        id: window as WindowId,
+        title: get_title(session, window).unwrap_or_default(),
+        process_id,
+        app_id: get_wm_class(session, window),
+        window_type,
+        workspace_id: desktop.map(|desktop| desktop.to_string()),
+        workspace_name: desktop.and_then(|desktop| desktops.get(desktop as usize).cloned()),
+        output,
+        ..Default::default()
+    })
+}
+
+/// Maps _NET_WM_WINDOW_TYPE, a list in order of preference, to a window type.
+///
+/// Windows without a type are treated as normal windows per the EWMH spec.
+fn get_window_type(atoms: &Atoms, window_types: &[xproto::Atom]) -> WindowType {
+    let Some(window_type) = window_types.first() else {
+        return WindowType::Normal;
+    };
+
+    if *window_type == atoms._NET_WM_WINDOW_TYPE_NORMAL {
+        WindowType::Normal
+    } else if *window_type == atoms._NET_WM_WINDOW_TYPE_DIALOG {
+        WindowType::Dialog
+    } else if [
+        atoms._NET_WM_WINDOW_TYPE_UTILITY,
+        atoms._NET_WM_WINDOW_TYPE_TOOLBAR,
+        atoms._NET_WM_WINDOW_TYPE_MENU,
+    ]
+    .contains(window_type)
+    {
+        WindowType::Utility
+    } else {
+        // Docks, splash screens, notifications and the desktop itself
+        WindowType::Popup
+    }
+}
+
+fn get_title(session: &X11Session, window: xproto::Window) -> Option<String> {
+    let atoms = &session.atoms;
+
+    if let Some(title) = get_string(&session.conn, window, atoms._NET_WM_NAME, atoms.UTF8_STRING) {
+        return Some(title);
+    }
+
+    get_string(
+        &session.conn,
+        window,
+        AtomEnum::WM_NAME.into(),
+        AtomEnum::STRING.into(),
+    )
+}
+
+/// Returns the class part of WM_CLASS, which is what desktop entries match against.
+fn get_wm_class(session: &X11Session, window: xproto::Window) -> Option<String> {
+    let wm_class = get_string(
+        &session.conn,
+        window,
+        AtomEnum::WM_CLASS.into(),
+        AtomEnum::STRING.into(),
+    )?;
+
+    // WM_CLASS holds two null-terminated strings, the instance name then the class name
+    wm_class
+        .split('\0')
+        .nth(1)
+        .filter(|class| !class.is_empty())
+        .map(|class| class.to_string())
+}
+
+pub fn get_property32(
+    conn: &RustConnection,
+    window: xproto::Window,
+    property: xproto::Atom,
+    type_: xproto::Atom,
+) -> Option<Vec<u32>> {
+    let reply = conn
+        .get_property(false, window, property, type_, 0, u32::MAX)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    let values = reply.value32()?.collect();
+    Some(values)
+}
+
+pub fn get_string(
+    conn: &RustConnection,
+    window: xproto::Window,
+    property: xproto::Atom,
+    type_: xproto::Atom,
+) -> Option<String> {
+    let reply = conn
+        .get_property(false, window, property, type_, 0, u32::MAX)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    if reply.format != 8 {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&reply.value).into_owned())
+}
+
+fn get_cardinal(
+    session: &X11Session,
+    window: xproto::Window,
+    property: xproto::Atom,
+) -> Option<u32> {
+    get_property32(&session.conn, window, property, AtomEnum::CARDINAL.into())?
+        .first()
+        .copied()
+}
+
+/// Active monitors as RandR reports them, none of them marked active.
+///
+/// X11 has no per-monitor scaling, so they all have a scale of 1.
+fn randr_monitors(session: &X11Session) -> Vec<Monitor> {
+    let Some(reply) = session
+        .conn
+        .randr_get_monitors(session.root, true)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+    else {
+        return Vec::new();
+    };
+
+    // _NET_WORKAREA is one rectangle per desktop spanning every monitor, cut
+    // down to each monitor it's close enough for panels along the screen edges
+    let desktop = get_cardinal(session, session.root, session.atoms._NET_CURRENT_DESKTOP);
+    let work_area = get_property32(
+        &session.conn,
+        session.root,
+        session.atoms._NET_WORKAREA,
+        AtomEnum::CARDINAL.into(),
+    )
+    .and_then(|areas| {
+        let area = areas.chunks_exact(4).nth(desktop.unwrap_or(0) as usize)?;
+        Some(MonitorGeometry {
+            x: area[0] as i32,
+            y: area[1] as i32,
+            width: area[2],
+            height: area[3],
+        })
+    });
+
+    reply
+        .monitors
+        .iter()
+        .filter_map(|monitor| {
+            let name = session
+                .conn
+                .get_atom_name(monitor.name)
+                .ok()?
+                .reply()
+                .ok()?;
+            let geometry = MonitorGeometry {
+                x: monitor.x.into(),
+                y: monitor.y.into(),
+                width: monitor.width.into(),
+                height: monitor.height.into(),
+            };
+
+            Some(Monitor {
+                name: String::from_utf8_lossy(&name.name).into_owned(),
+                geometry,
+                work_area: work_area
+                    .and_then(|area| geometry.intersect(&area))
+                    .unwrap_or(geometry),
+                scale: 1.0,
+                primary: monitor.primary,
+                active: false,
+            })
+        })
+        .collect()
+}
+
+/// Asks for `PropertyNotify` events from a client. Fails with an error event,
+/// which the listener ignores, when the window is gone already.
+fn watch_properties(session: &X11Session, window: xproto::Window) {
+    let attributes = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+    let _ = session.conn.change_window_attributes(window, &attributes);
+}
+
+/// Where a client window is, without its frame, in root window coordinates.
+fn client_rect(session: &X11Session, window: xproto::Window) -> Result<MonitorGeometry, String> {
+    let geometry = session
+        .conn
+        .get_geometry(window)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let origin = session
+        .conn
+        .translate_coordinates(window, session.root, 0, 0)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    Ok(MonitorGeometry {
+        x: origin.dst_x.into(),
+        y: origin.dst_y.into(),
+        width: geometry.width.into(),
+        height: geometry.height.into(),
+    })
+}
+
+/// Names of the desktops, the ones the window manager didn't name go by their number.
+fn get_desktop_names(session: &X11Session) -> Vec<String> {
+    let atoms = &session.atoms;
+
+    let count = get_cardinal(session, session.root, atoms._NET_NUMBER_OF_DESKTOPS).unwrap_or(0);
+    let names = get_string(
+        &session.conn,
+        session.root,
+        atoms._NET_DESKTOP_NAMES,
+        atoms.UTF8_STRING,
+    )
+    .unwrap_or_default();
+    let names: Vec<&str> = names.split('\0').collect();
+
+    (0..count as usize)
+        .map(|desktop| match names.get(desktop) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => (desktop + 1).to_string(),
+        })
+        .collect()
+}
+
+/// Moves a window onto `target`, where it takes up the same part of the
+/// monitor it took up on the one it was on.
+fn move_to_monitor(
+    session: &X11Session,
+    window: xproto::Window,
+    target: &MonitorGeometry,
+) -> Result<(), String> {
+    let atoms = &session.atoms;
+    let client = client_rect(session, window)?;
+
+    // _NET_MOVERESIZE_WINDOW positions the frame but sizes the client
+    let [left, right, top, bottom] = get_property32(
+        &session.conn,
+        window,
+        atoms._NET_FRAME_EXTENTS,
+        AtomEnum::CARDINAL.into(),
+    )
+    .and_then(|extents| extents.try_into().ok())
+    .unwrap_or([0; 4]);
+
+    let frame = MonitorGeometry {
+        x: client.x - left as i32,
+        y: client.y - top as i32,
+        width: client.width + left + right,
+        height: client.height + top + bottom,
+    };
+
+    let monitors = randr_monitors(session);
+    let (center_x, center_y) = frame.center();
+    let Some(source) = monitors
+        .iter()
+        .map(|monitor| &monitor.geometry)
+        .find(|monitor| monitor.contains(center_x, center_y))
+        .or(monitors.first().map(|monitor| &monitor.geometry))
+    else {
+        return Err("RandR reports no monitors".to_string());
+    };
+
+    if source == target {
+        return Ok(());
+    }
+
+    let moved = source.map_rect(frame, target);
+
+    // Window managers keep maximized windows where they are, so let go of that for the move
+    let states = get_property32(
+        &session.conn,
+        window,
+        atoms._NET_WM_STATE,
+        AtomEnum::ATOM.into(),
+    )
+    .unwrap_or_default();
+    let maximized = [
+        atoms._NET_WM_STATE_MAXIMIZED_VERT,
+        atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+    ];
+    let was_maximized = maximized.iter().any(|state| states.contains(state));
+    let set_maximized = |change: u32| {
+        send_root_message(
+            session,
+            window,
+            atoms._NET_WM_STATE,
+            [change, maximized[0], maximized[1], 2, 0],
+        )
+    };
+
+    if was_maximized {
+        set_maximized(STATE_REMOVE)?;
+    }
+
+    send_root_message(
+        session,
+        window,
+        atoms._NET_MOVERESIZE_WINDOW,
+        [
+            MOVERESIZE_FLAGS,
+            moved.x as u32,
+            moved.y as u32,
+            moved.width.saturating_sub(left + right).max(1),
+            moved.height.saturating_sub(top + bottom).max(1),
+        ],
+    )?;
+
+    if was_maximized {
+        set_maximized(STATE_ADD)?;
+    }
+
+    Ok(())
+}
+
+/// Whether a compositing manager runs, which is what reads `_NET_WM_WINDOW_OPACITY`.
+fn has_compositor(session: &X11Session) -> bool {
+    let screen = session
+        .conn
+        .setup()
+        .roots
+        .iter()
+        .position(|screen| screen.root == session.root)
+        .unwrap_or(0);
+
+    // The compositor of screen n owns the _NET_WM_CM_Sn selection
+    let Some(selection) = session
+        .conn
+        .intern_atom(false, format!("_NET_WM_CM_S{screen}").as_bytes())
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+    else {
+        return false;
+    };
+
+    session
+        .conn
+        .get_selection_owner(selection.atom)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .is_some_and(|reply| reply.owner != x11rb::NONE)
+}
+
+fn send_root_message(
+    session: &X11Session,
+    window: xproto::Window,
+    message_type: xproto::Atom,
+    data: [u32; 5],
+) -> Result<(), String> {
+    let event = ClientMessageEvent::new(32, window, message_type, data);
+
+    session
+        .conn
+        .send_event(
+            false,
+            session.root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )
+        .map_err(|e| e.to_string())?;
+
+    session.conn.flush().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Child, Command};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{Duration, Instant};
+
+    static NEXT_DISPLAY: AtomicU32 = AtomicU32::new(0);
+
+    /// A throwaway Xvfb server. The test plays the window manager on its own
+    /// connection: it keeps the root window properties up to date and gets the
+    /// client messages the backend sends to the root window.
+    struct Xvfb {
+        server: Child,
+        display: String,
+        wm: X11Session,
+        clients: Vec<xproto::Window>,
+    }
+
+    impl Xvfb {
+        fn start() -> Self {
+            Self::with_screen("1280x800x24")
+        }
+
+        fn with_screen(screen: &str) -> Self {
+            // Far above the displays a desktop uses, and apart per test process
+            let number = 1000
+                + std::process::id() % 1000 * 16
+                + NEXT_DISPLAY.fetch_add(1, Ordering::Relaxed);
+            let display = format!(":{number}");
+
+            let server = Command::new("Xvfb")
+                .args([&display, "-screen", "0", screen, "-nolisten", "tcp"])
+                .spawn()
+                .expect("Xvfb should be installed");
+
+            let started = Instant::now();
+            let wm = loop {
+                match connect_to(Some(&display)) {
+                    Ok(session) => break session,
+                    Err(_) if started.elapsed() < Duration::from_secs(10) => {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    Err(e) => panic!("Xvfb didn't come up on {display}: {e}"),
+                }
+            };
+
+            wm.conn
+                .change_window_attributes(
+                    wm.root,
+                    &ChangeWindowAttributesAux::new().event_mask(EventMask::SUBSTRUCTURE_REDIRECT),
+                )
+                .unwrap()
+                .check()
+                .unwrap();
+
+            Self {
+                server,
+                display,
+                wm,
+                clients: Vec::new(),
+            }
+        }
+
+        fn backend(&self) -> X11Backend {
+            X11Backend::with_display(Some(&self.display)).unwrap()
+        }
+
+        /// Maps a window with the given title, pid and class and adds it to
+        /// `_NET_CLIENT_LIST`.
+        fn client(&mut self, title: &str, pid: u32, class: &str) -> xproto::Window {
+            self.client_at(title, pid, class, (0, 0, 320, 240))
+        }
+
+        fn client_at(
+            &mut self,
+            title: &str,
+            pid: u32,
+            class: &str,
+            (x, y, width, height): (i16, i16, u16, u16),
+        ) -> xproto::Window {
+            let wm = &self.wm;
+            let window = wm.conn.generate_id().unwrap();
+
+            wm.conn
+                .create_window(
+                    x11rb::COPY_DEPTH_FROM_PARENT,
+                    window,
+                    wm.root,
+                    x,
+                    y,
+                    width,
+                    height,
+                    0,
+                    xproto::WindowClass::INPUT_OUTPUT,
+                    x11rb::COPY_FROM_PARENT,
+                    &xproto::CreateWindowAux::new(),
+                )
+                .unwrap();
+            wm.conn
+                .change_property8(
+                    PropMode::REPLACE,
+                    window,
+                    wm.atoms._NET_WM_NAME,
+                    wm.atoms.UTF8_STRING,
+                    title.as_bytes(),
+                )
+                .unwrap();
+            wm.conn
+                .change_property8(
+                    PropMode::REPLACE,
+                    window,
+                    AtomEnum::WM_CLASS,
+                    AtomEnum::STRING,
+                    format!("{}\0{class}\0", class.to_lowercase()).as_bytes(),
+                )
+                .unwrap();
+            self.set_cardinal(window, wm.atoms._NET_WM_PID, pid);
+            wm.conn.map_window(window).unwrap();
+
+            self.clients.push(window);
+            self.set_atoms(
+                wm.root,
+                wm.atoms._NET_CLIENT_LIST,
+                AtomEnum::WINDOW,
+                &self.clients,
+            );
+            window
+        }
+
+        fn set_cardinal(&self, window: xproto::Window, property: xproto::Atom, value: u32) {
+            self.set_atoms(window, property, AtomEnum::CARDINAL, &[value]);
+        }
+
+        fn set_atoms(
+            &self,
+            window: xproto::Window,
+            property: xproto::Atom,
+            type_: AtomEnum,
+            values: &[u32],
+        ) {
+            self.wm
+                .conn
+                .change_property32(PropMode::REPLACE, window, property, type_, values)
+                .unwrap()
+                .check()
+                .unwrap();
+        }
+
+        /// Splits the screen into RandR monitors side by side, the first one
+        /// taking over the screen's output.
+        fn set_monitors(&self, monitors: &[(&str, MonitorGeometry)]) {
+            let wm = &self.wm;
+            let output = wm
+                .conn
+                .randr_get_screen_resources(wm.root)
+                .unwrap()
+                .reply()
+                .unwrap()
+                .outputs[0];
+
+            for (index, (name, geometry)) in monitors.iter().enumerate() {
+                let name = wm
+                    .conn
+                    .intern_atom(false, name.as_bytes())
+                    .unwrap()
+                    .reply()
+                    .unwrap()
+                    .atom;
+                let monitor = x11rb::protocol::randr::MonitorInfo {
+                    name,
+                    primary: index == 0,
+                    automatic: false,
+                    x: geometry.x as i16,
+                    y: geometry.y as i16,
+                    width: geometry.width as u16,
+                    height: geometry.height as u16,
+                    width_in_millimeters: 0,
+                    height_in_millimeters: 0,
+                    outputs: if index == 0 { vec![output] } else { Vec::new() },
+                };
+                wm.conn
+                    .randr_set_monitor(wm.root, monitor)
+                    .unwrap()
+                    .check()
+                    .unwrap();
+            }
+        }
+
+        /// The next client message sent to the root window.
+        fn client_message(&self) -> ClientMessageEvent {
+            let started = Instant::now();
+
+            while started.elapsed() < Duration::from_secs(5) {
+                match self.wm.conn.poll_for_event().unwrap() {
+                    Some(Event::ClientMessage(event)) => return event,
+                    Some(_) => {}
+                    None => std::thread::sleep(Duration::from_millis(10)),
+                }
+            }
+
+            panic!("No client message reached the root window");
+        }
+    }
+
+    impl Drop for Xvfb {
+        fn drop(&mut self) {
+            let _ = self.server.kill();
+            let _ = self.server.wait();
+        }
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn lists_clients_newest_first_without_our_own() {
+        let mut xvfb = Xvfb::start();
+        let atoms = xvfb.wm.atoms;
+
+        let editor = xvfb.client("notes.txt - Editor", 4001, "Editor");
+        let palette = xvfb.client("Tools", 4001, "Editor");
+        xvfb.set_atoms(
+            palette,
+            atoms._NET_WM_WINDOW_TYPE,
+            AtomEnum::ATOM,
+            &[atoms._NET_WM_WINDOW_TYPE_UTILITY],
+        );
+        let tray = xvfb.client("Tray", 4002, "Tray");
+        xvfb.set_atoms(
+            tray,
+            atoms._NET_WM_STATE,
+            AtomEnum::ATOM,
+            &[atoms._NET_WM_STATE_SKIP_TASKBAR],
+        );
+        let dialog = xvfb.client("Save as", 4001, "Editor");
+        xvfb.set_atoms(
+            dialog,
+            atoms._NET_WM_WINDOW_TYPE,
+            AtomEnum::ATOM,
+            &[atoms._NET_WM_WINDOW_TYPE_DIALOG],
+        );
+        xvfb.client("binocular", 4999, "binocular");
+
+        let windows = xvfb.backend().get_windows(4999);
+        let listed: Vec<_> = windows
+            .iter()
+            .map(|window| (window.id as u32, window.title.as_str(), window.window_type))
+            .collect();
+
+        assert_eq!(
+            listed,
+            [
+                (dialog, "Save as", WindowType::Dialog),
+                (tray, "Tray", WindowType::Utility),
+                (palette, "Tools", WindowType::Utility),
+                (editor, "notes.txt - Editor", WindowType::Normal),
+            ]
+        );
+        assert_eq!(windows[3].process_id, 4001);
+        assert_eq!(windows[3].app_id.as_deref(), Some("Editor"));
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn focus_and_close_ask_the_window_manager() {
+        let mut xvfb = Xvfb::start();
+        let window = xvfb.client("Terminal", 4001, "Terminal");
+        let backend = xvfb.backend();
+
+        backend.focus_window(window as WindowId).unwrap();
+        let message = xvfb.client_message();
+        assert_eq!(message.window, window);
+        assert_eq!(message.type_, xvfb.wm.atoms._NET_ACTIVE_WINDOW);
+        // Sent as a pager
+        assert_eq!(message.data.as_data32()[0], 2);
+
+        backend.close_window(window as WindowId).unwrap();
+        let message = xvfb.client_message();
+        assert_eq!(message.window, window);
+        assert_eq!(message.type_, xvfb.wm.atoms._NET_CLOSE_WINDOW);
+        assert_eq!(message.data.as_data32()[1], 2);
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn icon_handle_changes_with_the_icon() {
+        let mut xvfb = Xvfb::start();
+        let window = xvfb.client("Terminal", 4001, "Terminal");
+        let backend = xvfb.backend();
+        let icon = |pixel: u32| [[2, 2].as_slice(), &[pixel; 4]].concat();
+        let handle = || backend.get_icon_handle(window as WindowId).unwrap();
+
+        xvfb.set_atoms(
+            window,
+            xvfb.wm.atoms._NET_WM_ICON,
+            AtomEnum::CARDINAL,
+            &icon(0xffff0000),
+        );
+        let red = handle();
+        assert_eq!(handle(), red);
+
+        xvfb.set_atoms(
+            window,
+            xvfb.wm.atoms._NET_WM_ICON,
+            AtomEnum::CARDINAL,
+            &icon(0xff0000ff),
+        );
+        assert_ne!(handle(), red);
+
+        // Past the part looked at, only the length tells
+        let mut large = vec![0; ICON_FINGERPRINT_LENGTH as usize];
+        xvfb.set_atoms(
+            window,
+            xvfb.wm.atoms._NET_WM_ICON,
+            AtomEnum::CARDINAL,
+            &large,
+        );
+        let before = handle();
+        large.push(0);
+        xvfb.set_atoms(
+            window,
+            xvfb.wm.atoms._NET_WM_ICON,
+            AtomEnum::CARDINAL,
+            &large,
+        );
+        assert_ne!(handle(), before);
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn captures_what_the_window_shows() {
+        let mut xvfb = Xvfb::start();
+        let window = xvfb.client_at("Paint", 4001, "Paint", (10, 20, 64, 32));
+        let hidden = xvfb.client("Hidden", 4001, "Paint");
+        let backend = xvfb.backend();
+
+        // Left half red, right half blue
+        let wm = &xvfb.wm;
+        let gc = wm.conn.generate_id().unwrap();
+        wm.conn
+            .create_gc(gc, window, &xproto::CreateGCAux::new())
+            .unwrap();
+        for (x, color) in [(0, 0xff0000), (32, 0x0000ff)] {
+            wm.conn
+                .change_gc(gc, &xproto::ChangeGCAux::new().foreground(color))
+                .unwrap();
+            wm.conn
+                .poly_fill_rectangle(
+                    window,
+                    gc,
+                    &[xproto::Rectangle {
+                        x,
+                        y: 0,
+                        width: 32,
+                        height: 32,
+                    }],
+                )
+                .unwrap()
+                .check()
+                .unwrap();
+        }
+        wm.conn.unmap_window(hidden).unwrap().check().unwrap();
+
+        let image = backend.capture_window(window as WindowId).unwrap();
+        assert_eq!(image.dimensions(), (64, 32));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(31, 31).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(32, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(63, 31).0, [0, 0, 255, 255]);
+
+        assert_eq!(
+            backend.capture_window(hidden as WindowId),
+            Err("The window isn't visible".to_string())
+        );
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn moves_and_summons_across_monitors() {
+        let mut xvfb = Xvfb::with_screen("2560x800x24");
+        let half = |x| MonitorGeometry {
+            x,
+            y: 0,
+            width: 1280,
+            height: 800,
+        };
+        xvfb.set_monitors(&[("LEFT", half(0)), ("RIGHT", half(1280))]);
+        let window = xvfb.client_at("Editor", 4001, "Editor", (100, 50, 320, 240));
+        let backend = xvfb.backend();
+        let atoms = xvfb.wm.atoms;
+
+        let monitors: Vec<String> = backend
+            .get_monitors()
+            .into_iter()
+            .map(|monitor| monitor.name)
+            .collect();
+        assert_eq!(monitors, ["LEFT", "RIGHT"]);
+
+        let move_to = |name: &str| {
+            backend.move_window(window as WindowId, &MoveTarget::Monitor(name.to_string()))
+        };
+        let expect = |message_type: xproto::Atom, data: [u32; 5]| {
+            let message = xvfb.client_message();
+            assert_eq!(message.window, window);
+            assert_eq!(message.type_, message_type);
+            assert_eq!(message.data.as_data32(), data);
+        };
+
+        // Already there, nothing is sent and the next message is the move to the right
+        move_to("LEFT").unwrap();
+        move_to("RIGHT").unwrap();
+        expect(
+            atoms._NET_MOVERESIZE_WINDOW,
+            [MOVERESIZE_FLAGS, 1380, 50, 320, 240],
+        );
+        assert_eq!(move_to("NOPE"), Err("No monitor named NOPE".to_string()));
+
+        // Maximized windows are let go of for the move
+        let maximized = [
+            atoms._NET_WM_STATE_MAXIMIZED_VERT,
+            atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+        ];
+        xvfb.set_atoms(window, atoms._NET_WM_STATE, AtomEnum::ATOM, &maximized);
+        move_to("RIGHT").unwrap();
+        expect(
+            atoms._NET_WM_STATE,
+            [STATE_REMOVE, maximized[0], maximized[1], 2, 0],
+        );
+        expect(
+            atoms._NET_MOVERESIZE_WINDOW,
+            [MOVERESIZE_FLAGS, 1380, 50, 320, 240],
+        );
+        expect(
+            atoms._NET_WM_STATE,
+            [STATE_ADD, maximized[0], maximized[1], 2, 0],
+        );
+        xvfb.set_atoms(window, atoms._NET_WM_STATE, AtomEnum::ATOM, &[]);
+
+        // Summoning brings it to the current desktop and the pointer's monitor
+        xvfb.set_cardinal(xvfb.wm.root, atoms._NET_CURRENT_DESKTOP, 0);
+        xvfb.set_cardinal(window, atoms._NET_WM_DESKTOP, 1);
+        xvfb.wm
+            .conn
+            .warp_pointer(x11rb::NONE, xvfb.wm.root, 0, 0, 0, 0, 2000, 400)
+            .unwrap()
+            .check()
+            .unwrap();
+
+        backend.summon_window(window as WindowId).unwrap();
+        expect(atoms._NET_WM_DESKTOP, [0, 2, 0, 0, 0]);
+        expect(
+            atoms._NET_MOVERESIZE_WINDOW,
+            [MOVERESIZE_FLAGS, 1380, 50, 320, 240],
+        );
+        assert_eq!(xvfb.client_message().type_, atoms._NET_ACTIVE_WINDOW);
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn monitors_get_their_part_of_the_work_area() {
+        let xvfb = Xvfb::with_screen("2560x800x24");
+        let rect = |x, y, width, height| MonitorGeometry {
+            x,
+            y,
+            width,
+            height,
+        };
+        xvfb.set_monitors(&[
+            ("LEFT", rect(0, 0, 1280, 800)),
+            ("RIGHT", rect(1280, 0, 1280, 800)),
+        ]);
+        let atoms = xvfb.wm.atoms;
+
+        // A top panel on every desktop, on the second one a dock along the right edge too
+        xvfb.set_atoms(
+            xvfb.wm.root,
+            atoms._NET_WORKAREA,
+            AtomEnum::CARDINAL,
+            &[0, 32, 2560, 768, 0, 32, 2400, 768],
+        );
+        xvfb.set_cardinal(xvfb.wm.root, atoms._NET_CURRENT_DESKTOP, 1);
+        xvfb.wm
+            .conn
+            .warp_pointer(x11rb::NONE, xvfb.wm.root, 0, 0, 0, 0, 2000, 400)
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let monitors = xvfb.backend().get_monitors();
+        let summary: Vec<_> = monitors
+            .iter()
+            .map(|monitor| {
+                (
+                    monitor.name.as_str(),
+                    monitor.geometry,
+                    monitor.work_area,
+                    monitor.primary,
+                    monitor.active,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "LEFT",
+                    rect(0, 0, 1280, 800),
+                    rect(0, 32, 1280, 768),
+                    true,
+                    false
+                ),
+                (
+                    "RIGHT",
+                    rect(1280, 0, 1280, 800),
+                    rect(1280, 32, 1120, 768),
+                    false,
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb"]
+    fn streams_changes() {
+        let mut xvfb = Xvfb::start();
+        let atoms = xvfb.wm.atoms;
+        let existing = xvfb.client("Terminal", 4001, "Terminal");
+        let (sender, events) = std::sync::mpsc::channel();
+        xvfb.backend()
+            .subscribe(Box::new(move |event| {
+                let _ = sender.send(event);
+            }))
+            .unwrap();
+        let next = || events.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let window = xvfb.client("Editor", 4002, "Editor");
+        assert_eq!(next(), WindowEvent::Created(window as WindowId));
+
+        // Titles, desktops and states of windows listed before and after subscribing
+        for client in [existing, window] {
+            xvfb.wm
+                .conn
+                .change_property8(
+                    PropMode::REPLACE,
+                    client,
+                    atoms._NET_WM_NAME,
+                    atoms.UTF8_STRING,
+                    b"Renamed",
+                )
+                .unwrap()
+                .check()
+                .unwrap();
+            assert_eq!(next(), WindowEvent::Changed(client as WindowId));
+        }
+        xvfb.set_cardinal(existing, atoms._NET_WM_DESKTOP, 1);
+        assert_eq!(next(), WindowEvent::Changed(existing as WindowId));
+        xvfb.set_atoms(
+            window,
+            atoms._NET_WM_STATE,
+            AtomEnum::ATOM,
+            &[atoms._NET_WM_STATE_HIDDEN],
+        );
+        assert_eq!(next(), WindowEvent::Changed(window as WindowId));
+
+        // Anything else about them isn't worth a refresh
+        xvfb.set_cardinal(window, atoms._NET_WM_PID, 4003);
+
+        xvfb.set_atoms(
+            xvfb.wm.root,
+            atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+            &[window],
+        );
+        assert_eq!(next(), WindowEvent::Activated(window as WindowId));
+
+        xvfb.clients.retain(|client| *client != existing);
+        xvfb.set_atoms(
+            xvfb.wm.root,
+            atoms._NET_CLIENT_LIST,
+            AtomEnum::WINDOW,
+            &xvfb.clients,
+        );
+        assert_eq!(next(), WindowEvent::Destroyed(existing as WindowId));
+    }
+}
@@ -1,11 +1,111 @@
-pub fn get_windows() {
-    println!("Getting windows on macOS");
-}
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MoveTarget, WindowAction, WindowBackend,
+    WindowCapabilities, WindowId, Workspace, MOVE_UNSUPPORTED, WORKSPACES_UNSUPPORTED,
+};
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::window_manager::Window;
+use image::RgbaImage;
 
-pub fn focus_window() {
-    println!("Focusing window on macOS");
-}
+pub struct MacosBackend;
+
+impl WindowBackend for MacosBackend {
+    fn get_windows(&self, _current_pid: u32) -> Vec<Window> {
+        println!("Getting windows on macOS");
+        Vec::new()
+    }
+
+    fn get_active_window(&self) -> Option<WindowId> {
+        None
+    }
+
+    fn focus_window(&self, _id: WindowId) -> Result<(), String> {
+        println!("Focusing window on macOS");
+        Ok(())
+    }
+
+    fn close_window(&self, _id: WindowId) -> Result<(), String> {
+        println!("Closing window on macOS");
+        Ok(())
+    }
+
+    fn window_action(&self, _id: WindowId, action: WindowAction) -> Result<(), String> {
+        Err(action.unsupported())
+    }
+
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::default()
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        Vec::new()
+    }
+
+    fn move_window(&self, _id: WindowId, _target: &MoveTarget) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn summon_window(&self, _id: WindowId) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        Vec::new()
+    }
+
+    fn switch_workspace(&self, _id: &str) -> Result<(), String> {
+        Err(WORKSPACES_UNSUPPORTED.to_string())
+    }
+
+    fn get_window_icon(&self, _window: &Window, _size: u32) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn get_icon_handle(&self, _id: WindowId) -> Option<IconHandle> {
+        None
+    }
+
+    fn capture_window(&self, _id: WindowId) -> Result<RgbaImage, String> {
+        Err("Window previews aren't supported on macOS yet".to_string())
+    }
+
+    fn get_process_name(&self, _process_id: u32) -> Option<String> {
+        // TODO: Implement proper macOS process name retrieval
+        None
+    }
+
+    fn get_process_path(&self, _process_id: u32) -> Option<String> {
+        // TODO: Implement proper macOS process path retrieval
+        None
+    }
+
+    fn get_parent_process_id(&self, _process_id: u32) -> Option<u32> {
+        None
+    }
+
+    fn get_process_info(&self, _process_id: u32) -> Option<ProcessInfo> {
+        // TODO: Implement proper macOS process info retrieval
+        None
+    }
+
+    fn get_sandbox(&self, _process_id: u32) -> Option<Sandbox> {
+        None
+    }
+
+    fn get_app_identity(&self, _window: &Window) -> Option<AppIdentity> {
+        // TODO: Read the bundle name of the application
+        None
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        // TODO: List NSScreen frames and visible frames
+        Vec::new()
+    }
 
-pub fn close_window() {
-    println!("Closing window on macOS");
+    fn subscribe(&self, _callback: EventCallback) -> Result<(), String> {
+        // TODO: Implement macOS window event listener
+        // This would require using Objective-C bindings to register for window notifications,
+        // potentially through NSWorkspace notifications or the Accessibility API
+        println!("window events are not yet implemented on macOS");
+        Ok(())
+    }
 }
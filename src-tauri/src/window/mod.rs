@@ -1,9 +1,457 @@
 #[cfg(target_os = "windows")]
 mod windows;
 #[cfg(target_os = "windows")]
-pub use windows::*;
+pub use windows::WindowsBackend;
 
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "macos")]
-pub use macos::*;
+pub use macos::MacosBackend;
+
+#[cfg(target_os = "linux")]
+mod linux_hyprland;
+#[cfg(target_os = "linux")]
+mod linux_sway;
+#[cfg(target_os = "linux")]
+mod linux_wayland;
+#[cfg(target_os = "linux")]
+mod linux_x11;
+#[cfg(target_os = "linux")]
+pub use linux_hyprland::HyprlandBackend;
+#[cfg(target_os = "linux")]
+pub use linux_sway::SwayBackend;
+#[cfg(target_os = "linux")]
+pub use linux_wayland::WaylandBackend;
+#[cfg(target_os = "linux")]
+pub use linux_x11::X11Backend;
+
+#[cfg(target_os = "linux")]
+mod null;
+#[cfg(target_os = "linux")]
+pub use null::NullBackend;
+
+#[cfg(test)]
+mod fake;
+#[cfg(test)]
+pub use fake::FakeBackend;
+
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::window_manager::Window;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Native handle of a window: an HWND on Windows, an X11 window id on X11,
+/// or an id handed out by the backend when the window system has none.
+pub type WindowId = isize;
+
+/// Changes a backend reports to its subscriber.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowEvent {
+    Created(WindowId),
+    Destroyed(WindowId),
+    /// The title or state of a window changed.
+    Changed(WindowId),
+    /// A window received focus.
+    Activated(WindowId),
+}
+
+pub type EventCallback = Box<dyn Fn(WindowEvent) + Send + Sync>;
+
+/// What kind of window the window system says it is.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowType {
+    #[default]
+    Normal,
+    Dialog,
+    /// Tool palettes, toolbars and torn-off menus.
+    Utility,
+    /// Splash screens, docks, captionless and other windows that aren't meant to be switched to.
+    Popup,
+}
+
+/// Things that can be done to a window besides focusing and closing it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowAction {
+    Minimize,
+    Maximize,
+    /// Brings a window back from being minimized or maximized.
+    Restore,
+    ToggleFullscreen,
+    /// Keeps the window above all others, or stops doing so.
+    ToggleAlwaysOnTop,
+    /// Shows the window on every workspace, or only on its own again.
+    TogglePin,
+    /// Rolls the window up into its title bar, or back down.
+    ToggleShade,
+    /// From 0.0 for invisible to 1.0 for opaque.
+    SetOpacity(f64),
+}
+
+impl WindowAction {
+    /// The error backends give for actions they can't carry out.
+    pub fn unsupported(&self) -> String {
+        format!("{self:?} isn't supported by this window system")
+    }
+}
+
+/// The `WindowAction`s a backend can carry out, so the UI only offers those.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WindowCapabilities {
+    pub minimize: bool,
+    pub maximize: bool,
+    pub restore: bool,
+    pub fullscreen: bool,
+    pub always_on_top: bool,
+    pub pin: bool,
+    pub shade: bool,
+    pub opacity: bool,
+    /// Whether `summon_window` works, moving windows to a target needs `get_move_targets`.
+    pub summon: bool,
+    /// Whether `capture_window` works.
+    pub preview: bool,
+}
+
+impl WindowCapabilities {
+    pub fn supports(&self, action: WindowAction) -> bool {
+        match action {
+            WindowAction::Minimize => self.minimize,
+            WindowAction::Maximize => self.maximize,
+            WindowAction::Restore => self.restore,
+            WindowAction::ToggleFullscreen => self.fullscreen,
+            WindowAction::ToggleAlwaysOnTop => self.always_on_top,
+            WindowAction::TogglePin => self.pin,
+            WindowAction::ToggleShade => self.shade,
+            WindowAction::SetOpacity(_) => self.opacity,
+        }
+    }
+}
+
+/// The error of backends that can't move windows between monitors and workspaces.
+pub const MOVE_UNSUPPORTED: &str = "Moving windows isn't supported by this window system";
+
+/// Where `move_window` sends a window.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveTarget {
+    /// A monitor by output name, like `DP-1`.
+    Monitor(String),
+    /// A workspace by name, unnamed desktops go by their number counting from 1.
+    Workspace(String),
+}
+
+/// The error of backends that don't know about workspaces.
+pub const WORKSPACES_UNSUPPORTED: &str = "Workspaces aren't supported by this window system";
+
+/// A workspace or virtual desktop.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    /// Stays the same while the workspace exists, unlike the name.
+    pub id: String,
+    pub name: String,
+    /// Whether a monitor shows it right now. On X11 that's only the current
+    /// desktop, tiling compositors show one workspace per monitor.
+    pub visible: bool,
+    /// How many listed windows it holds, filled in by the manager.
+    #[serde(default)]
+    pub window_count: usize,
+}
+
+/// Which windows the switcher lists, for workspaces and monitors alike.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    #[default]
+    All,
+    /// Only the ones on the workspaces shown right now or on the active
+    /// monitor, and the ones the window system doesn't place anywhere.
+    Current,
+}
+
+/// Identifies the icon a window shows without rendering it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconHandle {
+    /// True if the window set an icon of its own rather than using its application's.
+    pub own: bool,
+    /// Changes whenever the icon does, e.g. the HICON on Windows.
+    pub fingerprint: u64,
+}
+
+/// The application a window belongs to, as the desktop knows it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppIdentity {
+    /// Desktop file id, only used for windows the window system gives no app id.
+    pub app_id: Option<String>,
+    /// Human-friendly name such as "Visual Studio Code".
+    pub app_name: Option<String>,
+    /// Path of the application's `.desktop` file.
+    pub desktop_file: Option<String>,
+}
+
+/// Looks up the desktop entry of a window's application, what the Linux backends share.
+///
+/// Sandboxed applications are looked up by the id their sandbox knows them
+/// by first, their windows and executables often belong to a runtime.
+#[cfg(target_os = "linux")]
+fn resolve_desktop_entry(window: &Window) -> Option<AppIdentity> {
+    use crate::utils::freedesktop::desktop_entry;
+    use crate::utils::process::SandboxKind;
+
+    let sandboxed = window.sandbox.as_ref().and_then(|sandbox| {
+        let app_id = sandbox.app_id.as_deref()?;
+        match sandbox.kind {
+            SandboxKind::Flatpak => desktop_entry::find(app_id),
+            SandboxKind::Snap => desktop_entry::find_snap(app_id),
+            _ => None,
+        }
+    });
+
+    let entry = sandboxed
+        .or_else(|| desktop_entry::resolve(window.app_id.as_deref(), window.exe_path.as_deref()))?;
+
+    Some(AppIdentity {
+        app_id: Some(entry.id),
+        app_name: entry.name,
+        desktop_file: Some(entry.path.to_string_lossy().into_owned()),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A monitor, or output in Wayland terms.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Monitor {
+    /// Output name like `DP-1`, which is what `Window::output` holds.
+    pub name: String,
+    pub geometry: MonitorGeometry,
+    /// The part of the monitor left to windows, without panels and docks.
+    pub work_area: MonitorGeometry,
+    /// Physical pixels per logical pixel, 1.0 where the window system doesn't say.
+    pub scale: f64,
+    pub primary: bool,
+    /// Whether it holds the focused window, the switcher opens on this one.
+    pub active: bool,
+}
+
+impl MonitorGeometry {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && x < self.x + self.width as i32
+            && y < self.y + self.height as i32
+    }
+
+    /// The part of `other` that lies on this monitor.
+    pub fn intersect(&self, other: &MonitorGeometry) -> Option<MonitorGeometry> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width as i32).min(other.x + other.width as i32);
+        let bottom = (self.y + self.height as i32).min(other.y + other.height as i32);
+
+        (right > left && bottom > top).then(|| MonitorGeometry {
+            x: left,
+            y: top,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        })
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.x + self.width as i32 / 2,
+            self.y + self.height as i32 / 2,
+        )
+    }
+
+    /// Moves `rect` from this monitor onto `target`, keeping its position and
+    /// size relative to the monitor.
+    pub fn map_rect(&self, rect: MonitorGeometry, target: &MonitorGeometry) -> MonitorGeometry {
+        let scale_x = target.width as f64 / self.width.max(1) as f64;
+        let scale_y = target.height as f64 / self.height.max(1) as f64;
+
+        MonitorGeometry {
+            x: target.x + ((rect.x - self.x) as f64 * scale_x).round() as i32,
+            y: target.y + ((rect.y - self.y) as f64 * scale_y).round() as i32,
+            width: (rect.width as f64 * scale_x).round() as u32,
+            height: (rect.height as f64 * scale_y).round() as u32,
+        }
+    }
+}
+
+/// Platform window system integration used by the `WindowManager`.
+pub trait WindowBackend: Send + Sync {
+    /// Lists the visible top-level windows, skipping the ones owned by `current_pid`.
+    ///
+    /// Deciding which of them are worth switching to is left to the filter
+    /// rules. Process names, paths and icons are left empty, the manager fills
+    /// them in through `get_process_name`, `get_process_path` and `get_window_icon`.
+    fn get_windows(&self, current_pid: u32) -> Vec<Window>;
+
+    /// The window with focus, `None` if there is none or the window system doesn't tell.
+    fn get_active_window(&self) -> Option<WindowId>;
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String>;
+
+    fn close_window(&self, id: WindowId) -> Result<(), String>;
+
+    /// Carries out `action`, which the manager has checked against `capabilities`.
+    fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String>;
+
+    /// What `window_action` can do with the window system we're running on.
+    fn capabilities(&self) -> WindowCapabilities;
+
+    /// The monitors and workspaces `move_window` can send windows to.
+    fn get_move_targets(&self) -> Vec<MoveTarget>;
+
+    /// Sends a window to a workspace, or to a monitor keeping its size
+    /// relative to the monitor. Doesn't focus it.
+    fn move_window(&self, id: WindowId, target: &MoveTarget) -> Result<(), String>;
+
+    /// Brings a window to the current workspace and the monitor under the
+    /// pointer, keeping its relative size, then focuses it.
+    fn summon_window(&self, id: WindowId) -> Result<(), String>;
+
+    /// Lists the workspaces in the window system's order, empty if it has none
+    /// we can see. Windows refer to them by `workspace_id`.
+    fn get_workspaces(&self) -> Vec<Workspace>;
+
+    fn switch_workspace(&self, id: &str) -> Result<(), String>;
+
+    /// Returns the window icon, `size` pixels square, encoded as PNG.
+    ///
+    /// Gets the whole window so backends that can't ask for the icon itself
+    /// can look it up by app id.
+    fn get_window_icon(&self, window: &Window, size: u32) -> Option<Vec<u8>>;
+
+    /// Captures what the window currently shows, at its full size.
+    fn capture_window(&self, id: WindowId) -> Result<RgbaImage, String>;
+
+    /// Cheaply identifies the icon `get_window_icon` would render, `None` if that can't be told.
+    fn get_icon_handle(&self, id: WindowId) -> Option<IconHandle>;
+
+    fn get_process_name(&self, process_id: u32) -> Option<String>;
+
+    /// Returns the full path of the process' executable.
+    fn get_process_path(&self, process_id: u32) -> Option<String>;
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32>;
+
+    /// Gathers everything known about a process, `None` if it doesn't exist.
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo>;
+
+    /// Tells whether a process runs in a Flatpak, snap or container, `None` on the host.
+    fn get_sandbox(&self, process_id: u32) -> Option<Sandbox>;
+
+    /// Works out which application a window belongs to, from its sandbox,
+    /// app id, class or executable. Called once those are filled in.
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity>;
+
+    /// Lists the monitors, empty if the window system doesn't tell. At most
+    /// one of them is active.
+    fn get_monitors(&self) -> Vec<Monitor>;
+
+    /// Starts delivering window events to `callback`.
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String>;
+}
+
+/// Picks the backend for the current platform.
+pub fn default_backend() -> Box<dyn WindowBackend> {
+    #[cfg(target_os = "windows")]
+    {
+        Box::new(WindowsBackend)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Box::new(MacosBackend)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Hyprland's wlr protocol support is patchy, its own IPC is the reliable route
+        if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            match HyprlandBackend::new() {
+                Ok(backend) => return Box::new(backend),
+                Err(e) => println!("Failed to connect to Hyprland IPC: {e}"),
+            }
+        }
+
+        // The sway / i3 tree knows more about each window than the generic protocols do
+        if std::env::var_os("SWAYSOCK").is_some() || std::env::var_os("I3SOCK").is_some() {
+            match SwayBackend::new() {
+                Ok(backend) => return Box::new(backend),
+                Err(e) => println!("Failed to connect to sway IPC: {e}"),
+            }
+        }
+
+        // XWayland only sees X11 clients, prefer the native protocol when there is one
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            match WaylandBackend::new() {
+                Ok(backend) => return Box::new(backend),
+                Err(e) => println!("Falling back to X11: {e}"),
+            }
+        }
+
+        match X11Backend::new() {
+            Ok(backend) => Box::new(backend),
+            Err(e) => {
+                println!("Failed to connect to X server, no windows will be listed: {e}");
+                Box::new(NullBackend)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> MonitorGeometry {
+        MonitorGeometry {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn intersect_disjoint() {
+        let monitor = rect(0, 0, 1920, 1080);
+        assert_eq!(monitor.intersect(&rect(2000, 100, 400, 300)), None);
+        assert_eq!(monitor.intersect(&rect(100, -500, 400, 300)), None);
+    }
+
+    #[test]
+    fn intersect_touching_edges() {
+        let monitor = rect(0, 0, 1920, 1080);
+        assert_eq!(monitor.intersect(&rect(1920, 0, 2560, 1440)), None);
+        assert_eq!(monitor.intersect(&rect(0, 1080, 1920, 32)), None);
+        assert_eq!(monitor.intersect(&rect(-400, -300, 400, 300)), None);
+    }
+
+    #[test]
+    fn intersect_overlapping() {
+        let monitor = rect(0, 0, 1920, 1080);
+        assert_eq!(
+            monitor.intersect(&rect(1820, 980, 400, 300)),
+            Some(rect(1820, 980, 100, 100))
+        );
+    }
+
+    #[test]
+    fn intersect_containment() {
+        let monitor = rect(1920, 0, 2560, 1440);
+        let window = rect(2000, 100, 800, 600);
+        assert_eq!(monitor.intersect(&window), Some(window));
+        assert_eq!(window.intersect(&monitor), Some(window));
+        assert_eq!(monitor.intersect(&monitor), Some(monitor));
+    }
+}
@@ -0,0 +1,103 @@
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MoveTarget, WindowAction, WindowBackend,
+    WindowCapabilities, WindowId, Workspace, MOVE_UNSUPPORTED, WORKSPACES_UNSUPPORTED,
+};
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::window_manager::Window;
+use image::RgbaImage;
+
+/// Stands in when no window system could be reached: lists nothing and
+/// supports nothing, so the switcher stays empty instead of failing.
+pub struct NullBackend;
+
+const NO_WINDOW_SYSTEM: &str = "Not connected to a window system";
+
+impl WindowBackend for NullBackend {
+    fn get_windows(&self, _current_pid: u32) -> Vec<Window> {
+        Vec::new()
+    }
+
+    fn get_active_window(&self) -> Option<WindowId> {
+        None
+    }
+
+    fn focus_window(&self, _id: WindowId) -> Result<(), String> {
+        Err(NO_WINDOW_SYSTEM.to_string())
+    }
+
+    fn close_window(&self, _id: WindowId) -> Result<(), String> {
+        Err(NO_WINDOW_SYSTEM.to_string())
+    }
+
+    fn window_action(&self, _id: WindowId, action: WindowAction) -> Result<(), String> {
+        Err(action.unsupported())
+    }
+
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities::default()
+    }
+
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        Vec::new()
+    }
+
+    fn move_window(&self, _id: WindowId, _target: &MoveTarget) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn summon_window(&self, _id: WindowId) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        Vec::new()
+    }
+
+    fn switch_workspace(&self, _id: &str) -> Result<(), String> {
+        Err(WORKSPACES_UNSUPPORTED.to_string())
+    }
+
+    fn get_window_icon(&self, _window: &Window, _size: u32) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn get_icon_handle(&self, _id: WindowId) -> Option<IconHandle> {
+        None
+    }
+
+    fn capture_window(&self, _id: WindowId) -> Result<RgbaImage, String> {
+        Err(NO_WINDOW_SYSTEM.to_string())
+    }
+
+    fn get_process_name(&self, _process_id: u32) -> Option<String> {
+        None
+    }
+
+    fn get_process_path(&self, _process_id: u32) -> Option<String> {
+        None
+    }
+
+    fn get_parent_process_id(&self, _process_id: u32) -> Option<u32> {
+        None
+    }
+
+    fn get_process_info(&self, _process_id: u32) -> Option<ProcessInfo> {
+        None
+    }
+
+    fn get_sandbox(&self, _process_id: u32) -> Option<Sandbox> {
+        None
+    }
+
+    fn get_app_identity(&self, _window: &Window) -> Option<AppIdentity> {
+        None
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        Vec::new()
+    }
+
+    fn subscribe(&self, _callback: EventCallback) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -1,65 +1,234 @@
-use windows::Win32::Foundation::{BOOL, HWND, LPARAM, TRUE};
+use image::RgbaImage;
+use windows::Win32::Foundation::{BOOL, COLORREF, HWND, LPARAM, TRUE};
 use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetWindowLongPtrW, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
-    IsWindowVisible, SendMessageW, SetForegroundWindow, ShowWindow, GWL_EXSTYLE, GWL_STYLE,
-    SW_RESTORE, WM_CLOSE, WS_CAPTION, WS_EX_TOOLWINDOW, WS_VISIBLE,
+    EnumWindows, GetClassNameW, GetForegroundWindow, GetWindowLongPtrW, GetWindowTextW,
+    GetWindowThreadProcessId, IsIconic, IsWindowVisible, SendMessageW, SetForegroundWindow,
+    SetLayeredWindowAttributes, SetWindowLongPtrW, SetWindowPos, ShowWindow, GWL_EXSTYLE,
+    GWL_STYLE, HWND_NOTOPMOST, HWND_TOPMOST, LWA_ALPHA, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE,
+    SW_MAXIMIZE, SW_MINIMIZE, SW_RESTORE, WM_CLOSE, WS_CAPTION, WS_EX_DLGMODALFRAME, WS_EX_LAYERED,
+    WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
 };
 
+use super::{
+    AppIdentity, EventCallback, IconHandle, Monitor, MoveTarget, WindowAction, WindowBackend,
+    WindowCapabilities, WindowId, WindowType, Workspace, MOVE_UNSUPPORTED, WORKSPACES_UNSUPPORTED,
+};
+use crate::utils::capture;
+use crate::utils::display;
 use crate::utils::icon;
-use crate::utils::process::get_process_name;
-use crate::window_manager::{Window, WindowManager};
-
-pub fn focus_window(hwnd: isize) {
-    unsafe {
-        let hwnd = HWND(hwnd);
-
-        // Restore window if minimized
-        if IsIconic(hwnd).as_bool() {
-            match ShowWindow(hwnd, SW_RESTORE).as_bool() {
-                true => {}
-                false => println!("Failed to restore window"),
+use crate::utils::process::{self, ProcessInfo, Sandbox};
+use crate::utils::setup::setup_window_event_listener;
+use crate::window_manager::Window;
+
+pub struct WindowsBackend;
+
+/// State threaded through `EnumWindows` to `enum_window_proc`.
+struct EnumState {
+    current_pid: u32,
+    windows: Vec<Window>,
+}
+
+impl WindowBackend for WindowsBackend {
+    fn get_windows(&self, current_pid: u32) -> Vec<Window> {
+        let mut state = EnumState {
+            current_pid,
+            windows: Vec::new(),
+        };
+
+        unsafe {
+            // Fetch windows and call the callback function for each window
+            match EnumWindows(
+                Some(enum_window_proc),
+                LPARAM(&mut state as *mut EnumState as isize),
+            ) {
+                Ok(_) => (),
+                Err(e) => println!("Error: {}", e),
             }
         }
 
-        // Try to bring it to the foreground
-        match SetForegroundWindow(hwnd).as_bool() {
-            true => {}
-            false => println!("Failed to bring window to the foreground"),
+        state.windows
+    }
+
+    fn get_active_window(&self) -> Option<WindowId> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        Some(hwnd.0).filter(|&id| id != 0)
+    }
+
+    fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        unsafe {
+            let hwnd = HWND(id);
+
+            // Restore window if minimized
+            if IsIconic(hwnd).as_bool() && !ShowWindow(hwnd, SW_RESTORE).as_bool() {
+                println!("Failed to restore window");
+            }
+
+            // Try to bring it to the foreground
+            if !SetForegroundWindow(hwnd).as_bool() {
+                return Err("Failed to bring window to the foreground".to_string());
+            }
         }
+
+        Ok(())
     }
-}
 
-pub fn close_window(hwnd: isize) {
-    unsafe {
-        let hwnd = HWND(hwnd);
-        SendMessageW(hwnd, WM_CLOSE, None, None);
+    fn close_window(&self, id: WindowId) -> Result<(), String> {
+        unsafe {
+            SendMessageW(HWND(id), WM_CLOSE, None, None);
+        }
+
+        Ok(())
+    }
+
+    fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String> {
+        let hwnd = HWND(id);
+
+        unsafe {
+            match action {
+                // ShowWindow returns the previous visibility, not whether it worked
+                WindowAction::Minimize => {
+                    let _ = ShowWindow(hwnd, SW_MINIMIZE);
+                    Ok(())
+                }
+                WindowAction::Maximize => {
+                    let _ = ShowWindow(hwnd, SW_MAXIMIZE);
+                    Ok(())
+                }
+                WindowAction::Restore => {
+                    let _ = ShowWindow(hwnd, SW_RESTORE);
+                    Ok(())
+                }
+                WindowAction::ToggleAlwaysOnTop => {
+                    let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+                    let insert_after = if (ex_style & (WS_EX_TOPMOST.0 as isize)) != 0 {
+                        HWND_NOTOPMOST
+                    } else {
+                        HWND_TOPMOST
+                    };
+
+                    SetWindowPos(
+                        hwnd,
+                        insert_after,
+                        0,
+                        0,
+                        0,
+                        0,
+                        SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+                    )
+                    .map_err(|e| e.to_string())
+                }
+                WindowAction::SetOpacity(opacity) => {
+                    // Alpha only applies to layered windows
+                    let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+                    SetWindowLongPtrW(hwnd, GWL_EXSTYLE, ex_style | WS_EX_LAYERED.0 as isize);
+
+                    SetLayeredWindowAttributes(
+                        hwnd,
+                        COLORREF(0),
+                        (opacity * 255.0).round() as u8,
+                        LWA_ALPHA,
+                    )
+                    .map_err(|e| e.to_string())
+                }
+                _ => Err(action.unsupported()),
+            }
+        }
     }
-}
 
-pub fn get_windows(window_manager: &WindowManager) {
-    unsafe {
-        // Fetch windows and call the callback function for each window
-        match EnumWindows(
-            Some(enum_window_proc),
-            LPARAM(window_manager as *const _ as isize),
-        ) {
-            Ok(_) => (),
-            Err(e) => println!("Error: {}", e),
+    fn capabilities(&self) -> WindowCapabilities {
+        WindowCapabilities {
+            minimize: true,
+            maximize: true,
+            restore: true,
+            always_on_top: true,
+            opacity: true,
+            preview: true,
+            ..Default::default()
         }
     }
 
-    return;
+    fn get_move_targets(&self) -> Vec<MoveTarget> {
+        Vec::new()
+    }
+
+    fn move_window(&self, _id: WindowId, _target: &MoveTarget) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn summon_window(&self, _id: WindowId) -> Result<(), String> {
+        Err(MOVE_UNSUPPORTED.to_string())
+    }
+
+    fn get_workspaces(&self) -> Vec<Workspace> {
+        Vec::new()
+    }
+
+    fn switch_workspace(&self, _id: &str) -> Result<(), String> {
+        Err(WORKSPACES_UNSUPPORTED.to_string())
+    }
+
+    fn get_window_icon(&self, window: &Window, size: u32) -> Option<Vec<u8>> {
+        unsafe { icon::get_window_icon(HWND(window.id), size) }
+    }
+
+    fn get_icon_handle(&self, id: WindowId) -> Option<IconHandle> {
+        unsafe { icon::get_icon_handle(HWND(id)) }
+    }
+
+    fn capture_window(&self, id: WindowId) -> Result<RgbaImage, String> {
+        unsafe { capture::capture_window(HWND(id)) }
+    }
+
+    fn get_process_name(&self, process_id: u32) -> Option<String> {
+        unsafe { process::get_process_name(process_id) }
+    }
+
+    fn get_process_path(&self, process_id: u32) -> Option<String> {
+        unsafe { process::get_process_path(process_id) }
+    }
+
+    fn get_parent_process_id(&self, process_id: u32) -> Option<u32> {
+        unsafe { process::get_parent_process_id(process_id) }
+    }
+
+    fn get_process_info(&self, process_id: u32) -> Option<ProcessInfo> {
+        unsafe { process::get_process_info(process_id) }
+    }
+
+    fn get_sandbox(&self, _process_id: u32) -> Option<Sandbox> {
+        // Packaged apps run in AppContainers, but their executables already name them well
+        None
+    }
+
+    /// Executables describe themselves in their version resource, which is
+    /// what Task Manager shows too.
+    fn get_app_identity(&self, window: &Window) -> Option<AppIdentity> {
+        let app_name = unsafe { process::get_file_description(window.exe_path.as_deref()?) }?;
+
+        Some(AppIdentity {
+            app_name: Some(app_name),
+            ..Default::default()
+        })
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        display::get_monitors()
+    }
+
+    fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        setup_window_event_listener(callback)
+    }
 }
 
 unsafe extern "system" fn enum_window_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
-    let window_manager = &mut *(lparam.0 as *mut WindowManager);
+    let state = &mut *(lparam.0 as *mut EnumState);
 
     // if the window is the binocular process, skip it
     let mut process_id = 0u32;
 
     GetWindowThreadProcessId(hwnd, Some(&mut process_id));
 
-    if process_id == window_manager.current_pid {
+    if process_id == state.current_pid {
         return TRUE;
     }
 
@@ -71,31 +240,33 @@ unsafe extern "system" fn enum_window_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
     let style = GetWindowLongPtrW(hwnd, GWL_STYLE);
     let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
 
-    if (style & ((WS_VISIBLE | WS_CAPTION).0 as isize)) != ((WS_VISIBLE | WS_CAPTION).0 as isize) {
-        return TRUE;
-    }
-
-    if (ex_style & (WS_EX_TOOLWINDOW.0 as isize)) != 0 {
-        return TRUE;
-    }
+    let window_type = if (ex_style & (WS_EX_TOOLWINDOW.0 as isize)) != 0 {
+        WindowType::Utility
+    } else if (style & (WS_CAPTION.0 as isize)) != (WS_CAPTION.0 as isize) {
+        WindowType::Popup
+    } else if (ex_style & (WS_EX_DLGMODALFRAME.0 as isize)) != 0 {
+        WindowType::Dialog
+    } else {
+        WindowType::Normal
+    };
 
     // get the window title
     let mut title = [0u16; 512];
     let len = GetWindowTextW(hwnd, &mut title);
-    if len == 0 {
-        return TRUE;
-    }
+    let title = String::from_utf16_lossy(&title[..len.max(0) as usize]);
 
-    let title = String::from_utf16_lossy(&title[..len as usize]);
-    let icon_base64 = icon::get_window_icon(hwnd);
-    let process_name = get_process_name(process_id);
+    let mut class_name = [0u16; 256];
+    let len = GetClassNameW(hwnd, &mut class_name);
+    let class_name = (len > 0).then(|| String::from_utf16_lossy(&class_name[..len as usize]));
 
-    window_manager.windows.lock().unwrap().push(Window {
-        hwnd: hwnd.0,
+    state.windows.push(Window {
+        id: hwnd.0,
         title,
         process_id,
-        process_name,
-        icon_base64,
+        class_name,
+        window_type,
+        output: display::get_window_monitor(hwnd),
+        ..Default::default()
     });
 
     TRUE
@@ -1,38 +1,745 @@
-use crate::utils::icon;
-use crate::utils::process::get_process_name;
-use crate::window;
+use crate::config::Config;
+use crate::filter::WindowFilter;
+use crate::grouping::{self, GroupBy, WindowGroup};
+use crate::icon_cache::IconCache;
+use crate::preview_cache::PreviewCache;
+use crate::utils::icon::scale;
+use crate::utils::process::{ProcessInfo, Sandbox};
+use crate::window::{
+    EventCallback, Monitor, MonitorGeometry, MoveTarget, WindowAction, WindowBackend,
+    WindowCapabilities, WindowId, WindowType, Workspace, MOVE_UNSUPPORTED,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process;
 use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct Window {
-    pub hwnd: isize,
+    pub id: WindowId,
     pub title: String,
+    /// 0 when the window system doesn't tell which process owns the window.
     pub process_id: u32,
     pub process_name: Option<String>,
-    pub icon_base64: Option<String>,
+    pub exe_path: Option<String>,
+    /// Native window class, on window systems that have one.
+    pub class_name: Option<String>,
+    #[serde(default)]
+    pub window_type: WindowType,
+    /// Application id reported by the window system, e.g. the Wayland app_id,
+    /// or the desktop file id of its application when there is none.
+    pub app_id: Option<String>,
+    /// Human-friendly application name, e.g. "Google Chrome" rather than `chrome.exe`.
+    pub app_name: Option<String>,
+    /// Path of the application's `.desktop` file.
+    pub desktop_file: Option<String>,
+    /// The Flatpak, snap or container the process runs in, if any.
+    pub sandbox: Option<Sandbox>,
+    /// Served as PNG over the `binocular-icon` URI scheme.
+    pub icon_id: Option<String>,
+    /// The workspace the window is on, `None` for windows on every workspace
+    /// and on window systems without workspaces.
+    pub workspace_id: Option<String>,
+    pub workspace_name: Option<String>,
+    /// Whether the window is on a workspace shown right now, filled in by the manager.
+    #[serde(default)]
+    pub is_on_current_workspace: bool,
+    /// Name of the monitor the window is on, as in `Monitor::name`.
+    pub output: Option<String>,
+    /// Whether the window is on the monitor the switcher opens on, filled in by the manager.
+    #[serde(default)]
+    pub is_on_active_monitor: bool,
+    /// Layout details, only known to backends that can see the window manager's tree.
+    pub floating: Option<bool>,
+    #[serde(default)]
+    pub marks: Vec<String>,
+    /// Unix timestamp in milliseconds of the last time the window got focus.
+    pub last_focused: Option<u64>,
+    /// Only loaded once asked for through `get_process_info`.
+    pub process_info: Option<ProcessInfo>,
 }
 
-impl Window {
-    pub fn focus_window(&self) {
-        #[cfg(target_os = "windows")]
-        window::focus_window(self.hwnd);
-    }
+/// The known windows keyed by id, along with the order the backend listed them in.
+#[derive(Default, Clone)]
+struct WindowList {
+    order: Vec<WindowId>,
+    windows: HashMap<WindowId, Window>,
+    /// Windows the filter rules dropped, kept so their details aren't looked
+    /// up again on every refresh.
+    excluded: HashMap<WindowId, Window>,
+}
 
-    pub fn close_window(&self) {
-        #[cfg(target_os = "windows")]
-        window::close_window(self.hwnd);
-    }
+/// When each window was last focused, and which one has focus.
+#[derive(Default)]
+struct FocusHistory {
+    /// Milliseconds since the epoch, by window.
+    times: HashMap<WindowId, u64>,
+    /// The window that got focus last. Can be one we don't know about, like
+    /// the switcher itself, and is `None` once the window it names is gone.
+    focused: Option<WindowId>,
+}
+
+/// What changed between two refreshes of the window list.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct WindowListChanges {
+    pub added: Vec<Window>,
+    pub removed: Vec<WindowId>,
+    pub updated: Vec<Window>,
 }
+
 pub struct WindowManager {
-    pub windows: Mutex<Vec<Window>>,
+    windows: Mutex<WindowList>,
+    /// Held for the whole of a refresh, so refreshes don't overwrite each other.
+    refreshing: Mutex<()>,
+    focus_history: Mutex<FocusHistory>,
+    filter: Mutex<WindowFilter>,
+    icon_size: Mutex<u32>,
+    icons: IconCache,
+    previews: PreviewCache,
     pub current_pid: u32,
+    backend: Box<dyn WindowBackend>,
+}
+
+/// Preview dimensions `get_window_preview` accepts, in pixels.
+const PREVIEW_SIZES: std::ops::RangeInclusive<u32> = 16..=4096;
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
 }
 
 impl WindowManager {
-    pub fn refresh_window_list(&self) {
-        self.windows.lock().unwrap().clear();
+    /// The window with focus right now counts as the last one focused, so
+    /// Alt-Tab order holds before any focus change was seen.
+    pub fn new(backend: Box<dyn WindowBackend>) -> Self {
+        let manager = Self {
+            windows: Default::default(),
+            refreshing: Default::default(),
+            focus_history: Default::default(),
+            filter: Default::default(),
+            icon_size: Mutex::new(Config::default().icon_size),
+            icons: IconCache::default(),
+            previews: PreviewCache::default(),
+            current_pid: process::id(),
+            backend,
+        };
+
+        if let Some(id) = manager.backend.get_active_window() {
+            manager.record_focus(id);
+        }
+        manager
+    }
+
+    /// Re-enumerates the windows and reports what changed since the last refresh.
+    ///
+    /// Process details, sandboxes and application names are only looked up for windows
+    /// we haven't seen before, known windows keep the ones they already have. Icons come from the
+    /// cache, which only renders the ones that are new or changed. Windows the
+    /// filter rules exclude are left out of the list but kept in `excluded`
+    /// with their details, so they aren't looked up again on every refresh.
+    ///
+    /// The window list stays readable while the backend is asked, it's only
+    /// locked to take a copy and to put the new list in place.
+    pub fn refresh_window_list(&self) -> WindowListChanges {
+        let _refreshing = self.refreshing.lock().unwrap();
+
+        let enumerated = self.backend.get_windows(self.current_pid);
+        let workspaces = self.backend.get_workspaces();
+        let active_monitor = self
+            .backend
+            .get_monitors()
+            .into_iter()
+            .find(|monitor| monitor.active);
+        // A copy, so applying a config doesn't wait on the lookups below
+        let filter = self.filter.lock().unwrap().clone();
+        let icon_size = scale::snap_size(*self.icon_size.lock().unwrap());
+
+        let mut previous = self.windows.lock().unwrap().clone();
+        let mut changes = WindowListChanges::default();
+        let mut windows = HashMap::with_capacity(enumerated.len());
+        let mut excluded = HashMap::new();
+        let mut order = Vec::with_capacity(enumerated.len());
+
+        for mut window in enumerated {
+            let listed = previous.windows.remove(&window.id);
+            let known = listed
+                .clone()
+                .or_else(|| previous.excluded.remove(&window.id));
+            let process_id = Some(window.process_id).filter(|&process_id| process_id != 0);
+
+            if window.process_name.is_none() {
+                window.process_name = match &known {
+                    Some(known) => known.process_name.clone(),
+                    None => process_id.and_then(|pid| self.backend.get_process_name(pid)),
+                };
+            }
+            window.exe_path = match &known {
+                Some(known) => known.exe_path.clone(),
+                None => process_id.and_then(|pid| self.backend.get_process_path(pid)),
+            };
+            window.process_info = known.as_ref().and_then(|known| known.process_info.clone());
+
+            window.sandbox = match &known {
+                Some(known) => known.sandbox.clone(),
+                None => process_id.and_then(|pid| self.backend.get_sandbox(pid)),
+            };
+
+            match &known {
+                Some(known) => {
+                    window.app_id = window.app_id.take().or_else(|| known.app_id.clone());
+                    window.app_name = known.app_name.clone();
+                    window.desktop_file = known.desktop_file.clone();
+                }
+                None => {
+                    let identity = self.backend.get_app_identity(&window).unwrap_or_default();
+                    window.app_id = window.app_id.take().or(identity.app_id);
+                    window.app_name = identity.app_name;
+                    window.desktop_file = identity.desktop_file;
+                }
+            }
+
+            // Without workspaces to compare with every window counts as current
+            window.is_on_current_workspace = match &window.workspace_id {
+                Some(id) if !workspaces.is_empty() => workspaces
+                    .iter()
+                    .any(|workspace| workspace.visible && &workspace.id == id),
+                _ => true,
+            };
+            window.is_on_active_monitor = match (&window.output, &active_monitor) {
+                (Some(output), Some(monitor)) => *output == monitor.name,
+                _ => true,
+            };
+
+            if !filter.allows(&window) {
+                if listed.is_some() {
+                    changes.removed.push(window.id);
+                }
+                excluded.insert(window.id, window);
+                continue;
+            }
+
+            window.icon_id = self.icons.get(&*self.backend, &window, icon_size);
+
+            match listed {
+                Some(listed) => {
+                    if window != listed {
+                        changes.updated.push(window.clone());
+                    }
+                }
+                None => changes.added.push(window.clone()),
+            }
+
+            order.push(window.id);
+            windows.insert(window.id, window);
+        }
+
+        // Whatever is left over wasn't listed by the backend anymore
+        changes.removed.extend(previous.windows.keys().copied());
+
+        for id in &changes.removed {
+            self.icons.forget_window(*id);
+            self.previews.forget_window(*id);
+        }
+
+        {
+            let mut focus_history = self.focus_history.lock().unwrap();
+            focus_history.times.retain(|id, _| windows.contains_key(id));
+
+            // Left over are the windows that went away since the last refresh
+            focus_history.focused = focus_history.focused.filter(|id| {
+                !previous.windows.contains_key(id) && !previous.excluded.contains_key(id)
+            });
+        }
+
+        let mut list = self.windows.lock().unwrap();
+
+        // Process details may have been loaded while the backend was asked
+        for window in windows.values_mut() {
+            if window.process_info.is_none() {
+                window.process_info = list
+                    .windows
+                    .get(&window.id)
+                    .and_then(|listed| listed.process_info.clone());
+            }
+        }
+
+        *list = WindowList {
+            order,
+            windows,
+            excluded,
+        };
+        changes
+    }
+
+    /// Returns every known window, most recently used first.
+    ///
+    /// Like Alt-Tab, the currently focused window comes second so the top
+    /// entry is the window you were in before. That only applies while the
+    /// most recently used window still has focus, or while a window we don't
+    /// know about does, like the switcher. Windows that were never seen
+    /// focused keep the order the backend listed them in.
+    pub fn snapshot(&self) -> Vec<Window> {
+        let list = self.windows.lock().unwrap();
+        let focus_history = self.focus_history.lock().unwrap();
+
+        let mut windows: Vec<Window> = list
+            .order
+            .iter()
+            .filter_map(|id| list.windows.get(id))
+            .map(|window| Window {
+                last_focused: focus_history.times.get(&window.id).copied(),
+                ..window.clone()
+            })
+            .collect();
+
+        windows.sort_by_key(|window| std::cmp::Reverse(window.last_focused));
+
+        let top_has_focus = match (windows.first(), focus_history.focused) {
+            (Some(top), Some(focused)) if top.last_focused.is_some() => {
+                top.id == focused
+                    || (!list.windows.contains_key(&focused)
+                        && !list.excluded.contains_key(&focused))
+            }
+            _ => false,
+        };
+        if top_has_focus && windows.len() > 1 {
+            windows.swap(0, 1);
+        }
+
+        windows
+    }
+
+    /// The listed windows grouped, the group of the most recently used window first.
+    pub fn window_groups(&self, group_by: GroupBy) -> Vec<WindowGroup> {
+        grouping::group_windows(self.snapshot(), group_by, &*self.backend)
+    }
+
+    /// Renders every icon anew on the next refresh, e.g. after the icon theme changed.
+    pub fn forget_icons(&self) {
+        self.icons.clear();
+    }
+
+    /// Applies the filter rules and icon size, the rules take effect on the next refresh.
+    pub fn apply_config(&self, config: &Config) {
+        match WindowFilter::new(&config.filter) {
+            Ok(filter) => *self.filter.lock().unwrap() = filter,
+            Err(e) => println!("Error in filter rules: {e}"),
+        }
+
+        *self.icon_size.lock().unwrap() = config.icon_size;
+    }
+
+    /// Returns the PNG bytes of an icon, at the configured size unless `size` is given.
+    ///
+    /// Sizes are rounded up to a standard one, the webview scales the rest of the way.
+    pub fn get_icon_png(&self, id: &str, size: Option<u32>) -> Option<Vec<u8>> {
+        let size = size.unwrap_or_else(|| *self.icon_size.lock().unwrap());
+        self.icons.png(&*self.backend, id, scale::snap_size(size))
+    }
+
+    /// Returns a PNG thumbnail of a listed window, scaled to fit within `max_width` by `max_height`.
+    pub fn get_window_preview(
+        &self,
+        id: WindowId,
+        max_width: u32,
+        max_height: u32,
+    ) -> Result<Vec<u8>, String> {
+        if !PREVIEW_SIZES.contains(&max_width) || !PREVIEW_SIZES.contains(&max_height) {
+            return Err(format!(
+                "Preview size must be between {} and {} pixels",
+                PREVIEW_SIZES.start(),
+                PREVIEW_SIZES.end()
+            ));
+        }
+
+        // Only windows that made it through the filter, hidden ones stay hidden
+        if !self.windows.lock().unwrap().windows.contains_key(&id) {
+            return Err(format!("No window with id {id}"));
+        }
+
+        self.previews.get(&*self.backend, id, max_width, max_height)
+    }
+
+    /// Loads the process details of a listed window, fresh on every call, and
+    /// keeps them on the window.
+    pub fn get_process_info(&self, id: WindowId) -> Result<ProcessInfo, String> {
+        let process_id = self
+            .windows
+            .lock()
+            .unwrap()
+            .windows
+            .get(&id)
+            .map(|window| window.process_id)
+            .ok_or_else(|| format!("No window with id {id}"))?;
+        if process_id == 0 {
+            return Err("The window system doesn't tell which process owns the window".to_string());
+        }
+
+        let info = self
+            .backend
+            .get_process_info(process_id)
+            .ok_or_else(|| format!("Process {process_id} is gone"))?;
+
+        if let Some(window) = self.windows.lock().unwrap().windows.get_mut(&id) {
+            window.process_info = Some(info.clone());
+        }
+
+        Ok(info)
+    }
+
+    /// Records that a window got focus, from a backend event or our own command.
+    pub fn record_focus(&self, id: WindowId) {
+        let mut focus_history = self.focus_history.lock().unwrap();
+
+        // Focus changes within the same millisecond still keep their order
+        let latest = focus_history
+            .times
+            .values()
+            .max()
+            .copied()
+            .unwrap_or_default();
+        focus_history.times.insert(id, now_millis().max(latest + 1));
+        focus_history.focused = Some(id);
+    }
+
+    pub fn focus_window(&self, id: WindowId) -> Result<(), String> {
+        self.backend.focus_window(id)?;
+        self.record_focus(id);
+        Ok(())
+    }
+
+    pub fn close_window(&self, id: WindowId) -> Result<(), String> {
+        self.backend.close_window(id)
+    }
+
+    /// Carries out `action` if the window system can, opacity goes from 0 to 1.
+    pub fn window_action(&self, id: WindowId, action: WindowAction) -> Result<(), String> {
+        if !self.backend.capabilities().supports(action) {
+            return Err(action.unsupported());
+        }
+
+        if let WindowAction::SetOpacity(opacity) = action {
+            if !(0.0..=1.0).contains(&opacity) {
+                return Err(format!("Opacity must be between 0 and 1, got {opacity}"));
+            }
+        }
+
+        self.backend.window_action(id, action)
+    }
+
+    pub fn capabilities(&self) -> WindowCapabilities {
+        self.backend.capabilities()
+    }
+
+    /// The workspaces, each with how many of the listed windows are on it.
+    pub fn workspaces(&self) -> Vec<Workspace> {
+        let workspaces = self.backend.get_workspaces();
+        let list = self.windows.lock().unwrap();
+
+        workspaces
+            .into_iter()
+            .map(|workspace| Workspace {
+                window_count: list
+                    .windows
+                    .values()
+                    .filter(|window| window.workspace_id.as_ref() == Some(&workspace.id))
+                    .count(),
+                ..workspace
+            })
+            .collect()
+    }
+
+    pub fn switch_workspace(&self, id: &str) -> Result<(), String> {
+        self.backend.switch_workspace(id)
+    }
+
+    pub fn get_move_targets(&self) -> Vec<MoveTarget> {
+        self.backend.get_move_targets()
+    }
+
+    pub fn move_window(&self, id: WindowId, target: &MoveTarget) -> Result<(), String> {
+        self.backend.move_window(id, target)
+    }
+
+    /// Brings the window over to where the user is and focuses it.
+    pub fn summon_window(&self, id: WindowId) -> Result<(), String> {
+        if !self.backend.capabilities().summon {
+            return Err(MOVE_UNSUPPORTED.to_string());
+        }
+
+        self.backend.summon_window(id)?;
+        self.record_focus(id);
+        Ok(())
+    }
+
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.backend.get_monitors()
+    }
+
+    /// Geometry of the monitor holding the focused window.
+    pub fn get_active_monitor(&self) -> Option<MonitorGeometry> {
+        self.backend
+            .get_monitors()
+            .into_iter()
+            .find(|monitor| monitor.active)
+            .map(|monitor| monitor.geometry)
+    }
+
+    pub fn subscribe(&self, callback: EventCallback) -> Result<(), String> {
+        self.backend.subscribe(callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::process::SandboxKind;
+    use crate::window::{FakeBackend, WindowEvent};
+    use std::sync::mpsc;
+
+    /// The sample windows, from the newest: DevTools (5), GitHub (4), the
+    /// terminal (3), README.md (2) and the mail client (1).
+    fn manager() -> (WindowManager, FakeBackend) {
+        let backend = FakeBackend::with_sample_windows();
+        let manager = WindowManager::new(Box::new(backend.clone()));
+        manager.refresh_window_list();
+        (manager, backend)
+    }
+
+    fn ids(windows: &[Window]) -> Vec<WindowId> {
+        windows.iter().map(|window| window.id).collect()
+    }
+
+    #[test]
+    fn refresh_reports_what_changed() {
+        let backend = FakeBackend::with_sample_windows();
+        let manager = WindowManager::new(Box::new(backend.clone()));
+
+        let changes = manager.refresh_window_list();
+        assert_eq!(ids(&changes.added), [5, 4, 3, 2, 1]);
+        assert!(changes.removed.is_empty());
+
+        let calculator = backend.add_window("Calculator", 1006, "calc");
+        backend.close_window(1).unwrap();
+        backend
+            .move_window(2, &MoveTarget::Workspace("3".to_string()))
+            .unwrap();
+
+        let changes = manager.refresh_window_list();
+        assert_eq!(ids(&changes.added), [calculator]);
+        assert_eq!(changes.removed, [1]);
+        assert_eq!(ids(&changes.updated), [2]);
+
+        let changes = manager.refresh_window_list();
+        assert!(changes.added.is_empty() && changes.removed.is_empty());
+        assert!(changes.updated.is_empty());
+    }
+
+    #[test]
+    fn fills_in_process_and_application_details() {
+        let (manager, _) = manager();
+        let mail = manager
+            .snapshot()
+            .into_iter()
+            .find(|window| window.id == 1)
+            .unwrap();
+
+        assert_eq!(mail.process_name.as_deref(), Some("mail"));
+        assert_eq!(mail.exe_path.as_deref(), Some("/usr/bin/mail"));
+        assert_eq!(mail.app_id.as_deref(), Some("org.gnome.Evolution"));
+        assert_eq!(mail.app_name.as_deref(), Some("Evolution"));
+        assert_eq!(
+            mail.sandbox.map(|sandbox| sandbox.kind),
+            Some(SandboxKind::Flatpak)
+        );
+    }
+
+    #[test]
+    fn no_process_lookups_without_a_pid() {
+        let (manager, backend) = manager();
+        let id = backend.add_window("foot", 0, "foot");
+        manager.refresh_window_list();
+
+        let window = manager
+            .snapshot()
+            .into_iter()
+            .find(|window| window.id == id)
+            .unwrap();
+        assert_eq!(window.exe_path, None);
+        assert_eq!(window.sandbox, None);
+        assert!(manager.get_process_info(id).is_err());
+    }
+
+    #[test]
+    fn filter_rules_apply_on_the_next_refresh() {
+        let (manager, _) = manager();
+
+        let mut config = Config::default();
+        config.filter.exclude_processes.push("chrome".to_string());
+        manager.apply_config(&config);
+
+        let changes = manager.refresh_window_list();
+        let mut removed = changes.removed.clone();
+        removed.sort();
+        assert_eq!(removed, [4, 5]);
+        assert!(manager
+            .snapshot()
+            .iter()
+            .all(|window| window.process_name.as_deref() != Some("chrome")));
+    }
+
+    #[test]
+    fn excluded_windows_are_looked_up_once() {
+        let backend = FakeBackend::with_sample_windows();
+        let manager = WindowManager::new(Box::new(backend.clone()));
+        let mut config = Config::default();
+        config.filter.exclude_processes.push("chrome".to_string());
+        manager.apply_config(&config);
+
+        manager.refresh_window_list();
+        let lookups = backend.process_lookups();
+        manager.refresh_window_list();
+        assert_eq!(backend.process_lookups(), lookups);
+
+        // Let back in, they come with the details looked up before
+        manager.apply_config(&Config::default());
+        let changes = manager.refresh_window_list();
+        assert_eq!(ids(&changes.added), [5, 4]);
+        assert_eq!(
+            changes.added[0].exe_path.as_deref(),
+            Some("/usr/bin/chrome")
+        );
+        assert_eq!(backend.process_lookups(), lookups);
+    }
+
+    #[test]
+    fn alt_tab_order() {
+        let (manager, backend) = manager();
+
+        // DevTools is on top and has focus, the window before it comes first
+        assert_eq!(ids(&manager.snapshot()), [4, 5, 3, 2, 1]);
+
+        manager.focus_window(2).unwrap();
+        manager.focus_window(3).unwrap();
+        assert_eq!(ids(&manager.snapshot()), [2, 3, 5, 4, 1]);
+
+        // The switcher has focus while it's shown, that doesn't change a thing
+        manager.record_focus(999);
+        assert_eq!(ids(&manager.snapshot()), [2, 3, 5, 4, 1]);
+
+        // Once the focused window is gone the most recently used one is first
+        manager.focus_window(3).unwrap();
+        backend.close_window(3).unwrap();
+        manager.refresh_window_list();
+        assert_eq!(ids(&manager.snapshot()), [2, 5, 4, 1]);
+    }
+
+    #[test]
+    fn focusing_records_when() {
+        let (manager, backend) = manager();
+        let (sender, events) = mpsc::channel();
+        backend
+            .subscribe(Box::new(move |event| sender.send(event).unwrap()))
+            .unwrap();
+
+        manager.focus_window(3).unwrap();
+
+        assert_eq!(events.try_recv(), Ok(WindowEvent::Activated(3)));
+        let terminal = manager.snapshot().into_iter().find(|window| window.id == 3);
+        assert!(terminal.unwrap().last_focused.is_some());
+        assert!(manager.focus_window(42).is_err());
+    }
+
+    #[test]
+    fn window_actions_are_checked() {
+        let (manager, _) = manager();
+
+        assert!(manager
+            .window_action(3, WindowAction::SetOpacity(0.5))
+            .is_ok());
+        assert!(manager
+            .window_action(3, WindowAction::SetOpacity(1.5))
+            .is_err());
+        assert!(manager
+            .window_action(3, WindowAction::SetOpacity(-0.1))
+            .is_err());
+        assert!(manager.window_action(42, WindowAction::Minimize).is_err());
+    }
+
+    #[test]
+    fn previews_only_for_listed_windows() {
+        let (manager, _) = manager();
+
+        let png = manager.get_window_preview(3, 320, 200).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        assert!(manager.get_window_preview(42, 320, 200).is_err());
+        assert!(manager.get_window_preview(3, 8, 200).is_err());
+    }
+
+    #[test]
+    fn workspaces_count_their_windows() {
+        let (manager, _) = manager();
+
+        let counts: Vec<_> = manager
+            .workspaces()
+            .into_iter()
+            .map(|workspace| (workspace.id, workspace.visible, workspace.window_count))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("1".to_string(), true, 4),
+                ("2".to_string(), false, 1),
+                ("3".to_string(), false, 0),
+            ]
+        );
+
+        let devtools = manager.snapshot().into_iter().find(|window| window.id == 5);
+        assert!(!devtools.unwrap().is_on_current_workspace);
+    }
+
+    #[test]
+    fn summoning_counts_as_focusing() {
+        let (manager, _) = manager();
+
+        manager.summon_window(5).unwrap();
+        manager.refresh_window_list();
+
+        let devtools = manager
+            .snapshot()
+            .into_iter()
+            .find(|window| window.id == 5)
+            .unwrap();
+        assert!(devtools.is_on_current_workspace);
+        assert!(devtools.last_focused.is_some());
+    }
+
+    #[test]
+    fn windows_are_tagged_with_the_active_monitor() {
+        let backend = FakeBackend::with_sample_windows();
+        let manager = WindowManager::new(Box::new(backend.clone()));
+        // Minimized, it isn't on any output
+        backend.set_output(2, None);
+        manager.refresh_window_list();
+
+        let mut on_active_monitor: Vec<WindowId> = manager
+            .snapshot()
+            .iter()
+            .filter(|window| window.is_on_active_monitor)
+            .map(|window| window.id)
+            .collect();
+        on_active_monitor.sort();
+        // The terminal is on FAKE-2, the pointer on FAKE-1
+        assert_eq!(on_active_monitor, [1, 2, 4, 5]);
 
-        window::get_windows(self);
+        backend.set_output(3, Some("FAKE-1"));
+        manager.refresh_window_list();
+        assert!(manager
+            .snapshot()
+            .iter()
+            .all(|window| window.is_on_active_monitor));
     }
 }
@@ -1,15 +1,52 @@
-import { useCallback, useEffect, useRef, useState } from "react";
+import { Fragment, useCallback, useEffect, useRef, useState } from "react";
 import "./App.css";
 import { type UnlistenFn } from "@tauri-apps/api/event";
-import { Fzf } from "fzf";
 import { cn } from "./lib/utils";
 import { ChevronRight } from "lucide-react";
 import {
   addWindowsUpdatedListener,
+  addWindowAddedListener,
+  addWindowRemovedListener,
+  addWindowUpdatedListener,
+  addConfigChangedListener,
+  addConfigErrorListener,
+  addHotkeysChangedListener,
+  addOpenPickerListener,
+  getConfig,
+  getConfigError,
+  getHotkeys,
+  getWindowSnapshot,
+  getWindowGroups,
+  groupAction,
+  getWindowPreview,
+  getProcessInfo,
+  searchWindows,
   focusWindow,
   closeWindow,
+  windowAction,
+  getWindowCapabilities,
+  getMoveTargets,
+  moveWindow,
+  summonWindow,
+  getWorkspaces,
+  switchWorkspace,
+  getMonitors,
+  iconUrl,
 } from "./lib/tauri";
-import { NativeWindow } from "./lib/types";
+import {
+  HotkeyStatus,
+  Monitor,
+  MoveTarget,
+  NativeWindow,
+  Picker,
+  ProcessInfo,
+  SearchResult,
+  WindowAction,
+  WindowCapabilities,
+  WindowGroup,
+  Scope,
+  Workspace,
+} from "./lib/types";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 
 window.addEventListener("blur", async () => {
@@ -17,29 +54,283 @@ window.addEventListener("blur", async () => {
   await webview.hide();
 });
 
+function HighlightedText({
+  text,
+  positions = [],
+}: {
+  text: string;
+  positions?: number[];
+}) {
+  const matched = new Set(positions);
+
+  return (
+    <span className="overflow-hidden text-ellipsis">
+      {Array.from(text).map((char, index) =>
+        matched.has(index) ? (
+          <span key={index} className="text-yellow-300">
+            {char}
+          </span>
+        ) : (
+          char
+        )
+      )}
+    </span>
+  );
+}
+
+function ProcessDetails({ info }: { info: ProcessInfo }) {
+  const rows: [string, string | undefined][] = [
+    ["Executable", info.exe_path],
+    ["Command", info.command_line.join(" ") || undefined],
+    ["Directory", info.working_dir],
+    ["PID", `${info.pid}${info.parent_pid ? ` (parent ${info.parent_pid})` : ""}`],
+    ["User", info.user],
+    [
+      "Started",
+      info.start_time ? new Date(info.start_time).toLocaleString() : undefined,
+    ],
+    [
+      "Memory",
+      info.memory !== undefined
+        ? `${(info.memory / 1024 / 1024).toFixed(1)} MB`
+        : undefined,
+    ],
+    [
+      "CPU time",
+      info.cpu_time !== undefined
+        ? `${(info.cpu_time / 1000).toFixed(1)} s`
+        : undefined,
+    ],
+  ];
+
+  return (
+    <dl className="grid grid-cols-[auto_1fr] gap-x-2 text-xs text-gray-300">
+      {rows
+        .filter(([, value]) => value)
+        .map(([label, value]) => (
+          <Fragment key={label}>
+            <dt className="text-gray-500">{label}</dt>
+            <dd className="truncate" title={value}>
+              {value}
+            </dd>
+          </Fragment>
+        ))}
+    </dl>
+  );
+}
+
+const WINDOW_ACTIONS: [keyof WindowCapabilities, WindowAction, string][] = [
+  ["minimize", "minimize", "Minimize"],
+  ["maximize", "maximize", "Maximize"],
+  ["restore", "restore", "Restore"],
+  ["fullscreen", "toggle_fullscreen", "Fullscreen"],
+  ["always_on_top", "toggle_always_on_top", "On top"],
+  ["pin", "toggle_pin", "Pin"],
+  ["shade", "toggle_shade", "Shade"],
+];
+
+// Only offers what the window system can do
+function WindowActions({
+  window,
+  capabilities,
+}: {
+  window: NativeWindow;
+  capabilities: WindowCapabilities;
+}) {
+  // The window's current opacity isn't known, start out opaque
+  const [opacity, setOpacity] = useState(1);
+  const [moveTargets, setMoveTargets] = useState<MoveTarget[]>([]);
+
+  useEffect(() => {
+    getMoveTargets()
+      .then(setMoveTargets)
+      .catch((error) => console.error("Error loading move targets:", error));
+  }, []);
+
+  // Moving where the window already is does nothing
+  const otherTargets = moveTargets.filter((target) =>
+    "monitor" in target
+      ? target.monitor !== window.output
+      : target.workspace !== window.workspace_name
+  );
+
+  async function run(action: WindowAction) {
+    try {
+      await windowAction(window, action);
+    } catch (error) {
+      console.error("Error running window action:", error);
+    }
+  }
+
+  return (
+    <div className="flex flex-wrap items-center gap-1 text-xs text-gray-300">
+      {WINDOW_ACTIONS.filter(([capability]) => capabilities[capability]).map(
+        ([capability, action, label]) => (
+          <button
+            key={capability}
+            onClick={() => run(action)}
+            className="border border-gray-600 rounded px-1 hover:bg-slate-700"
+          >
+            {label}
+          </button>
+        )
+      )}
+      {capabilities.summon && (
+        <button
+          onClick={() =>
+            summonWindow(window).catch((error) =>
+              console.error("Error summoning window:", error)
+            )
+          }
+          className="border border-gray-600 rounded px-1 hover:bg-slate-700"
+        >
+          Summon
+        </button>
+      )}
+      {otherTargets.length > 0 && (
+        <select
+          value=""
+          onChange={(e) => {
+            const target = otherTargets[Number(e.target.value)];
+            moveWindow(window, target).catch((error) =>
+              console.error("Error moving window:", error)
+            );
+          }}
+          className="bg-slate-900 border border-gray-600 rounded"
+        >
+          <option value="" disabled>
+            Move to...
+          </option>
+          {otherTargets.map((target, index) => (
+            <option key={index} value={index}>
+              {"monitor" in target
+                ? `Monitor ${target.monitor}`
+                : `Workspace ${target.workspace}`}
+            </option>
+          ))}
+        </select>
+      )}
+      {capabilities.opacity && (
+        <label className="flex items-center gap-1">
+          Opacity
+          <input
+            type="range"
+            min={0.1}
+            max={1}
+            step={0.05}
+            value={opacity}
+            onChange={(e) => {
+              const value = Number(e.target.value);
+              setOpacity(value);
+              run({ set_opacity: value });
+            }}
+          />
+        </label>
+      )}
+    </div>
+  );
+}
+
+function WindowIcon({ iconId, size }: { iconId?: string; size: number }) {
+  return iconId ? (
+    <img
+      src={iconUrl(iconId, size)}
+      alt=""
+      width={size}
+      height={size}
+      className="flex-shrink-0"
+    />
+  ) : (
+    <div
+      style={{ width: size, height: size }}
+      className="bg-gray-600 flex-shrink-0"
+    />
+  );
+}
+
+function appKey(window: NativeWindow) {
+  return window.app_id ?? window.process_name;
+}
+
 function App() {
   const [windows, setWindows] = useState<NativeWindow[]>([]);
+  const [results, setResults] = useState<SearchResult[]>([]);
   const [search, setSearch] = useState("");
   const [selectedWindow, setSelectedWindow] = useState<number>(0);
+  const [configError, setConfigError] = useState<string | null>(null);
+  const [hotkeys, setHotkeys] = useState<HotkeyStatus[]>([]);
+  const [picker, setPicker] = useState<Picker>("windows");
+  const [iconSize, setIconSize] = useState(16);
+  const [preview, setPreview] = useState<string | null>(null);
+  const [processInfo, setProcessInfo] = useState<ProcessInfo | null>(null);
+  const [groups, setGroups] = useState<WindowGroup[]>([]);
+  const [capabilities, setCapabilities] = useState<WindowCapabilities | null>(
+    null
+  );
+  const [workspaces, setWorkspaces] = useState<Workspace[]>([]);
+  const [workspaceScope, setWorkspaceScope] = useState<Scope>("all");
+  const [monitors, setMonitors] = useState<Monitor[]>([]);
+  const [monitorScope, setMonitorScope] = useState<Scope>("all");
+  // What the scopes go back to whenever the switcher opens
+  const defaultWorkspaceScopeRef = useRef<Scope>("all");
+  const defaultMonitorScopeRef = useRef<Scope>("all");
 
   const searchInputRef = useRef<HTMLInputElement>(null);
+  const previewRef = useRef<HTMLDivElement>(null);
+
+  // The focused window is the one focused most recently
+  const focusedWindow = windows.reduce<NativeWindow | undefined>(
+    (focused, window) =>
+      (window.last_focused ?? 0) > (focused?.last_focused ?? 0)
+        ? window
+        : focused,
+    undefined
+  );
+
+  const scopedResults = results.filter(
+    (result) =>
+      (workspaceScope === "all" || result.window.is_on_current_workspace) &&
+      (monitorScope === "all" || result.window.is_on_active_monitor)
+  );
+
+  const pickerResults =
+    picker === "same_app" && focusedWindow
+      ? scopedResults.filter(
+          (result) => appKey(result.window) === appKey(focusedWindow)
+        )
+      : scopedResults;
 
-  const fzf = new Fzf(windows, {
-    selector: (item) => `${item.title} ${item.process_name}`,
-  });
+  const failedHotkeys = hotkeys.filter((hotkey) => hotkey.error);
 
-  const filteredWindows = fzf.find(search).map((item) => item.item);
+  const filteredWindows = pickerResults.map((result) => result.window);
+
+  // Searching or scoping the apps picker keeps the groups with a matching window
+  const visibleGroups =
+    search || workspaceScope === "current" || monitorScope === "current"
+      ? groups.filter((group) =>
+          group.windows.some((window) =>
+            filteredWindows.some((result) => result.id === window.id)
+          )
+        )
+      : groups;
+
+  const visibleWorkspaces = workspaces.filter((workspace) =>
+    workspace.name.toLowerCase().includes(search.toLowerCase())
+  );
+
+  const itemCount =
+    picker === "apps"
+      ? visibleGroups.length
+      : picker === "workspaces"
+        ? visibleWorkspaces.length
+        : filteredWindows.length;
 
   function getNextWindow() {
-    setSelectedWindow((prev) =>
-      prev < filteredWindows.length - 1 ? prev + 1 : 0
-    );
+    setSelectedWindow((prev) => (prev < itemCount - 1 ? prev + 1 : 0));
   }
 
   function getPreviousWindow() {
-    setSelectedWindow((prev) =>
-      prev > 0 ? prev - 1 : filteredWindows.length - 1
-    );
+    setSelectedWindow((prev) => (prev > 0 ? prev - 1 : itemCount - 1));
   }
 
   const handleKeyDown = useCallback(
@@ -62,10 +353,58 @@ function App() {
         return;
       }
 
+      if (e.ctrlKey && e.key === "d") {
+        e.preventDefault();
+        setWorkspaceScope((scope) => (scope === "all" ? "current" : "all"));
+        return;
+      }
+
+      if (e.ctrlKey && e.key === "o") {
+        e.preventDefault();
+        setMonitorScope((scope) => (scope === "all" ? "current" : "all"));
+        return;
+      }
+
+      if (picker === "workspaces") {
+        const workspace = visibleWorkspaces[selectedWindow];
+
+        if (e.key === "Enter") {
+          if (workspace) {
+            await switchWorkspace(workspace);
+          }
+          return;
+        }
+      }
+
+      if (picker === "apps") {
+        const group = visibleGroups[selectedWindow];
+
+        // Shift+Enter goes through the group's windows one after the other
+        if (e.key === "Enter") {
+          if (group) {
+            await groupAction(group, e.shiftKey ? "cycle" : "focus_recent");
+          }
+          return;
+        }
+
+        if (e.ctrlKey && e.key === "w") {
+          if (group) {
+            await groupAction(group, "close_all");
+            await getCurrentWebviewWindow().hide();
+          }
+          return;
+        }
+      }
+
       if (e.key === "Enter") {
         if (selectedWindow < filteredWindows.length) {
           const window = filteredWindows[selectedWindow];
-          await focusWindow(window);
+          // Alt+Enter brings the window over instead of going to it
+          if (e.altKey && capabilities?.summon) {
+            await summonWindow(window);
+          } else {
+            await focusWindow(window);
+          }
           return;
         }
       }
@@ -83,22 +422,64 @@ function App() {
         return;
       }
     },
-    [filteredWindows, selectedWindow]
+    [
+      filteredWindows,
+      visibleGroups,
+      visibleWorkspaces,
+      picker,
+      selectedWindow,
+      capabilities,
+    ]
   );
 
   useEffect(() => {
-    let unlisten: UnlistenFn;
+    const unlisteners: UnlistenFn[] = [];
 
     const setupListener = async () => {
       try {
-        unlisten = await addWindowsUpdatedListener((event) => {
-          if (searchInputRef.current) {
-            searchInputRef.current.focus();
-          }
-          setSelectedWindow(0);
-          setSearch("");
-          setWindows(event.payload);
-        });
+        unlisteners.push(
+          await addWindowsUpdatedListener((event) => {
+            if (searchInputRef.current) {
+              searchInputRef.current.focus();
+            }
+            setSelectedWindow(0);
+            setSearch("");
+            setWorkspaceScope(defaultWorkspaceScopeRef.current);
+            setMonitorScope(defaultMonitorScopeRef.current);
+            setWindows(event.payload);
+            // The switcher opened, monitors may have been plugged in since
+            getMonitors()
+              .then(setMonitors)
+              .catch((error) => console.error("Error loading monitors:", error));
+          })
+        );
+
+        // Background changes patch the list in place so the selection survives
+        unlisteners.push(
+          await addWindowAddedListener((event) => {
+            setWindows((prev) => [...event.payload, ...prev]);
+          })
+        );
+        unlisteners.push(
+          await addWindowRemovedListener((event) => {
+            setWindows((prev) =>
+              prev.filter((window) => !event.payload.includes(window.id))
+            );
+          })
+        );
+        unlisteners.push(
+          await addWindowUpdatedListener((event) => {
+            setWindows((prev) =>
+              prev.map(
+                (window) =>
+                  event.payload.find((updated) => updated.id === window.id) ??
+                  window
+              )
+            );
+          })
+        );
+
+        setWindows(await getWindowSnapshot());
       } catch (error) {
         console.error("Error setting up Tauri event listener:", error);
       }
@@ -107,51 +488,382 @@ function App() {
     setupListener();
 
     return () => {
-      if (unlisten) {
-        unlisten();
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, []);
+
+  // The config may have failed to load before the webview was listening
+  useEffect(() => {
+    const unlisteners: UnlistenFn[] = [];
+
+    const setupListener = async () => {
+      try {
+        setConfigError(await getConfigError());
+        const config = await getConfig();
+        setIconSize(config.icon_size);
+        defaultWorkspaceScopeRef.current = config.workspace_scope;
+        setWorkspaceScope(config.workspace_scope);
+        defaultMonitorScopeRef.current = config.monitor_scope;
+        setMonitorScope(config.monitor_scope);
+
+        unlisteners.push(
+          await addConfigChangedListener((event) => {
+            setConfigError(null);
+            setIconSize(event.payload.icon_size);
+            defaultWorkspaceScopeRef.current = event.payload.workspace_scope;
+            setWorkspaceScope(event.payload.workspace_scope);
+            defaultMonitorScopeRef.current = event.payload.monitor_scope;
+            setMonitorScope(event.payload.monitor_scope);
+          })
+        );
+        unlisteners.push(
+          await addConfigErrorListener((event) => setConfigError(event.payload))
+        );
+
+        setHotkeys(await getHotkeys());
+
+        unlisteners.push(
+          await addHotkeysChangedListener((event) => setHotkeys(event.payload))
+        );
+        unlisteners.push(
+          await addOpenPickerListener((event) => {
+            setPicker(event.payload);
+            setSelectedWindow(0);
+          })
+        );
+      } catch (error) {
+        console.error("Error setting up config listener:", error);
       }
     };
+
+    setupListener();
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten());
+    };
   }, []);
 
   useEffect(() => {
     setSelectedWindow(0);
   }, [search]);
 
+  // Groups are built in Rust from the same list, rebuild them when it changes
+  useEffect(() => {
+    if (picker !== "apps") {
+      return;
+    }
+
+    let cancelled = false;
+
+    getWindowGroups()
+      .then((groups) => {
+        if (!cancelled) {
+          setGroups(groups);
+        }
+      })
+      .catch((error) => console.error("Error grouping windows:", error));
+
+    return () => {
+      cancelled = true;
+    };
+  }, [picker, windows]);
+
+  // Window counts change with the list, reload them along with it
+  useEffect(() => {
+    if (picker !== "workspaces") {
+      return;
+    }
+
+    let cancelled = false;
+
+    getWorkspaces()
+      .then((workspaces) => {
+        if (!cancelled) {
+          setWorkspaces(workspaces);
+        }
+      })
+      .catch((error) => console.error("Error loading workspaces:", error));
+
+    return () => {
+      cancelled = true;
+    };
+  }, [picker, windows]);
+
+  // Ranking happens in Rust, re-run it whenever the query or the list changes
+  useEffect(() => {
+    let cancelled = false;
+
+    searchWindows(search)
+      .then((results) => {
+        if (!cancelled) {
+          setResults(results);
+        }
+      })
+      .catch((error) => console.error("Error searching windows:", error));
+
+    return () => {
+      cancelled = true;
+    };
+  }, [search, windows]);
+
+  const previewedWindow =
+    picker === "apps"
+      ? visibleGroups[selectedWindow]?.windows[0]
+      : picker === "workspaces"
+        ? windows.find(
+            (window) =>
+              window.workspace_id === visibleWorkspaces[selectedWindow]?.id
+          )
+        : filteredWindows[selectedWindow];
+
+  // Previews are cached briefly in Rust, refreshing keeps them live while the switcher is open
+  useEffect(() => {
+    if (!previewedWindow || capabilities?.preview === false) {
+      setPreview(null);
+      return;
+    }
+
+    let cancelled = false;
+    let shown: string | null = null;
+
+    const refresh = async () => {
+      const pane = previewRef.current;
+      if (!pane || document.visibilityState !== "visible") {
+        return;
+      }
+
+      try {
+        const url = await getWindowPreview(
+          previewedWindow,
+          Math.round(pane.clientWidth * window.devicePixelRatio),
+          Math.round(pane.clientHeight * window.devicePixelRatio)
+        );
+
+        if (cancelled) {
+          URL.revokeObjectURL(url);
+          return;
+        }
+
+        if (shown) {
+          URL.revokeObjectURL(shown);
+        }
+        shown = url;
+        setPreview(url);
+      } catch (error) {
+        if (!cancelled) {
+          setPreview(null);
+        }
+        console.error("Error capturing preview:", error);
+      }
+    };
+
+    refresh();
+    const interval = setInterval(refresh, 2000);
+
+    return () => {
+      cancelled = true;
+      clearInterval(interval);
+      if (shown) {
+        URL.revokeObjectURL(shown);
+      }
+    };
+  }, [previewedWindow?.id, capabilities?.preview]);
+
+  useEffect(() => {
+    getWindowCapabilities()
+      .then(setCapabilities)
+      .catch((error) => console.error("Error loading capabilities:", error));
+  }, []);
+
+  // Process details take a few system calls, only load them for the selected window
+  useEffect(() => {
+    setProcessInfo(previewedWindow?.process_info ?? null);
+    if (!previewedWindow) {
+      return;
+    }
+
+    let cancelled = false;
+
+    getProcessInfo(previewedWindow)
+      .then((info) => {
+        if (!cancelled) {
+          setProcessInfo(info);
+        }
+      })
+      .catch((error) => console.error("Error loading process info:", error));
+
+    return () => {
+      cancelled = true;
+    };
+  }, [previewedWindow?.id]);
+
   useEffect(() => {
     window.addEventListener("keydown", handleKeyDown);
     return () => window.removeEventListener("keydown", handleKeyDown);
-  }, [filteredWindows, selectedWindow]);
+  }, [
+    filteredWindows,
+    visibleGroups,
+    visibleWorkspaces,
+    picker,
+    selectedWindow,
+    capabilities,
+  ]);
 
   return (
     <div className="bg-slate-900 flex flex-col p-4 gap-2 h-screen w-screen">
+      {configError && (
+        <pre className="border border-red-500 text-red-300 text-xs p-2 whitespace-pre-wrap">
+          {configError}
+        </pre>
+      )}
+      {failedHotkeys.length > 0 && (
+        <pre className="border border-red-500 text-red-300 text-xs p-2 whitespace-pre-wrap">
+          {failedHotkeys
+            .map((hotkey) => `${hotkey.shortcut}: ${hotkey.error}`)
+            .join("\n")}
+        </pre>
+      )}
       <div className="flex-1 flex gap-2">
         <div className="border border-white flex-1 h-full w-1/2 p-2 flex flex-col-reverse">
-          {filteredWindows.map((window: NativeWindow, index: number) => {
-            const isSelected = index === selectedWindow;
-            return (
+          {picker === "apps" &&
+            visibleGroups.map((group, index) => (
               <button
-                key={window.hwnd}
+                key={group.key}
                 onClick={async () => {
-                  await focusWindow(window);
+                  await groupAction(group, "focus_recent");
                 }}
                 className={cn(
                   "text-white text-left whitespace-nowrap overflow-hidden text-ellipsis flex items-center gap-2 p-1",
-                  isSelected ? "bg-slate-700" : ""
+                  index === selectedWindow ? "bg-slate-700" : ""
                 )}
               >
-                {window.icon_base64 ? (
-                  <img
-                    src={window.icon_base64}
-                    alt=""
-                    className="w-4 h-4 flex-shrink-0"
-                  />
-                ) : (
-                  <div className="w-4 h-4 bg-gray-600 flex-shrink-0" />
+                <WindowIcon iconId={group.icon_id} size={iconSize} />
+                <span className="overflow-hidden text-ellipsis">
+                  {group.name}
+                </span>
+                <span className="text-xs text-gray-400">{group.count}</span>
+              </button>
+            ))}
+          {picker === "workspaces" &&
+            visibleWorkspaces.map((workspace, index) => (
+              <button
+                key={workspace.id}
+                onClick={async () => {
+                  await switchWorkspace(workspace);
+                }}
+                className={cn(
+                  "text-white text-left whitespace-nowrap overflow-hidden text-ellipsis flex items-center gap-2 p-1",
+                  index === selectedWindow ? "bg-slate-700" : ""
                 )}
-                {window.title}
+              >
+                <span
+                  className={cn(
+                    "overflow-hidden text-ellipsis",
+                    workspace.visible ? "text-yellow-300" : ""
+                  )}
+                >
+                  {workspace.name}
+                </span>
+                <span className="text-xs text-gray-400">
+                  {workspace.window_count}
+                </span>
               </button>
-            );
-          })}
+            ))}
+          {picker !== "apps" &&
+            picker !== "workspaces" &&
+            pickerResults.map(
+              ({ window, matches }: SearchResult, index: number) => {
+                const isSelected = index === selectedWindow;
+                return (
+                  <button
+                    key={window.id}
+                    onClick={async () => {
+                      await focusWindow(window);
+                    }}
+                    className={cn(
+                      "text-white text-left whitespace-nowrap overflow-hidden text-ellipsis flex items-center gap-2 p-1",
+                      isSelected ? "bg-slate-700" : ""
+                    )}
+                  >
+                    <WindowIcon iconId={window.icon_id} size={iconSize} />
+                    <HighlightedText text={window.title} positions={matches.title} />
+                    {window.app_name && (
+                      <span className="text-gray-400 text-sm">
+                        <HighlightedText
+                          text={window.app_name}
+                          positions={matches.app_name}
+                        />
+                      </span>
+                    )}
+                    {window.workspace_name && (
+                      <span
+                        className={cn(
+                          "text-xs",
+                          window.is_on_current_workspace
+                            ? "text-gray-500"
+                            : "text-gray-400"
+                        )}
+                      >
+                        <HighlightedText
+                          text={window.workspace_name}
+                          positions={matches.workspace_name}
+                        />
+                      </span>
+                    )}
+                    {monitors.length > 1 && window.output && (
+                      <span
+                        className={cn(
+                          "text-xs",
+                          window.is_on_active_monitor
+                            ? "text-gray-500"
+                            : "text-gray-400"
+                        )}
+                      >
+                        <HighlightedText
+                          text={window.output}
+                          positions={matches.output}
+                        />
+                      </span>
+                    )}
+                    {window.sandbox && (
+                      <span
+                        className="text-xs text-gray-400 border border-gray-600 rounded px-1"
+                        title={window.sandbox.app_id ?? window.sandbox.container}
+                      >
+                        <HighlightedText
+                          text={window.sandbox.kind}
+                          positions={matches.sandbox}
+                        />
+                      </span>
+                    )}
+                  </button>
+                );
+              }
+            )}
+        </div>
+        <div className="border border-white flex-1 h-full w-1/2 p-2 flex flex-col gap-2 overflow-hidden">
+          <div
+            ref={previewRef}
+            className="flex-1 min-h-0 flex items-center justify-center"
+          >
+            {preview ? (
+              <img
+                src={preview}
+                alt=""
+                className="max-w-full max-h-full object-contain"
+              />
+            ) : (
+              <span className="text-gray-500 text-sm">No preview</span>
+            )}
+          </div>
+          {previewedWindow && capabilities && (
+            <WindowActions
+              key={previewedWindow.id}
+              window={previewedWindow}
+              capabilities={capabilities}
+            />
+          )}
+          {processInfo && <ProcessDetails info={processInfo} />}
         </div>
       </div>
       <div className="flex border-white items-center border bg-slate-900 text-white p-2">
@@ -164,6 +876,30 @@ function App() {
           placeholder="Search..."
           className="focus:outline-none w-full bg-slate-900"
         />
+        {windows.some((window) => window.workspace_id) && (
+          <button
+            onClick={() =>
+              setWorkspaceScope((scope) =>
+                scope === "all" ? "current" : "all"
+              )
+            }
+            title="Ctrl+D"
+            className="text-xs text-gray-400 whitespace-nowrap"
+          >
+            {workspaceScope === "all" ? "All workspaces" : "This workspace"}
+          </button>
+        )}
+        {monitors.length > 1 && (
+          <button
+            onClick={() =>
+              setMonitorScope((scope) => (scope === "all" ? "current" : "all"))
+            }
+            title="Ctrl+O"
+            className="text-xs text-gray-400 whitespace-nowrap ml-2"
+          >
+            {monitorScope === "all" ? "All monitors" : "This monitor"}
+          </button>
+        )}
       </div>
     </div>
   );
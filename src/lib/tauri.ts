@@ -1,6 +1,30 @@
-import { invoke } from "@tauri-apps/api/core";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import { EventCallback, listen } from "@tauri-apps/api/event";
-import { NativeWindow } from "./types";
+import {
+  Config,
+  FrecencyEntry,
+  GroupAction,
+  GroupBy,
+  HotkeyStatus,
+  Monitor,
+  MoveTarget,
+  NativeWindow,
+  Picker,
+  ProcessInfo,
+  SearchField,
+  SearchResult,
+  WindowAction,
+  WindowCapabilities,
+  WindowGroup,
+  Workspace,
+} from "./types";
+
+// Icon urls are stable until the icon changes, so the webview only fetches each one once
+export function iconUrl(iconId: string, size: number) {
+  // Ask for device pixels so icons stay sharp on scaled displays
+  const pixels = Math.round(size * window.devicePixelRatio);
+  return `${convertFileSrc(iconId, "binocular-icon")}?size=${pixels}`;
+}
 
 export async function focusWindow(window: NativeWindow) {
   await invoke("focus_window", { window });
@@ -10,8 +34,135 @@ export async function closeWindow(window: NativeWindow) {
   await invoke("close_window", { window });
 }
 
+export async function windowAction(window: NativeWindow, action: WindowAction) {
+  await invoke("window_action", { id: window.id, action });
+}
+
+export async function getWindowCapabilities() {
+  return await invoke<WindowCapabilities>("get_window_capabilities");
+}
+
+export async function getMoveTargets() {
+  return await invoke<MoveTarget[]>("get_move_targets");
+}
+
+export async function moveWindow(window: NativeWindow, target: MoveTarget) {
+  await invoke("move_window", { id: window.id, target });
+}
+
+// Brings the window to the current workspace and the monitor under the pointer
+export async function summonWindow(window: NativeWindow) {
+  await invoke("summon_window", { window });
+}
+
+// Each with the number of listed windows on it
+export async function getWorkspaces() {
+  return await invoke<Workspace[]>("get_workspaces");
+}
+
+export async function switchWorkspace(workspace: Workspace) {
+  await invoke("switch_workspace", { id: workspace.id });
+}
+
+export async function getMonitors() {
+  return await invoke<Monitor[]>("get_monitors");
+}
+
+export async function getWindowSnapshot() {
+  return await invoke<NativeWindow[]>("get_window_snapshot");
+}
+
+export async function searchWindows(query: string, fields?: SearchField[]) {
+  return await invoke<SearchResult[]>("search_windows", { query, fields });
+}
+
+// Grouped as configured unless groupBy is given
+export async function getWindowGroups(groupBy?: GroupBy) {
+  return await invoke<WindowGroup[]>("get_window_groups", { groupBy });
+}
+
+export async function groupAction(
+  group: WindowGroup,
+  action: GroupAction,
+  groupBy?: GroupBy
+) {
+  await invoke("group_action", { key: group.key, groupBy, action });
+}
+
+// Returns an object url for the PNG, revoke it once it's no longer shown
+export async function getWindowPreview(
+  window: NativeWindow,
+  maxWidth: number,
+  maxHeight: number
+) {
+  const png = await invoke<ArrayBuffer>("get_window_preview", {
+    id: window.id,
+    maxWidth,
+    maxHeight,
+  });
+  return URL.createObjectURL(new Blob([png], { type: "image/png" }));
+}
+
+export async function getProcessInfo(window: NativeWindow) {
+  return await invoke<ProcessInfo>("get_process_info", { id: window.id });
+}
+
+export async function getFrecency() {
+  return await invoke<FrecencyEntry[]>("get_frecency");
+}
+
+export async function resetFrecency() {
+  await invoke("reset_frecency");
+}
+
+export async function getConfig() {
+  return await invoke<Config>("get_config");
+}
+
+export async function getConfigError() {
+  return await invoke<string | null>("get_config_error");
+}
+
+export async function getHotkeys() {
+  return await invoke<HotkeyStatus[]>("get_hotkeys");
+}
+
+export function addHotkeysChangedListener(
+  callback: EventCallback<HotkeyStatus[]>
+) {
+  return listen<HotkeyStatus[]>("hotkeys-changed", callback);
+}
+
+export function addOpenPickerListener(callback: EventCallback<Picker>) {
+  return listen<Picker>("open-picker", callback);
+}
+
+export function addConfigChangedListener(callback: EventCallback<Config>) {
+  return listen<Config>("config-changed", callback);
+}
+
+export function addConfigErrorListener(callback: EventCallback<string>) {
+  return listen<string>("config-error", callback);
+}
+
 export function addWindowsUpdatedListener(
   callback: EventCallback<NativeWindow[]>
 ) {
   return listen<NativeWindow[]>("windows-updated", callback);
 }
+
+export function addWindowAddedListener(
+  callback: EventCallback<NativeWindow[]>
+) {
+  return listen<NativeWindow[]>("window-added", callback);
+}
+
+export function addWindowRemovedListener(callback: EventCallback<number[]>) {
+  return listen<number[]>("window-removed", callback);
+}
+
+export function addWindowUpdatedListener(
+  callback: EventCallback<NativeWindow[]>
+) {
+  return listen<NativeWindow[]>("window-updated", callback);
+}
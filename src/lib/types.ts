@@ -1,7 +1,175 @@
+export type WindowType = "normal" | "dialog" | "utility" | "popup";
+
+export type SandboxKind =
+  | "flatpak"
+  | "snap"
+  | "toolbox"
+  | "distrobox"
+  | "container";
+
+export type Sandbox = {
+  kind: SandboxKind;
+  app_id?: string;
+  container?: string;
+};
+
+export type ProcessInfo = {
+  pid: number;
+  parent_pid?: number;
+  exe_path?: string;
+  command_line: string[];
+  working_dir?: string;
+  user?: string;
+  start_time?: number;
+  memory?: number;
+  cpu_time?: number;
+};
+
 export type NativeWindow = {
-  hwnd: number;
+  id: number;
   title: string;
   process_id: number;
   process_name?: string;
-  icon_base64?: string;
+  exe_path?: string;
+  class_name?: string;
+  window_type: WindowType;
+  app_id?: string;
+  app_name?: string;
+  desktop_file?: string;
+  sandbox?: Sandbox;
+  icon_id?: string;
+  workspace_id?: string;
+  workspace_name?: string;
+  is_on_current_workspace: boolean;
+  output?: string;
+  is_on_active_monitor: boolean;
+  floating?: boolean;
+  marks: string[];
+  last_focused?: number;
+  process_info?: ProcessInfo;
+};
+
+export type WindowAction =
+  | "minimize"
+  | "maximize"
+  | "restore"
+  | "toggle_fullscreen"
+  | "toggle_always_on_top"
+  | "toggle_pin"
+  | "toggle_shade"
+  | { set_opacity: number };
+
+export type WindowCapabilities = {
+  minimize: boolean;
+  maximize: boolean;
+  restore: boolean;
+  fullscreen: boolean;
+  always_on_top: boolean;
+  pin: boolean;
+  shade: boolean;
+  opacity: boolean;
+  summon: boolean;
+  preview: boolean;
+};
+
+export type MoveTarget = { monitor: string } | { workspace: string };
+
+export type SearchField =
+  | "title"
+  | "process_name"
+  | "app_name"
+  | "app_id"
+  | "workspace_name"
+  | "output"
+  | "sandbox"
+  | "sandbox_id";
+
+export type SearchResult = {
+  window: NativeWindow;
+  score: number;
+  matches: Partial<Record<SearchField, number[]>>;
+};
+
+export type FrecencyEntry = {
+  key: string;
+  score: number;
+  visits: number;
+  last_used: number;
+};
+
+export type Picker = "windows" | "same_app" | "apps" | "workspaces";
+
+export type Workspace = {
+  id: string;
+  name: string;
+  visible: boolean;
+  window_count: number;
+};
+
+// Whether the switcher lists every window or only the current workspace or monitor
+export type Scope = "all" | "current";
+
+export type MonitorGeometry = {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+};
+
+export type Monitor = {
+  name: string;
+  geometry: MonitorGeometry;
+  // The geometry minus panels and docks
+  work_area: MonitorGeometry;
+  scale: number;
+  primary: boolean;
+  active: boolean;
+};
+
+export type GroupBy = "app" | "process_tree";
+
+export type GroupAction = "focus_recent" | "cycle" | "close_all";
+
+export type WindowGroup = {
+  key: string;
+  name: string;
+  icon_id?: string;
+  count: number;
+  windows: NativeWindow[];
+};
+
+export type HotkeyAction =
+  | "toggle_switcher"
+  | "focus_previous"
+  | { open_picker: Picker }
+  | { run_command: string[] };
+
+export type HotkeyStatus = {
+  shortcut: string;
+  action: HotkeyAction;
+  error?: string;
+};
+
+export type FilterRule = {
+  action: "include" | "exclude";
+  process?: string;
+  exe_path?: string;
+  class?: string;
+  title?: string;
+  window_type?: WindowType;
+};
+
+export type Config = {
+  icon_size: number;
+  autostart: boolean;
+  remember_titles: boolean;
+  group_by: GroupBy;
+  workspace_scope: Scope;
+  monitor_scope: Scope;
+  hotkeys: Record<string, HotkeyAction>;
+  filter: {
+    exclude_processes: string[];
+    exclude_titles: string[];
+    rules: FilterRule[];
+  };
 };